@@ -0,0 +1,411 @@
+use std::{collections::BTreeMap, fs};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+/// Where the player's rebound controls are saved, next to the game data
+const CONTROLS_PATH: &str = "controls.ron";
+
+/// Everything the player can do with a key or mouse button.
+/// Systems read these through `ActionInput` instead of checking keys directly.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, EnumIter,
+)]
+pub enum InputAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Dash,
+    Attack,
+    UseItem,
+    Interact,
+    Inventory,
+    Stats,
+    RecipeBook,
+    CloseMenu,
+    QuickMove,
+    CompanionCommand,
+    Zoom,
+    Hotbar1,
+    Hotbar2,
+    Hotbar3,
+    Hotbar4,
+    Hotbar5,
+    Hotbar6,
+    HotbarNext,
+    HotbarPrev,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    MenuSelect,
+    MenuSplit,
+}
+
+pub const HOTBAR_ACTIONS: [InputAction; 6] = [
+    InputAction::Hotbar1,
+    InputAction::Hotbar2,
+    InputAction::Hotbar3,
+    InputAction::Hotbar4,
+    InputAction::Hotbar5,
+    InputAction::Hotbar6,
+];
+pub const MOVE_ACTIONS: [InputAction; 4] = [
+    InputAction::MoveUp,
+    InputAction::MoveDown,
+    InputAction::MoveLeft,
+    InputAction::MoveRight,
+];
+pub const MENU_NAV_ACTIONS: [InputAction; 4] = [
+    InputAction::MenuUp,
+    InputAction::MenuDown,
+    InputAction::MenuLeft,
+    InputAction::MenuRight,
+];
+
+/// How far a stick has to be pushed before it counts as input
+const STICK_DEAD_ZONE: f32 = 0.25;
+
+impl InputAction {
+    /// Short enough to fit a row of the controls screen
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            InputAction::MoveUp => "Up",
+            InputAction::MoveDown => "Down",
+            InputAction::MoveLeft => "Left",
+            InputAction::MoveRight => "Right",
+            InputAction::Dash => "Dash",
+            InputAction::Attack => "Attack",
+            InputAction::UseItem => "Use/Place",
+            InputAction::Interact => "Interact",
+            InputAction::Inventory => "Inventory",
+            InputAction::Stats => "Stats",
+            InputAction::RecipeBook => "Recipes",
+            InputAction::CloseMenu => "Close",
+            InputAction::QuickMove => "Quick Move",
+            InputAction::CompanionCommand => "Companion",
+            InputAction::Zoom => "Zoom",
+            InputAction::Hotbar1 => "Hotbar 1",
+            InputAction::Hotbar2 => "Hotbar 2",
+            InputAction::Hotbar3 => "Hotbar 3",
+            InputAction::Hotbar4 => "Hotbar 4",
+            InputAction::Hotbar5 => "Hotbar 5",
+            InputAction::Hotbar6 => "Hotbar 6",
+            InputAction::HotbarNext => "Next Slot",
+            InputAction::HotbarPrev => "Prev Slot",
+            InputAction::MenuUp => "Menu Up",
+            InputAction::MenuDown => "Menu Down",
+            InputAction::MenuLeft => "Menu Left",
+            InputAction::MenuRight => "Menu Right",
+            InputAction::MenuSelect => "Pick/Drop",
+            InputAction::MenuSplit => "Split",
+        }
+    }
+    /// Menu actions are only read while a menu is open,
+    /// so they can share buttons with gameplay actions
+    pub fn is_menu_action(&self) -> bool {
+        matches!(
+            self,
+            InputAction::CloseMenu
+                | InputAction::MenuUp
+                | InputAction::MenuDown
+                | InputAction::MenuLeft
+                | InputAction::MenuRight
+                | InputAction::MenuSelect
+                | InputAction::MenuSplit
+        )
+    }
+    fn get_default_bindings(&self) -> Vec<InputBinding> {
+        use GamepadButtonType as Pad;
+        use InputBinding::{Gamepad, Key, Mouse};
+        // movement on a gamepad is the left stick, which is read directly
+        match self {
+            InputAction::MoveUp => vec![Key(KeyCode::W)],
+            InputAction::MoveDown => vec![Key(KeyCode::S)],
+            InputAction::MoveLeft => vec![Key(KeyCode::A)],
+            InputAction::MoveRight => vec![Key(KeyCode::D)],
+            InputAction::Dash => vec![Key(KeyCode::Space), Gamepad(Pad::East)],
+            InputAction::Attack => vec![Mouse(MouseButton::Left), Gamepad(Pad::RightTrigger2)],
+            InputAction::UseItem => vec![Mouse(MouseButton::Right), Gamepad(Pad::LeftTrigger2)],
+            InputAction::Interact => vec![Key(KeyCode::F), Gamepad(Pad::West)],
+            InputAction::Inventory => vec![
+                Key(KeyCode::I),
+                Key(KeyCode::Tab),
+                Key(KeyCode::E),
+                Gamepad(Pad::North),
+            ],
+            InputAction::Stats => vec![Key(KeyCode::B), Gamepad(Pad::Select)],
+            InputAction::RecipeBook => vec![Key(KeyCode::R)],
+            InputAction::CloseMenu => vec![Key(KeyCode::Escape), Gamepad(Pad::East)],
+            InputAction::QuickMove => vec![Key(KeyCode::LShift), Gamepad(Pad::RightThumb)],
+            InputAction::CompanionCommand => vec![Key(KeyCode::G), Gamepad(Pad::LeftThumb)],
+            InputAction::Zoom => vec![Key(KeyCode::Z), Gamepad(Pad::DPadUp)],
+            InputAction::Hotbar1 => vec![Key(KeyCode::Key1)],
+            InputAction::Hotbar2 => vec![Key(KeyCode::Key2)],
+            InputAction::Hotbar3 => vec![Key(KeyCode::Key3)],
+            InputAction::Hotbar4 => vec![Key(KeyCode::Key4)],
+            InputAction::Hotbar5 => vec![Key(KeyCode::Key5)],
+            InputAction::Hotbar6 => vec![Key(KeyCode::Key6)],
+            InputAction::HotbarNext => vec![Gamepad(Pad::RightTrigger)],
+            InputAction::HotbarPrev => vec![Gamepad(Pad::LeftTrigger)],
+            InputAction::MenuUp => vec![Key(KeyCode::Up), Gamepad(Pad::DPadUp)],
+            InputAction::MenuDown => vec![Key(KeyCode::Down), Gamepad(Pad::DPadDown)],
+            InputAction::MenuLeft => vec![Key(KeyCode::Left), Gamepad(Pad::DPadLeft)],
+            InputAction::MenuRight => vec![Key(KeyCode::Right), Gamepad(Pad::DPadRight)],
+            InputAction::MenuSelect => vec![Key(KeyCode::Return), Gamepad(Pad::South)],
+            InputAction::MenuSplit => vec![Gamepad(Pad::West)],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Matches the button on any connected gamepad
+    Gamepad(GamepadButtonType),
+}
+
+impl InputBinding {
+    pub fn get_name(&self) -> String {
+        match self {
+            InputBinding::Key(key) => format!("{key:?}"),
+            InputBinding::Mouse(MouseButton::Left) => "LMB".to_string(),
+            InputBinding::Mouse(MouseButton::Right) => "RMB".to_string(),
+            InputBinding::Mouse(MouseButton::Middle) => "MMB".to_string(),
+            InputBinding::Mouse(MouseButton::Other(button)) => format!("Mouse{button}"),
+            InputBinding::Gamepad(button) => match button {
+                GamepadButtonType::South => "A".to_string(),
+                GamepadButtonType::East => "B".to_string(),
+                GamepadButtonType::West => "X".to_string(),
+                GamepadButtonType::North => "Y".to_string(),
+                GamepadButtonType::LeftTrigger => "LB".to_string(),
+                GamepadButtonType::RightTrigger => "RB".to_string(),
+                GamepadButtonType::LeftTrigger2 => "LT".to_string(),
+                GamepadButtonType::RightTrigger2 => "RT".to_string(),
+                GamepadButtonType::LeftThumb => "LS".to_string(),
+                GamepadButtonType::RightThumb => "RS".to_string(),
+                GamepadButtonType::DPadUp => "DUp".to_string(),
+                GamepadButtonType::DPadDown => "DDown".to_string(),
+                GamepadButtonType::DPadLeft => "DLeft".to_string(),
+                GamepadButtonType::DPadRight => "DRight".to_string(),
+                other => format!("{other:?}"),
+            },
+        }
+    }
+    pub fn is_gamepad(&self) -> bool {
+        matches!(self, InputBinding::Gamepad(_))
+    }
+    fn pressed(&self, devices: &InputDevices) -> bool {
+        match self {
+            InputBinding::Key(key) => devices.keys.pressed(*key),
+            InputBinding::Mouse(button) => devices.mouse.pressed(*button),
+            InputBinding::Gamepad(button) => devices
+                .gamepad
+                .get_pressed()
+                .any(|b| b.button_type == *button),
+        }
+    }
+    fn just_pressed(&self, devices: &InputDevices) -> bool {
+        match self {
+            InputBinding::Key(key) => devices.keys.just_pressed(*key),
+            InputBinding::Mouse(button) => devices.mouse.just_pressed(*button),
+            InputBinding::Gamepad(button) => devices
+                .gamepad
+                .get_just_pressed()
+                .any(|b| b.button_type == *button),
+        }
+    }
+    fn just_released(&self, devices: &InputDevices) -> bool {
+        match self {
+            InputBinding::Key(key) => devices.keys.just_released(*key),
+            InputBinding::Mouse(button) => devices.mouse.just_released(*button),
+            InputBinding::Gamepad(button) => devices
+                .gamepad
+                .get_just_released()
+                .any(|b| b.button_type == *button),
+        }
+    }
+}
+
+/// The button state of every device a binding can be on, for systems that
+/// need mutable access to one of them and so can't use `ActionInput`
+pub struct InputDevices<'a> {
+    pub keys: &'a Input<KeyCode>,
+    pub mouse: &'a Input<MouseButton>,
+    pub gamepad: &'a Input<GamepadButton>,
+}
+
+/// The keys and buttons bound to each action, loaded from and saved to controls.ron
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct ActionMap {
+    pub bindings: BTreeMap<InputAction, Vec<InputBinding>>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        Self {
+            bindings: InputAction::iter()
+                .map(|action| (action, action.get_default_bindings()))
+                .collect(),
+        }
+    }
+}
+
+impl ActionMap {
+    /// Falls back to the default bindings if there is no saved file, and fills in
+    /// actions, or a device's bindings, added since the file was saved
+    pub fn load() -> Self {
+        let Ok(controls_desc) = fs::read_to_string(CONTROLS_PATH) else {
+            return Self::default();
+        };
+        let mut action_map: Self = ron::de::from_str(&controls_desc).unwrap_or_else(|e| {
+            println!("Failed to load controls, using the defaults: {e}");
+            Self::default()
+        });
+        for action in InputAction::iter() {
+            let bindings = action_map.bindings.entry(action).or_default();
+            for is_gamepad in [false, true] {
+                if bindings.iter().all(|b| b.is_gamepad() != is_gamepad) {
+                    bindings.extend(
+                        action
+                            .get_default_bindings()
+                            .into_iter()
+                            .filter(|b| b.is_gamepad() == is_gamepad),
+                    );
+                }
+            }
+        }
+        action_map
+    }
+    pub fn save(&self) {
+        let controls_desc = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .expect("Could not serialize controls");
+        if let Err(e) = fs::write(CONTROLS_PATH, controls_desc) {
+            println!("Failed to save controls: {e}");
+        }
+    }
+    pub fn get_bindings(&self, action: InputAction) -> &[InputBinding] {
+        self.bindings.get(&action).map_or(&[], |b| b.as_slice())
+    }
+    /// The action already using this binding, other than `action` itself.
+    /// Menu and gameplay actions are never read at the same time, so they don't conflict.
+    pub fn get_conflict(&self, action: InputAction, binding: InputBinding) -> Option<InputAction> {
+        self.bindings
+            .iter()
+            .find(|(other, bindings)| {
+                **other != action
+                    && other.is_menu_action() == action.is_menu_action()
+                    && bindings.contains(&binding)
+            })
+            .map(|(other, _)| *other)
+    }
+    /// Binds the action to only this key or button on its device, keeping the bindings
+    /// on the other device. If another action was using it, the two swap so neither
+    /// is left unbound, and the other action is returned.
+    pub fn rebind(&mut self, action: InputAction, binding: InputBinding) -> Option<InputAction> {
+        let bindings = self.bindings.entry(action).or_default();
+        let old_bindings: Vec<InputBinding> = bindings
+            .iter()
+            .filter(|b| b.is_gamepad() == binding.is_gamepad())
+            .copied()
+            .collect();
+        bindings.retain(|b| b.is_gamepad() != binding.is_gamepad());
+        bindings.push(binding);
+        let conflict = self.get_conflict(action, binding)?;
+        let other_bindings = self.bindings.get_mut(&conflict).unwrap();
+        other_bindings.retain(|b| b != &binding);
+        if other_bindings
+            .iter()
+            .all(|b| b.is_gamepad() != binding.is_gamepad())
+        {
+            other_bindings.extend(old_bindings.iter().filter(|b| **b != binding).take(1));
+        }
+        Some(conflict)
+    }
+    pub fn pressed(&self, action: InputAction, devices: &InputDevices) -> bool {
+        self.get_bindings(action).iter().any(|b| b.pressed(devices))
+    }
+    pub fn just_pressed(&self, action: InputAction, devices: &InputDevices) -> bool {
+        self.get_bindings(action)
+            .iter()
+            .any(|b| b.just_pressed(devices))
+    }
+    pub fn just_released(&self, action: InputAction, devices: &InputDevices) -> bool {
+        self.get_bindings(action)
+            .iter()
+            .any(|b| b.just_released(devices))
+    }
+}
+
+/// Reads the keyboard, mouse and gamepads through the player's bindings
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    pub map: Res<'w, ActionMap>,
+    keys: Res<'w, Input<KeyCode>>,
+    mouse: Res<'w, Input<MouseButton>>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+}
+
+impl<'w> ActionInput<'w> {
+    fn devices(&self) -> InputDevices {
+        InputDevices {
+            keys: &self.keys,
+            mouse: &self.mouse,
+            gamepad: &self.gamepad_buttons,
+        }
+    }
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.map.pressed(action, &self.devices())
+    }
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.map.just_pressed(action, &self.devices())
+    }
+    pub fn just_released(&self, action: InputAction) -> bool {
+        self.map.just_released(action, &self.devices())
+    }
+    pub fn any_pressed(&self, actions: impl IntoIterator<Item = InputAction>) -> bool {
+        actions.into_iter().any(|action| self.pressed(action))
+    }
+    pub fn any_just_released(&self, actions: impl IntoIterator<Item = InputAction>) -> bool {
+        actions.into_iter().any(|action| self.just_released(action))
+    }
+    /// The left stick of the first connected gamepad, zero inside the dead zone
+    pub fn get_move_stick(&self) -> Vec2 {
+        self.get_stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY)
+    }
+    /// The right stick of the first connected gamepad, zero inside the dead zone
+    pub fn get_aim_stick(&self) -> Vec2 {
+        self.get_stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY)
+    }
+    fn get_stick(&self, x_axis: GamepadAxisType, y_axis: GamepadAxisType) -> Vec2 {
+        let Some(gamepad) = self.gamepads.iter().next() else {
+            return Vec2::ZERO;
+        };
+        let stick = Vec2::new(
+            self.gamepad_axes
+                .get(GamepadAxis::new(gamepad, x_axis))
+                .unwrap_or(0.),
+            self.gamepad_axes
+                .get(GamepadAxis::new(gamepad, y_axis))
+                .unwrap_or(0.),
+        );
+        if stick.length() < STICK_DEAD_ZONE {
+            Vec2::ZERO
+        } else {
+            stick
+        }
+    }
+}
+
+pub struct ActionMapPlugin;
+impl Plugin for ActionMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ActionMap::load());
+    }
+}
@@ -0,0 +1,382 @@
+use bevy::prelude::*;
+use bevy_proto::prelude::{ReflectSchematic, Schematic};
+use seldom_state::prelude::*;
+
+use crate::{
+    enemy::{
+        companion::{CompanionAttackState, GuardState},
+        raid::RaidState,
+        red_mushling::{GasAttackState, SproutingState, WaitingToSproutState},
+        CombatAlignment,
+    },
+    inputs::FacingDirection,
+    item::projectile::Projectile,
+};
+
+use super::{
+    AttackDistance, FleeDone, FleeState, FollowState, HasTarget, HurtByPlayer, IdleState,
+    LeapAttackState, LineOfSight, NightTimeAggro, ProjectileAttackState,
+};
+
+/// A state a mob can be in, with the parameters used to build it.
+/// Used as the target of a [MobTransition], and as a mob's starting state.
+#[derive(Reflect, FromReflect, Clone, Debug)]
+pub enum MobStateProto {
+    Idle {
+        walk_dir_change_time: f32,
+        speed: f32,
+    },
+    Follow {
+        speed: f32,
+    },
+    Flee {
+        speed: f32,
+        duration: f32,
+    },
+    LeapAttack {
+        startup: f32,
+        duration: f32,
+        cooldown: f32,
+        speed: f32,
+    },
+    ProjectileAttack {
+        startup: f32,
+        cooldown: f32,
+        projectile: Projectile,
+    },
+    WaitingToSprout,
+    Sprouting,
+    GasAttack,
+    CompanionAttack {
+        cooldown: f32,
+    },
+    Guard {
+        radius: f32,
+    },
+    Raid {
+        attack_cooldown: f32,
+    },
+}
+impl Default for MobStateProto {
+    fn default() -> Self {
+        Self::Idle {
+            walk_dir_change_time: 2.,
+            speed: 0.5,
+        }
+    }
+}
+
+/// The state a [MobTransition] is allowed to fire from
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MobStateType {
+    #[default]
+    Idle,
+    Follow,
+    Flee,
+    LeapAttack,
+    ProjectileAttack,
+    WaitingToSprout,
+    Sprouting,
+    GasAttack,
+    CompanionAttack,
+    Guard,
+    Raid,
+}
+
+/// Conditions that can move a mob between states. Ranges are in pixels,
+/// and checked against the player. `HasTarget` checks the mob's [MobTarget].
+#[derive(Reflect, FromReflect, Clone, Debug, Default)]
+pub enum MobTrigger {
+    LineOfSight(f32),
+    LostLineOfSight(f32),
+    #[default]
+    HurtByPlayer,
+    NightTimeAggro,
+    AttackDistance(f32),
+    OutOfAttackDistance(f32),
+    HasTarget,
+    LostTarget,
+    /// The mob's flee state has run for its whole duration
+    FleeDone,
+}
+
+#[derive(Reflect, FromReflect, Clone, Debug, Default)]
+pub struct MobTransition {
+    pub from: MobStateType,
+    pub trigger: MobTrigger,
+    pub to: MobStateProto,
+}
+
+/// Declares a mob's AI as a list of transitions, compiled into a
+/// [StateMachine] when the mob spawns.
+#[derive(Component, Reflect, FromReflect, Schematic, Default, Clone, Debug)]
+#[reflect(Component, Schematic)]
+pub struct MobBehaviour {
+    pub initial_state: Option<MobStateProto>,
+    pub transitions: Vec<MobTransition>,
+    /// Used instead of `transitions` when the mob is made [CombatAlignment::Hostile],
+    /// in dungeons and by combat shrines. Mobs without them keep their usual transitions.
+    pub hostile_transitions: Option<Vec<MobTransition>>,
+}
+
+// builds the state a [MobStateProto] describes as `$state`, then evaluates `$body` with it
+macro_rules! with_state {
+    ($proto:expr, $target:expr, |$state:ident| $body:expr) => {
+        match $proto {
+            MobStateProto::Idle {
+                walk_dir_change_time,
+                speed,
+            } => {
+                let $state = IdleState {
+                    walk_timer: Timer::from_seconds(*walk_dir_change_time, TimerMode::Repeating),
+                    direction: FacingDirection::new_rand_dir(rand::thread_rng()),
+                    speed: *speed,
+                    is_stopped: false,
+                };
+                $body
+            }
+            MobStateProto::Follow { speed } => {
+                let $state = FollowState {
+                    target: $target,
+                    speed: *speed,
+                };
+                $body
+            }
+            MobStateProto::Flee { speed, duration } => {
+                let $state = FleeState {
+                    target: $target,
+                    speed: *speed,
+                    flee_timer: Timer::from_seconds(*duration, TimerMode::Once),
+                };
+                $body
+            }
+            MobStateProto::LeapAttack {
+                startup,
+                duration,
+                cooldown,
+                speed,
+            } => {
+                let $state = LeapAttackState {
+                    target: $target,
+                    attack_startup_timer: Timer::from_seconds(*startup, TimerMode::Once),
+                    attack_duration_timer: Timer::from_seconds(*duration, TimerMode::Once),
+                    attack_cooldown_timer: Timer::from_seconds(*cooldown, TimerMode::Once),
+                    dir: None,
+                    speed: *speed,
+                };
+                $body
+            }
+            MobStateProto::ProjectileAttack {
+                startup,
+                cooldown,
+                projectile,
+            } => {
+                let $state = ProjectileAttackState {
+                    target: $target,
+                    attack_startup_timer: Timer::from_seconds(*startup, TimerMode::Once),
+                    attack_cooldown_timer: Timer::from_seconds(*cooldown, TimerMode::Once),
+                    dir: None,
+                    projectile: projectile.clone(),
+                };
+                $body
+            }
+            MobStateProto::WaitingToSprout => {
+                let $state = WaitingToSproutState;
+                $body
+            }
+            MobStateProto::Sprouting => {
+                let $state = SproutingState;
+                $body
+            }
+            MobStateProto::GasAttack => {
+                let $state = GasAttackState { hitbox: None };
+                $body
+            }
+            MobStateProto::CompanionAttack { cooldown } => {
+                let $state = CompanionAttackState {
+                    attack_cooldown_timer: Timer::from_seconds(*cooldown, TimerMode::Once),
+                };
+                $body
+            }
+            MobStateProto::Guard { radius } => {
+                let $state = GuardState { radius: *radius };
+                $body
+            }
+            MobStateProto::Raid { attack_cooldown } => {
+                let $state = RaidState {
+                    attack_timer: Timer::from_seconds(*attack_cooldown, TimerMode::Once),
+                };
+                $body
+            }
+        }
+    };
+}
+
+// expands to a `trans::<$from>` call for every possible target state
+macro_rules! trans_to_state {
+    ($sm:expr, $from:ty, $trigger:expr, $to:expr, $target:expr) => {
+        with_state!($to, $target, |state| $sm.trans::<$from>($trigger, state))
+    };
+}
+
+// expands to the concrete trigger type for a [MobTrigger]
+macro_rules! trans_with_trigger {
+    ($sm:expr, $from:ty, $transition:expr, $target:expr) => {
+        match &$transition.trigger {
+            MobTrigger::LineOfSight(range) => trans_to_state!(
+                $sm,
+                $from,
+                LineOfSight {
+                    target: $target,
+                    range: *range,
+                },
+                &$transition.to,
+                $target
+            ),
+            MobTrigger::LostLineOfSight(range) => trans_to_state!(
+                $sm,
+                $from,
+                Trigger::not(LineOfSight {
+                    target: $target,
+                    range: *range,
+                }),
+                &$transition.to,
+                $target
+            ),
+            MobTrigger::HurtByPlayer => {
+                trans_to_state!($sm, $from, HurtByPlayer, &$transition.to, $target)
+            }
+            MobTrigger::NightTimeAggro => {
+                trans_to_state!($sm, $from, NightTimeAggro, &$transition.to, $target)
+            }
+            MobTrigger::AttackDistance(range) => trans_to_state!(
+                $sm,
+                $from,
+                AttackDistance {
+                    target: $target,
+                    range: *range,
+                },
+                &$transition.to,
+                $target
+            ),
+            MobTrigger::OutOfAttackDistance(range) => trans_to_state!(
+                $sm,
+                $from,
+                Trigger::not(AttackDistance {
+                    target: $target,
+                    range: *range,
+                }),
+                &$transition.to,
+                $target
+            ),
+            MobTrigger::HasTarget => {
+                trans_to_state!($sm, $from, HasTarget, &$transition.to, $target)
+            }
+            MobTrigger::LostTarget => trans_to_state!(
+                $sm,
+                $from,
+                Trigger::not(HasTarget),
+                &$transition.to,
+                $target
+            ),
+            MobTrigger::FleeDone => {
+                trans_to_state!($sm, $from, FleeDone, &$transition.to, $target)
+            }
+        }
+    };
+}
+
+impl MobBehaviour {
+    fn get_transitions(&self, alignment: &CombatAlignment) -> &Vec<MobTransition> {
+        match (alignment, &self.hostile_transitions) {
+            (CombatAlignment::Hostile, Some(hostile_transitions)) => hostile_transitions,
+            _ => &self.transitions,
+        }
+    }
+
+    /// Compiles the declared transitions into a [StateMachine] targeting the given entity
+    pub fn build_state_machine(&self, target: Entity, alignment: &CombatAlignment) -> StateMachine {
+        let transitions = self.get_transitions(alignment);
+        let mut state_machine = StateMachine::default().set_trans_logging(false);
+        for transition in transitions.iter() {
+            state_machine = match transition.from {
+                MobStateType::Idle => {
+                    trans_with_trigger!(state_machine, IdleState, transition, target)
+                }
+                MobStateType::Follow => {
+                    trans_with_trigger!(state_machine, FollowState, transition, target)
+                }
+                MobStateType::Flee => {
+                    trans_with_trigger!(state_machine, FleeState, transition, target)
+                }
+                MobStateType::LeapAttack => {
+                    trans_with_trigger!(state_machine, LeapAttackState, transition, target)
+                }
+                MobStateType::ProjectileAttack => {
+                    trans_with_trigger!(state_machine, ProjectileAttackState, transition, target)
+                }
+                MobStateType::WaitingToSprout => {
+                    trans_with_trigger!(state_machine, WaitingToSproutState, transition, target)
+                }
+                MobStateType::Sprouting => {
+                    trans_with_trigger!(state_machine, SproutingState, transition, target)
+                }
+                MobStateType::GasAttack => {
+                    trans_with_trigger!(state_machine, GasAttackState, transition, target)
+                }
+                MobStateType::CompanionAttack => {
+                    trans_with_trigger!(state_machine, CompanionAttackState, transition, target)
+                }
+                MobStateType::Guard => {
+                    trans_with_trigger!(state_machine, GuardState, transition, target)
+                }
+                MobStateType::Raid => {
+                    trans_with_trigger!(state_machine, RaidState, transition, target)
+                }
+            };
+        }
+        // the sprout and gas attack systems move between these states themselves,
+        // so the machine needs to know about them even without a transition
+        if self.uses_state(transitions, MobStateType::Sprouting) {
+            state_machine = state_machine
+                .with_state::<GasAttackState>()
+                .with_state::<WaitingToSproutState>();
+        }
+
+        state_machine
+    }
+
+    fn uses_state(&self, transitions: &[MobTransition], state: MobStateType) -> bool {
+        self.initial_state
+            .as_ref()
+            .map(|s| s.get_type() == state)
+            .unwrap_or(false)
+            || transitions
+                .iter()
+                .any(|t| t.from == state || t.to.get_type() == state)
+    }
+}
+
+impl MobStateProto {
+    pub fn get_type(&self) -> MobStateType {
+        match self {
+            MobStateProto::Idle { .. } => MobStateType::Idle,
+            MobStateProto::Follow { .. } => MobStateType::Follow,
+            MobStateProto::Flee { .. } => MobStateType::Flee,
+            MobStateProto::LeapAttack { .. } => MobStateType::LeapAttack,
+            MobStateProto::ProjectileAttack { .. } => MobStateType::ProjectileAttack,
+            MobStateProto::WaitingToSprout => MobStateType::WaitingToSprout,
+            MobStateProto::Sprouting => MobStateType::Sprouting,
+            MobStateProto::GasAttack => MobStateType::GasAttack,
+            MobStateProto::CompanionAttack { .. } => MobStateType::CompanionAttack,
+            MobStateProto::Guard { .. } => MobStateType::Guard,
+            MobStateProto::Raid { .. } => MobStateType::Raid,
+        }
+    }
+    /// Inserts this state directly on a mob, used for its starting state
+    pub fn insert_state(&self, e_cmds: &mut bevy::ecs::system::EntityCommands, target: Entity) {
+        with_state!(self, target, |state| {
+            e_cmds.insert(state);
+        })
+    }
+}
@@ -89,6 +89,40 @@ impl BoolTrigger for HurtByPlayer {
         return false;
     }
 }
+
+/// What a mob that picks its own fights is going after. Companions and raiders
+/// pick these as they go, since their target isn't known when the state machine is built.
+#[derive(Component, Default, Debug)]
+pub struct MobTarget(pub Option<Entity>);
+
+// This trigger checks if the mob has picked something to go after
+#[derive(Clone, Copy, Reflect)]
+pub struct HasTarget;
+
+impl BoolTrigger for HasTarget {
+    type Param<'w, 's> = Query<'w, 's, &'static MobTarget>;
+
+    fn trigger(&self, entity: Entity, targets: Self::Param<'_, '_>) -> bool {
+        targets
+            .get(entity)
+            .map(|target| target.0.is_some())
+            .unwrap_or(false)
+    }
+}
+// This trigger checks if the mob has run away for as long as its flee state lasts
+#[derive(Clone, Copy, Reflect)]
+pub struct FleeDone;
+
+impl BoolTrigger for FleeDone {
+    type Param<'w, 's> = Query<'w, 's, &'static FleeState>;
+
+    fn trigger(&self, entity: Entity, flees: Self::Param<'_, '_>) -> bool {
+        flees
+            .get(entity)
+            .map(|flee| flee.flee_timer.finished())
+            .unwrap_or(false)
+    }
+}
 // This trigger checks if the enemy is within the the given range of the target
 #[derive(Clone, Copy, Reflect)]
 pub struct AttackDistance {
@@ -144,6 +178,14 @@ pub struct FollowState {
     pub target: Entity,
     pub speed: f32,
 }
+// Entities in the `Flee` state should move away from the given entity until the timer is done
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct FleeState {
+    pub target: Entity,
+    pub speed: f32,
+    pub flee_timer: Timer,
+}
 // Entities in the `Attack` state should move towards the given entity at the given speed
 #[derive(Clone, Component, Reflect)]
 #[component(storage = "SparseSet")]
@@ -214,6 +256,35 @@ pub fn follow(
     }
 }
 
+/// Runs away until the flee timer is done, then the mob's `FleeDone` transition takes over
+pub fn flee(
+    transforms: Query<&Transform>,
+    mut mover: Query<&mut KinematicCharacterController>,
+    mut flees: Query<(Entity, &mut FleeState)>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (entity, mut flee) in flees.iter_mut() {
+        flee.flee_timer.tick(time.delta());
+        if flee.flee_timer.finished() {
+            continue;
+        }
+        let (Ok(target_t), Ok(flee_t)) = (transforms.get(flee.target), transforms.get(entity)) else {
+            continue;
+        };
+        // run directly away from the target
+        let delta = (flee_t.translation - target_t.translation)
+            .normalize_or_zero()
+            .truncate();
+        if let Ok(mut kcc) = mover.get_mut(entity) {
+            kcc.translation = Some(delta * flee.speed * PLAYER_MOVE_SPEED * time.delta_seconds());
+        }
+        commands
+            .entity(entity)
+            .insert(FacingDirection::from_translation(delta));
+    }
+}
+
 pub fn leap_attack(
     mut transforms: Query<&mut Transform>,
     mut attacks: Query<(
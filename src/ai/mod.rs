@@ -1,3 +1,4 @@
+mod behaviour;
 mod enemy_hostile_basic;
 
 use crate::{
@@ -6,6 +7,7 @@ use crate::{
 };
 
 use bevy::prelude::*;
+pub use behaviour::*;
 pub use enemy_hostile_basic::*;
 use seldom_state::StateMachinePlugin;
 
@@ -16,6 +18,7 @@ impl Plugin for AIPlugin {
         app.add_plugin(StateMachinePlugin).add_systems(
             (
                 follow,
+                flee,
                 leap_attack,
                 gas_attack,
                 sprout,
@@ -3,16 +3,16 @@ use std::fs;
 use bevy::{prelude::*, render::view::RenderLayers};
 
 use crate::{
-    client::GameOverEvent,
+    client::{get_data_path, GameOverEvent, SAVE_PATH},
     colors::overwrite_alpha,
     container::ContainerRegistry,
-    enemy::Mob,
+    enemy::{passive::TamedMobRegistry, Mob},
     item::CraftingTracker,
     night::NightTracker,
     player::Player,
-    ui::{screen_effects::HealthScreenEffect, ChestContainer, FurnaceContainer},
+    ui::{screen_effects::HealthScreenEffect, ChestContainer, FurnaceContainer, ScreenOverlay},
     world::{chunk::Chunk, dimension::ActiveDimension, generation::WorldObjectCache},
-    Game, GameState, GAME_HEIGHT, GAME_WIDTH,
+    Game, GameState,
 };
 
 #[derive(Component)]
@@ -28,7 +28,6 @@ pub fn handle_game_over_fadeout(
             .spawn(SpriteBundle {
                 sprite: Sprite {
                     color: Color::rgba(0., 0., 0., 0.),
-                    custom_size: Some(Vec2::new(GAME_WIDTH + 10., GAME_HEIGHT + 10.)),
                     ..default()
                 },
                 transform: Transform {
@@ -39,6 +38,7 @@ pub fn handle_game_over_fadeout(
                 ..default()
             })
             .insert(RenderLayers::from_layers(&[3]))
+            .insert(ScreenOverlay::new(10.))
             .insert(Name::new("overlay"))
             .insert(GameOverFadeout(Timer::from_seconds(2.0, TimerMode::Once)));
 
@@ -46,42 +46,49 @@ pub fn handle_game_over_fadeout(
     }
 }
 
+/// Everything spawned for a run, despawned when it ends
+pub type RunEntityFilter = Or<(
+    With<Mob>,
+    With<Chunk>,
+    With<Sprite>,
+    With<Player>,
+    With<Text>,
+    With<ActiveDimension>,
+    With<HealthScreenEffect>,
+)>;
+
+/// Despawns the run's entities and removes the resources holding `Entity` refs to them,
+/// so the next run started from the main menu begins with a clean world
+pub fn teardown_run(commands: &mut Commands, everything: &Query<Entity, RunEntityFilter>) {
+    for e in everything.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    commands.remove_resource::<ChestContainer>();
+    commands.remove_resource::<FurnaceContainer>();
+    commands.remove_resource::<Game>();
+    commands.remove_resource::<NightTracker>();
+    commands.remove_resource::<ContainerRegistry>();
+    commands.remove_resource::<CraftingTracker>();
+    commands.remove_resource::<WorldObjectCache>();
+    // loading a save replaces this, a new world starts with no tamed mobs stored
+    commands.insert_resource(TamedMobRegistry::default());
+}
+
 pub fn tick_game_over_overlay(
     mut commands: Commands,
     time: Res<Time>,
     mut query: Query<(Entity, &mut GameOverFadeout, &mut Sprite)>,
-    everything: Query<
-        Entity,
-        Or<(
-            With<Mob>,
-            With<Chunk>,
-            With<Sprite>,
-            With<Player>,
-            With<Text>,
-            With<ActiveDimension>,
-            With<HealthScreenEffect>,
-        )>,
-    >,
+    everything: Query<Entity, RunEntityFilter>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     for (e, mut timer, mut sprite) in query.iter_mut() {
         timer.0.tick(time.delta());
         if timer.0.finished() {
             println!("Despawning everything, Sending to main menu");
-            for e in everything.iter() {
-                commands.entity(e).despawn_recursive();
-            }
+            teardown_run(&mut commands, &everything);
             commands.entity(e).despawn();
-            let _ = fs::remove_file("save_state.json");
+            let _ = fs::remove_file(get_data_path(SAVE_PATH));
             next_state.0 = Some(GameState::MainMenu);
-            //cleanup resources with Entity refs
-            commands.remove_resource::<ChestContainer>();
-            commands.remove_resource::<FurnaceContainer>();
-            commands.remove_resource::<Game>();
-            commands.remove_resource::<NightTracker>();
-            commands.remove_resource::<ContainerRegistry>();
-            commands.remove_resource::<CraftingTracker>();
-            commands.remove_resource::<WorldObjectCache>();
         } else {
             println!("Setting overlay to {:?}", timer.0.percent());
             sprite.color = overwrite_alpha(sprite.color, timer.0.percent());
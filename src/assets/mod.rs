@@ -11,10 +11,14 @@ use bevy_proto::prelude::{ReflectSchematic, Schematic};
 use serde::Deserialize;
 use strum::IntoEnumIterator;
 
+use crate::attributes::affixes::AffixPools;
+use crate::attributes::item_abilities::ItemAbilityDefs;
+use crate::attributes::gems::Gems;
+use crate::attributes::set_bonuses::EquipmentSets;
 use crate::enemy::Mob;
 use crate::item::{
-    CraftingTracker, Equipment, Foliage, FurnaceRecipeList, RecipeList, RecipeListProto, Recipes,
-    Wall, WorldObject, WorldObjectResource,
+    CraftingTracker, Equipment, Foliage, RecipeListProto, Recipes, Wall, WorldObject,
+    WorldObjectResource,
 };
 use crate::ui::UIElement;
 use crate::GameParam;
@@ -81,16 +85,7 @@ pub struct GraphicsDesc {
 impl Plugin for GameAssetsPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(Material2dPlugin::<FoliageMaterial>::default())
-            .insert_resource(Graphics {
-                texture_atlas: None,
-                wall_texture_atlas: None,
-                spritesheet_map: None,
-                icons: None,
-                foliage_material_map: None,
-                ui_image_handles: None,
-                player_spritesheets: None,
-                mob_spritesheets: None,
-            })
+            .init_resource::<Graphics>()
             .add_system(Self::update_graphics.in_set(OnUpdate(GameState::Main)))
             .add_system(Self::load_graphics.in_schedule(OnExit(GameState::Loading)));
     }
@@ -132,7 +127,7 @@ pub struct FoliageMaterial {
     #[sampler(11)]
     pub source_texture: Option<Handle<Image>>,
 }
-#[derive(Resource)]
+#[derive(Resource, Default)]
 
 pub struct Graphics {
     pub texture_atlas: Option<Handle<TextureAtlas>>,
@@ -203,6 +198,10 @@ impl GameAssetsPlugin {
     pub fn load_graphics(
         mut graphics: ResMut<Graphics>,
         mut recipes: ResMut<Recipes>,
+        mut affix_pools: ResMut<AffixPools>,
+        mut ability_defs: ResMut<ItemAbilityDefs>,
+        mut equipment_sets: ResMut<EquipmentSets>,
+        mut gems: ResMut<Gems>,
         sprite_sheet: Res<ImageAssets>,
         mut texture_assets: ResMut<Assets<TextureAtlas>>,
         mut world_obj_data: ResMut<WorldObjectResource>,
@@ -228,6 +227,10 @@ impl GameAssetsPlugin {
 
         let sprite_desc = fs::read_to_string("./assets/textures/sprites_desc.ron").unwrap();
         let recipe_desc = fs::read_to_string("./assets/recipes/recipes.ron").unwrap();
+        let affix_desc = fs::read_to_string("./assets/items/affixes.ron").unwrap();
+        let ability_desc = fs::read_to_string("./assets/items/abilities.ron").unwrap();
+        let sets_desc = fs::read_to_string("./assets/items/sets.ron").unwrap();
+        let gems_desc = fs::read_to_string("./assets/items/gems.ron").unwrap();
 
         let sprite_desc: GraphicsDesc = from_str(&sprite_desc).unwrap_or_else(|e| {
             println!("Failed to load config for graphics: {e}");
@@ -237,6 +240,22 @@ impl GameAssetsPlugin {
             println!("Failed to load config for recipes: {e}");
             std::process::exit(1);
         });
+        *affix_pools = from_str(&affix_desc).unwrap_or_else(|e| {
+            println!("Failed to load config for affixes: {e}");
+            std::process::exit(1);
+        });
+        *ability_defs = from_str(&ability_desc).unwrap_or_else(|e| {
+            println!("Failed to load config for item abilities: {e}");
+            std::process::exit(1);
+        });
+        *equipment_sets = from_str(&sets_desc).unwrap_or_else(|e| {
+            println!("Failed to load config for equipment sets: {e}");
+            std::process::exit(1);
+        });
+        *gems = from_str(&gems_desc).unwrap_or_else(|e| {
+            println!("Failed to load config for gems: {e}");
+            std::process::exit(1);
+        });
 
         let mut atlas = TextureAtlas::new_empty(image_handle.clone(), Vec2::new(256., 32.));
         let wall_atlas = TextureAtlas::from_grid(
@@ -269,9 +288,6 @@ impl GameAssetsPlugin {
                 )
             })
             .collect::<HashMap<_, _>>();
-        let mut recipes_list = RecipeList::default();
-        let mut furnace_list = FurnaceRecipeList::default();
-        let mut upgradeable_items = Vec::new();
 
         for (item, rect) in sprite_desc.items.iter() {
             match item {
@@ -327,24 +343,7 @@ impl GameAssetsPlugin {
             icon_map.insert(*item, sprite);
         }
 
-        // load recipes
-        for (result, recipe) in recipes_desc.0.iter() {
-            recipes_list.insert(*result, (recipe.0.clone(), recipe.1.clone(), recipe.2));
-        }
-        // load furnace recipes
-        for (result, recipe) in recipes_desc.1.iter() {
-            furnace_list.insert(*result, recipe.clone());
-        }
-        // load upgradeable items
-        for item in recipes_desc.2.iter() {
-            upgradeable_items.push(*item);
-        }
-
-        *recipes = Recipes {
-            crafting_list: recipes_list,
-            furnace_list,
-            upgradeable_items,
-        };
+        *recipes = Recipes::from(recipes_desc);
         // load UI
         for u in UIElement::iter() {
             println!("LOADED UI ASSET {:?}", u.to_string());
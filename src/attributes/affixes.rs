@@ -0,0 +1,233 @@
+use std::ops::Range;
+
+use bevy::prelude::*;
+use rand::{ seq::SliceRandom, Rng };
+use serde::{ Deserialize, Serialize };
+
+use crate::{
+    attributes::{ item_abilities::ItemAbility, ItemAttributes, ItemRarity, RawItemBonusAttributes },
+    inventory::ItemStack,
+    item::EquipmentType,
+};
+
+/// Items can have at most this many prefixes, and this many suffixes
+const MAX_AFFIXES_PER_SIDE: usize = 3;
+
+/// Which items an affix can roll on
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum AffixItemType {
+    Weapon,
+    Tool,
+    Armor,
+    Accessory,
+}
+impl AffixItemType {
+    pub fn from_equipment_type(eqp_type: &EquipmentType) -> Option<Self> {
+        if eqp_type.is_weapon() {
+            Some(AffixItemType::Weapon)
+        } else if eqp_type.is_tool() {
+            Some(AffixItemType::Tool)
+        } else if eqp_type.is_accessory() {
+            Some(AffixItemType::Accessory)
+        } else if eqp_type.is_equipment() {
+            Some(AffixItemType::Armor)
+        } else {
+            None
+        }
+    }
+}
+
+/// What an affix gives the item, scaled by the value rolled from its tier
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum AffixStat {
+    /// Adds to a bonus attribute, by its field name in `ItemAttributes`.
+    /// Only rolls on items that list the attribute in their `RawItemBonusAttributes`.
+    Attribute(String),
+    /// Gives the item an `ItemAbility`, by its name, with the rolled value as its power
+    Ability(String),
+}
+impl AffixStat {
+    pub fn is_ability(&self) -> bool {
+        matches!(self, AffixStat::Ability(_))
+    }
+    fn get_name(&self) -> String {
+        match self {
+            AffixStat::Attribute(attribute) => attribute.clone(),
+            AffixStat::Ability(ability) => ability.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AffixTier {
+    pub name: String,
+    /// Lowest item level this tier can roll on. Items take their level from the
+    /// `MobLevel` of the mob that dropped them, or the day the loot chest was found on.
+    pub min_level: u8,
+    pub value: Range<i32>,
+}
+
+/// A group of affixes for the same stat, from weakest to strongest tier.
+/// An item never rolls two affixes from the same family.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AffixFamily {
+    pub stat: AffixStat,
+    pub item_types: Vec<AffixItemType>,
+    pub tiers: Vec<AffixTier>,
+}
+impl AffixFamily {
+    fn get_unlocked_tiers(&self, level: u8) -> Vec<usize> {
+        self.tiers
+            .iter()
+            .enumerate()
+            .filter(|(_, tier)| tier.min_level <= level)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Loaded from affixes.ron, the prefix and suffix pools new items roll from
+#[derive(Resource, Default, Clone, Debug, Deserialize)]
+pub struct AffixPools {
+    pub prefixes: Vec<AffixFamily>,
+    pub suffixes: Vec<AffixFamily>,
+}
+
+/// An affix rolled onto an item, kept on the item so it can be shown in its tooltip
+/// and taken back off when the item is rerolled
+#[derive(Debug, PartialEq, Reflect, FromReflect, Clone, Default, Serialize, Deserialize)]
+pub struct ItemAffix {
+    pub name: String,
+    pub tier: u8,
+    pub is_prefix: bool,
+    pub stat: String,
+    pub value: i32,
+}
+
+#[derive(Default)]
+pub struct RolledAffixes {
+    pub affixes: Vec<ItemAffix>,
+    pub attributes: ItemAttributes,
+    pub ability: Option<ItemAbility>,
+}
+
+impl ItemRarity {
+    fn get_power(&self) -> u32 {
+        match self {
+            ItemRarity::Common => 0,
+            ItemRarity::Uncommon => 1,
+            ItemRarity::Rare => 2,
+            ItemRarity::Legendary => 3,
+        }
+    }
+}
+
+/// Rolls the affixes for a new item. Rarity decides how many it gets,
+/// and the item level decides which tiers are allowed to roll.
+pub fn roll_item_affixes(
+    pools: &AffixPools,
+    rarity: &ItemRarity,
+    eqp_type: &EquipmentType,
+    valid_attributes: &[&str],
+    level: u8
+) -> RolledAffixes {
+    let mut rolled = RolledAffixes::default();
+    let Some(item_type) = AffixItemType::from_equipment_type(eqp_type) else {
+        return rolled;
+    };
+    let mut rng = rand::thread_rng();
+    let can_roll = |family: &AffixFamily| {
+        family.item_types.contains(&item_type) &&
+            !family.get_unlocked_tiers(level).is_empty() &&
+            (match &family.stat {
+                AffixStat::Attribute(attribute) => valid_attributes.contains(&attribute.as_str()),
+                _ => true,
+            })
+    };
+    let mut candidates: Vec<(bool, &AffixFamily)> = pools.prefixes
+        .iter()
+        .filter(|family| can_roll(*family))
+        .map(|family| (true, family))
+        .chain(
+            pools.suffixes
+                .iter()
+                .filter(|family| can_roll(*family))
+                .map(|family| (false, family))
+        )
+        .collect();
+    candidates.shuffle(&mut rng);
+
+    let num_affixes = rng.gen_range(rarity.get_num_bonus_attributes(eqp_type)) as usize;
+    for (is_prefix, family) in candidates {
+        if rolled.affixes.len() >= num_affixes {
+            break;
+        }
+        let num_on_side = rolled.affixes
+            .iter()
+            .filter(|a| a.is_prefix == is_prefix)
+            .count();
+        // items only have room for one ability
+        if
+            num_on_side >= MAX_AFFIXES_PER_SIDE ||
+            (family.stat.is_ability() && rolled.ability.is_some())
+        {
+            continue;
+        }
+        let tier_index = *family.get_unlocked_tiers(level).choose(&mut rng).unwrap();
+        let tier = &family.tiers[tier_index];
+        let mut value = rng.gen_range(tier.value.clone());
+        match &family.stat {
+            AffixStat::Attribute(attribute) => {
+                value += rarity.get_rarity_attributes_bonus();
+                RawItemBonusAttributes::add_to_item_attributes(
+                    attribute,
+                    value,
+                    &mut rolled.attributes
+                );
+            }
+            AffixStat::Ability(ability) => {
+                rolled.ability = ItemAbility::from_name(ability, value);
+            }
+        }
+        rolled.affixes.push(ItemAffix {
+            name: tier.name.clone(),
+            tier: (tier_index + 1) as u8,
+            is_prefix,
+            stat: family.stat.get_name(),
+            value,
+        });
+    }
+    // strongest affixes first, they are the ones that name the item
+    rolled.affixes.sort_by(|a, b| b.tier.cmp(&a.tier));
+
+    rolled
+}
+
+/// Takes the attributes an item's affixes gave it back off, so they can be rerolled
+pub fn remove_affix_attributes(attributes: &mut ItemAttributes, affixes: &[ItemAffix]) {
+    for affix in affixes.iter() {
+        RawItemBonusAttributes::add_to_item_attributes(&affix.stat, -affix.value, attributes);
+    }
+}
+
+/// Names an item after its strongest prefix and suffix, like "Blazing Dagger of the Hog"
+pub fn get_affixed_item_name(base_name: &str, affixes: &[ItemAffix]) -> String {
+    let mut name = base_name.to_string();
+    if let Some(prefix) = affixes.iter().find(|a| a.is_prefix) {
+        name = format!("{} {}", prefix.name, name);
+    }
+    if let Some(suffix) = affixes.iter().find(|a| !a.is_prefix) {
+        name = format!("{} {}", name, suffix.name);
+    }
+    name
+}
+
+/// A single number to compare items by, growing with item level, rarity and affix tiers
+pub fn get_item_power(stack: &ItemStack) -> u32 {
+    let level = stack.metadata.level.unwrap_or(1) as u32;
+    let affix_power: u32 = stack.metadata.affixes
+        .iter()
+        .map(|a| (a.tier as u32) * 5)
+        .sum();
+    level * 10 + stack.rarity.get_power() * 5 + affix_power
+}
@@ -3,7 +3,20 @@ use std::cmp::max;
 use rand::{ rngs::ThreadRng, Rng };
 
 use crate::{
-    attributes::{ ItemAttributes, ItemRarity, RawItemBaseAttributes, RawItemBonusAttributes },
+    attributes::{
+        affixes::{
+            get_affixed_item_name,
+            remove_affix_attributes,
+            roll_item_affixes,
+            AffixPools,
+            RolledAffixes,
+        },
+        gems::Gems,
+        ItemAttributes,
+        ItemRarity,
+        RawItemBaseAttributes,
+        RawItemBonusAttributes,
+    },
     inventory::ItemStack,
     item::EquipmentType,
     proto::proto_param::ProtoParam,
@@ -29,11 +42,16 @@ pub fn create_new_random_item_stack_with_attributes(
         raw_bonus_att_option,
         rarity,
         eqp_type,
-        stack.metadata.level
+        stack.metadata.level,
+        &proto.affix_pools
     )
 }
 
-pub fn reroll_item_bonus_attributes(stack: &ItemStack, proto: &ProtoParam) -> ItemStack {
+pub fn reroll_item_bonus_attributes(
+    stack: &ItemStack,
+    proto: &ProtoParam,
+    gems: &Gems
+) -> ItemStack {
     let raw_bonus_att_option = proto.get_component::<RawItemBonusAttributes, _>(stack.obj_type);
     let Some(eqp_type) = proto.get_component::<EquipmentType, _>(stack.obj_type) else {
         return stack.clone();
@@ -46,24 +64,59 @@ pub fn reroll_item_bonus_attributes(stack: &ItemStack, proto: &ProtoParam) -> It
     } else {
         stack.rarity.clone()
     };
-    let parsed_bonus_att = if let Some(raw_bonus_att) = raw_bonus_att_option {
-        raw_bonus_att.into_item_attributes(rarity.clone(), eqp_type)
+    let mut final_att = if stack.metadata.affixes.is_empty() {
+        // items from before affixes only keep their core stats, and what their gems add
+        let mut core_att = gems.get_socketed_attributes(stack);
+        core_att.max_durability = stack.attributes.max_durability;
+        core_att.durability = stack.attributes.durability;
+        core_att.attack = stack.attributes.attack;
+        core_att.attack_cooldown = stack.attributes.attack_cooldown;
+        core_att.defense = stack.attributes.defense;
+        core_att.health = stack.attributes.health;
+        core_att
     } else {
-        ItemAttributes::default()
+        let mut att = stack.attributes.clone();
+        remove_affix_attributes(&mut att, &stack.metadata.affixes);
+        att
     };
-    let mut final_att = parsed_bonus_att;
-
-    final_att.max_durability = stack.attributes.max_durability;
-    final_att.attack = stack.attributes.attack;
-    final_att.attack_cooldown = stack.attributes.attack_cooldown;
-    final_att.defense = stack.attributes.defense;
-    final_att.health = stack.attributes.health;
+    let rolled = roll_affixes(
+        &proto.affix_pools,
+        &rarity,
+        eqp_type,
+        raw_bonus_att_option,
+        stack.metadata.level.unwrap_or(1)
+    );
+    final_att = final_att.combine(&rolled.attributes);
 
+    let base_name = proto
+        .get_item_data(stack.obj_type)
+        .map(|data| data.metadata.name.clone())
+        .unwrap_or_else(|| stack.metadata.name.clone());
     let mut new_stack = stack.copy_with_attributes(&final_att);
     new_stack.rarity = rarity;
+    set_affix_metadata(&mut new_stack, &base_name, rolled);
     new_stack
 }
 
+fn roll_affixes(
+    affix_pools: &AffixPools,
+    rarity: &ItemRarity,
+    eqp_type: &EquipmentType,
+    raw_bonus_att_option: Option<&RawItemBonusAttributes>,
+    level: u8
+) -> RolledAffixes {
+    let valid_attributes = raw_bonus_att_option
+        .map(|raw_bonus_att| raw_bonus_att.get_valid_attributes())
+        .unwrap_or_default();
+    roll_item_affixes(affix_pools, rarity, eqp_type, &valid_attributes, level)
+}
+
+fn set_affix_metadata(stack: &mut ItemStack, base_name: &str, rolled: RolledAffixes) {
+    stack.metadata.name = get_affixed_item_name(base_name, &rolled.affixes);
+    stack.metadata.item_ability = rolled.ability;
+    stack.metadata.affixes = rolled.affixes;
+}
+
 pub fn get_rarity_rng(mut rng: ThreadRng) -> ItemRarity {
     let rarity_rng = rng.gen_range(0..40);
     if rarity_rng == 0 {
@@ -83,15 +136,22 @@ pub fn build_item_stack_with_parsed_attributes(
     raw_bonus_att_option: Option<&RawItemBonusAttributes>,
     rarity: ItemRarity,
     equip_type: &EquipmentType,
-    level_option: Option<u8>
+    level_option: Option<u8>,
+    affix_pools: &AffixPools
 ) -> ItemStack {
-    let parsed_bonus_att = if let Some(raw_bonus_att) = raw_bonus_att_option {
-        raw_bonus_att.into_item_attributes(rarity.clone(), equip_type)
-    } else {
-        ItemAttributes::default()
-    };
+    let rolled = roll_affixes(
+        affix_pools,
+        &rarity,
+        equip_type,
+        raw_bonus_att_option,
+        level_option.unwrap_or(1)
+    );
+    let parsed_bonus_att = rolled.attributes.clone();
     let parsed_base_att = raw_base_att.into_item_attributes(stack.attributes.attack_cooldown);
     let mut final_att = parsed_bonus_att.combine(&parsed_base_att);
+    // durability comes from the prototype's ItemStack, and new items start undamaged
+    final_att.max_durability = stack.attributes.max_durability;
+    final_att.durability = final_att.max_durability;
     let mut level = 1;
     if let Some(item_level) = level_option {
         if equip_type.is_weapon() {
@@ -105,6 +165,7 @@ pub fn build_item_stack_with_parsed_attributes(
     let mut new_stack = stack.copy_with_attributes(&final_att);
     new_stack.metadata.level = Some(level);
     new_stack.rarity = rarity.clone();
+    set_affix_metadata(&mut new_stack, &stack.metadata.name, rolled);
 
     new_stack
 }
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{inventory::ItemStack, item::WorldObject};
+
+use super::{item_abilities::ItemAbility, ItemAttributes, RawItemBonusAttributes};
+
+/// What a gem adds to the equipment it is socketed into
+#[derive(Clone, Debug, Deserialize)]
+pub struct Gem {
+    /// Bonus attributes by their field name in `ItemAttributes`, and how much they add
+    #[serde(default)]
+    pub attributes: Vec<(String, i32)>,
+    #[serde(default)]
+    pub ability: Option<ItemAbility>,
+}
+
+impl Gem {
+    fn add_attributes(&self, attributes: &mut ItemAttributes, sign: i32) {
+        for (attribute, value) in self.attributes.iter() {
+            RawItemBonusAttributes::add_to_item_attributes(attribute, sign * value, attributes);
+        }
+    }
+}
+
+/// Loaded from gems.ron, every item that can be socketed and what it grants
+#[derive(Resource, Default, Clone, Debug, Deserialize)]
+pub struct Gems(pub HashMap<WorldObject, Gem>);
+
+impl Gems {
+    pub fn is_gem(&self, obj: WorldObject) -> bool {
+        self.0.contains_key(&obj)
+    }
+    pub fn can_socket(&self, stack: &ItemStack, gem: WorldObject) -> bool {
+        self.is_gem(gem) && stack.metadata.gems.len() < stack.rarity.get_num_sockets()
+    }
+    /// Puts the gem in the item's next free socket and adds its attributes,
+    /// returns None if the item has no free sockets
+    pub fn socket_gem(&self, stack: &ItemStack, gem: WorldObject) -> Option<ItemStack> {
+        if !self.can_socket(stack, gem) {
+            return None;
+        }
+        let mut attributes = stack.attributes.clone();
+        self.0.get(&gem).unwrap().add_attributes(&mut attributes, 1);
+        let mut new_stack = stack.copy_with_attributes(&attributes);
+        new_stack.metadata.gems.push(gem);
+        Some(new_stack)
+    }
+    /// Takes the most recently socketed gem back out of the item, and its attributes with it
+    pub fn unsocket_gem(&self, stack: &ItemStack) -> Option<(ItemStack, WorldObject)> {
+        let gem = *stack.metadata.gems.last()?;
+        let mut attributes = stack.attributes.clone();
+        if let Some(gem_data) = self.0.get(&gem) {
+            gem_data.add_attributes(&mut attributes, -1);
+        }
+        let mut new_stack = stack.copy_with_attributes(&attributes);
+        new_stack.metadata.gems.pop();
+        Some((new_stack, gem))
+    }
+    /// The attributes every gem socketed in the item is adding to it
+    pub fn get_socketed_attributes(&self, stack: &ItemStack) -> ItemAttributes {
+        let mut attributes = ItemAttributes::default();
+        for gem in stack.metadata.gems.iter().filter_map(|gem| self.0.get(gem)) {
+            gem.add_attributes(&mut attributes, 1);
+        }
+        attributes
+    }
+    pub fn get_socketed_abilities(&self, stack: &ItemStack) -> Vec<ItemAbility> {
+        stack
+            .metadata
+            .gems
+            .iter()
+            .filter_map(|gem| self.0.get(gem))
+            .filter_map(|gem| gem.ability.clone())
+            .collect()
+    }
+}
@@ -1,74 +1,426 @@
-use bevy::prelude::*;
+use std::collections::HashMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_proto::prelude::ProtoCommands;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
 
 use crate::{
     animations::AttackEvent,
-    inventory::ItemStack,
+    attributes::{gems::Gems, modifiers::ModifyHealthEvent, set_bonuses::ActiveSetBonuses, Attack},
+    combat::{EnemyDeathEvent, HitEvent, InvincibilityTimer},
+    custom_commands::CommandsExt,
+    enemy::{
+        companion::{Companion, LastHitByCompanion, TemporaryAlly},
+        Mob,
+    },
+    inventory::{Inventory, ItemStack},
     item::{
-        projectile::{Projectile, RangedAttackEvent},
-        MainHand,
+        item_upgrades::Burning,
+        projectile::{Projectile, RangedAttack, RangedAttackEvent},
+        MainHand, WorldObject,
     },
+    player::Player,
+    proto::proto_param::ProtoParam,
+    Game, GameState,
 };
 
-#[derive(Debug, PartialEq, Reflect, FromReflect, Clone, Serialize, Deserialize)]
+pub struct ItemAbilityPlugin;
+impl Plugin for ItemAbilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ItemAbilityDefs>()
+            .init_resource::<ItemAbilityCooldowns>()
+            .add_systems(
+                (
+                    tick_item_ability_cooldowns,
+                    handle_item_abilitiy_on_attack,
+                    handle_item_ability_on_hit,
+                    handle_item_ability_on_kill,
+                    handle_item_ability_on_hurt,
+                    handle_item_ability_on_dash,
+                    handle_passive_item_abilities,
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            );
+    }
+}
+
+/// Abilities that items can roll as affixes. The number is the ability's power,
+/// usually its damage, and what sets it off is defined in abilities.ron
+#[derive(
+    Debug, PartialEq, Reflect, FromReflect, Clone, Serialize, Deserialize, IntoStaticStr, EnumIter,
+)]
 #[reflect(Default)]
 pub enum ItemAbility {
+    /// Swings an extra arc projectile
     Arc(i32),
+    /// Swings an extra fire projectile
     FireAttack(i32),
+    /// Lightning jumps from the hit mob to the closest mob near it
+    ChainLightning(i32),
+    /// Heals the player
+    HealOnKill(i32),
+    /// Summons a temporary companion of this level
+    SummonAlly(i32),
+    /// Hits every mob around the player
+    DashShockwave(i32),
+    /// Shoots enemy projectiles back the way they came
+    ReflectProjectile(i32),
+    /// Sets mobs near the player on fire, burning for this much damage per tick
+    BurningAura(i32),
 }
 impl Default for ItemAbility {
     fn default() -> Self {
         ItemAbility::Arc(2)
     }
 }
-
-// items will have a chance to spawn with 1 ItemAbility.
-// for now, it can be a fixed rate for all items, maybe 20%
-// perhapse a 3rd item upgrade can add or override abilities on items
-// when AttackEvent is fired, we match on enum and handle teh ability.
-const ITEM_ABILITY_CHANCE: u32 = 25;
-pub fn add_ability_to_item_drops(stack: &mut ItemStack) {
-    let mut rng = rand::thread_rng();
-    let chance = rng.gen_range(0..100);
-    if chance <= ITEM_ABILITY_CHANCE {
-        let ability = match rng.gen_range(0..2) {
-            0 => Some(ItemAbility::Arc(rng.gen_range(2..5))),
-            1 => Some(ItemAbility::FireAttack(rng.gen_range(2..5))),
+impl ItemAbility {
+    pub fn from_name(name: &str, value: i32) -> Option<Self> {
+        match name {
+            "Arc" => Some(ItemAbility::Arc(value)),
+            "FireAttack" => Some(ItemAbility::FireAttack(value)),
+            "ChainLightning" => Some(ItemAbility::ChainLightning(value)),
+            "HealOnKill" => Some(ItemAbility::HealOnKill(value)),
+            "SummonAlly" => Some(ItemAbility::SummonAlly(value)),
+            "DashShockwave" => Some(ItemAbility::DashShockwave(value)),
+            "ReflectProjectile" => Some(ItemAbility::ReflectProjectile(value)),
+            "BurningAura" => Some(ItemAbility::BurningAura(value)),
             _ => None,
+        }
+    }
+    pub fn get_name(&self) -> &'static str {
+        self.into()
+    }
+    pub fn get_value(&self) -> i32 {
+        match self {
+            ItemAbility::Arc(v)
+            | ItemAbility::FireAttack(v)
+            | ItemAbility::ChainLightning(v)
+            | ItemAbility::HealOnKill(v)
+            | ItemAbility::SummonAlly(v)
+            | ItemAbility::DashShockwave(v)
+            | ItemAbility::ReflectProjectile(v)
+            | ItemAbility::BurningAura(v) => *v,
+        }
+    }
+}
+
+/// What sets an ability off
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum AbilityTrigger {
+    OnAttack,
+    OnHit,
+    OnKill,
+    OnHurt,
+    OnDash,
+    /// Tries to go off every frame, so it relies on its cooldown
+    Passive,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ItemAbilityDef {
+    pub trigger: AbilityTrigger,
+    /// Percent chance the ability goes off when triggered
+    pub proc_chance: u32,
+    /// Seconds before the ability can go off again
+    pub cooldown: f32,
+    /// Reach of area abilities, in pixels
+    #[serde(default)]
+    pub radius: f32,
+    /// Seconds that summons and burns last
+    #[serde(default)]
+    pub duration: f32,
+    #[serde(default)]
+    pub summon: Option<Mob>,
+    /// Shown next to the ability in item tooltips
+    pub icon: WorldObject,
+}
+
+/// Loaded from abilities.ron, keyed by the ability's name
+#[derive(Resource, Default, Clone, Debug, Deserialize)]
+pub struct ItemAbilityDefs(pub HashMap<String, ItemAbilityDef>);
+
+#[derive(Resource, Default)]
+pub struct ItemAbilityCooldowns(HashMap<&'static str, Timer>);
+
+/// Abilities from the player's equipment, its gems and set bonuses, and their cooldowns
+#[derive(SystemParam)]
+pub struct ItemAbilityParam<'w, 's> {
+    defs: Res<'w, ItemAbilityDefs>,
+    cooldowns: ResMut<'w, ItemAbilityCooldowns>,
+    main_hand: Query<'w, 's, &'static ItemStack, With<MainHand>>,
+    inv: Query<'w, 's, &'static Inventory>,
+    set_bonuses: Query<'w, 's, &'static ActiveSetBonuses, With<Player>>,
+    gems: Res<'w, Gems>,
+}
+impl<'w, 's> ItemAbilityParam<'w, 's> {
+    fn get_equipped_abilities(&self) -> Vec<ItemAbility> {
+        let Ok(inv) = self.inv.get_single() else {
+            return vec![];
         };
-        stack.metadata.item_ability = ability;
+        let worn_items = inv
+            .equipment_items
+            .items
+            .iter()
+            .chain(inv.accessory_items.items.iter())
+            .flatten()
+            .map(|item| &item.item_stack);
+        let set_abilities = self
+            .set_bonuses
+            .get_single()
+            .map(|sets| sets.abilities.clone())
+            .unwrap_or_default();
+        self.main_hand
+            .iter()
+            .chain(worn_items)
+            .filter(|stack| !stack.attributes.is_broken())
+            .flat_map(|stack| {
+                stack
+                    .metadata
+                    .item_ability
+                    .clone()
+                    .into_iter()
+                    .chain(self.gems.get_socketed_abilities(stack))
+            })
+            .chain(set_abilities)
+            .collect()
+    }
+    /// Rolls every equipped ability with this trigger that is off cooldown,
+    /// and starts the cooldown of the ones that go off
+    pub fn proc_abilities(
+        &mut self,
+        trigger: AbilityTrigger,
+        can_proc: impl Fn(&ItemAbility) -> bool,
+    ) -> Vec<(ItemAbility, ItemAbilityDef)> {
+        let mut rng = rand::thread_rng();
+        let mut procs = vec![];
+        for ability in self.get_equipped_abilities() {
+            let Some(def) = self.defs.0.get(ability.get_name()) else {
+                continue;
+            };
+            if def.trigger != trigger || !can_proc(&ability) {
+                continue;
+            }
+            if let Some(cooldown) = self.cooldowns.0.get(ability.get_name()) {
+                if !cooldown.finished() {
+                    continue;
+                }
+            }
+            if rng.gen_range(0..100) >= def.proc_chance {
+                continue;
+            }
+            self.cooldowns.0.insert(
+                ability.get_name(),
+                Timer::from_seconds(def.cooldown, TimerMode::Once),
+            );
+            procs.push((ability, def.clone()));
+        }
+        procs
+    }
+}
+
+fn tick_item_ability_cooldowns(mut cooldowns: ResMut<ItemAbilityCooldowns>, time: Res<Time>) {
+    for timer in cooldowns.0.values_mut() {
+        timer.tick(time.delta());
     }
 }
 
 pub fn handle_item_abilitiy_on_attack(
     mut attacks: EventReader<AttackEvent>,
-    weapon: Query<&ItemStack, With<MainHand>>,
+    mut abilities: ItemAbilityParam,
     mut ranged_attack_event: EventWriter<RangedAttackEvent>,
 ) {
     for attack in attacks.iter() {
-        if let Ok(weapon_stack) = weapon.get_single() {
-            match weapon_stack.metadata.item_ability {
-                Some(ItemAbility::Arc(dmg)) => {
-                    ranged_attack_event.send(RangedAttackEvent {
-                        projectile: Projectile::Arc,
-                        direction: attack.direction,
-                        from_enemy: None,
-                        is_followup_proj: true,
-                        mana_cost: None,
-                        dmg_override: Some(dmg),
-                    });
+        for (ability, _) in abilities.proc_abilities(AbilityTrigger::OnAttack, |_| true) {
+            let projectile = match ability {
+                ItemAbility::Arc(_) => Projectile::Arc,
+                ItemAbility::FireAttack(_) => Projectile::FireAttack,
+                _ => continue,
+            };
+            ranged_attack_event.send(RangedAttackEvent {
+                projectile,
+                direction: attack.direction,
+                from_enemy: None,
+                is_followup_proj: true,
+                mana_cost: None,
+                dmg_override: Some(ability.get_value()),
+            });
+        }
+    }
+}
+
+/// Hits from the player's weapon, but not from the projectiles abilities spawn,
+/// so abilities can't set themselves off
+pub fn handle_item_ability_on_hit(
+    mut hits: EventReader<HitEvent>,
+    mut abilities: ItemAbilityParam,
+    mut proto_commands: ProtoCommands,
+    proto: ProtoParam,
+    mobs: Query<(Entity, &GlobalTransform), With<Mob>>,
+    allies: Query<(), With<Companion>>,
+    ranged_weapon: Query<&RangedAttack, With<MainHand>>,
+) {
+    for hit in hits.iter() {
+        let is_weapon_hit = hit.hit_with_melee.is_some()
+            || (hit.hit_with_projectile.is_some()
+                && hit.hit_with_projectile == ranged_weapon.get_single().ok().map(|r| r.0.clone()));
+        if !is_weapon_hit || allies.contains(hit.hit_entity) {
+            continue;
+        }
+        let Ok((_, hit_t)) = mobs.get(hit.hit_entity) else {
+            continue;
+        };
+        let hit_pos = hit_t.translation().truncate();
+        for (ability, def) in abilities.proc_abilities(AbilityTrigger::OnHit, |_| true) {
+            let ItemAbility::ChainLightning(dmg) = ability else {
+                continue;
+            };
+            let Some(next_pos) = mobs
+                .iter()
+                .filter(|(e, _)| *e != hit.hit_entity && !allies.contains(*e))
+                .map(|(_, t)| t.translation().truncate())
+                .filter(|pos| pos.distance(hit_pos) <= def.radius)
+                .min_by(|a, b| a.distance(hit_pos).total_cmp(&b.distance(hit_pos)))
+            else {
+                continue;
+            };
+            if let Some(bolt) = proto_commands.spawn_projectile_from_proto(
+                Projectile::Electricity,
+                &proto,
+                hit_pos,
+                (next_pos - hit_pos).normalize_or_zero(),
+            ) {
+                proto_commands.commands().entity(bolt).insert(Attack(dmg));
+            }
+        }
+    }
+}
+
+/// Mobs killed by the player, kills by companions don't count
+pub fn handle_item_ability_on_kill(
+    mut commands: Commands,
+    mut deaths: EventReader<EnemyDeathEvent>,
+    mut abilities: ItemAbilityParam,
+    mut proto_commands: ProtoCommands,
+    proto: ProtoParam,
+    killed_by_companion: Query<(), With<LastHitByCompanion>>,
+    mut modify_health_event: EventWriter<ModifyHealthEvent>,
+) {
+    for death in deaths.iter() {
+        if killed_by_companion.contains(death.entity) {
+            continue;
+        }
+        for (ability, def) in abilities.proc_abilities(AbilityTrigger::OnKill, |_| true) {
+            match ability {
+                ItemAbility::HealOnKill(hp) => modify_health_event.send(ModifyHealthEvent(hp)),
+                ItemAbility::SummonAlly(level) => {
+                    let Some(mob) = def.summon else {
+                        continue;
+                    };
+                    let Some(ally) =
+                        proto_commands.spawn_from_proto(mob, &proto.prototypes, death.enemy_pos)
+                    else {
+                        continue;
+                    };
+                    commands.entity(ally).insert((
+                        Companion {
+                            level: level.clamp(1, u8::MAX as i32) as u8,
+                            ..default()
+                        },
+                        TemporaryAlly(Timer::from_seconds(def.duration, TimerMode::Once)),
+                    ));
                 }
-                Some(ItemAbility::FireAttack(dmg)) => ranged_attack_event.send(RangedAttackEvent {
-                    projectile: Projectile::FireAttack,
-                    direction: attack.direction,
-                    from_enemy: None,
-                    is_followup_proj: true,
-                    mana_cost: None,
-                    dmg_override: Some(dmg),
-                }),
-                None => {}
+                _ => {}
+            }
+        }
+    }
+}
+
+pub fn handle_item_ability_on_hurt(
+    mut hits: EventReader<HitEvent>,
+    mut abilities: ItemAbilityParam,
+    player: Query<(Entity, Option<&InvincibilityTimer>), With<Player>>,
+    mut ranged_attack_event: EventWriter<RangedAttackEvent>,
+) {
+    let (player_e, i_frame_option) = player.single();
+    for hit in hits.iter() {
+        if hit.hit_entity != player_e || hit.damage <= 0 || i_frame_option.is_some() {
+            continue;
+        }
+        let Some(projectile) = hit.hit_with_projectile.clone() else {
+            continue;
+        };
+        for (ability, _) in abilities.proc_abilities(AbilityTrigger::OnHurt, |ability| {
+            matches!(ability, ItemAbility::ReflectProjectile(_))
+        }) {
+            ranged_attack_event.send(RangedAttackEvent {
+                projectile: projectile.clone(),
+                direction: -hit.dir,
+                from_enemy: None,
+                is_followup_proj: true,
+                mana_cost: None,
+                dmg_override: Some(ability.get_value()),
+            });
+        }
+    }
+}
+
+pub fn handle_item_ability_on_dash(
+    game: Res<Game>,
+    mut was_dashing: Local<bool>,
+    mut abilities: ItemAbilityParam,
+    player_t: Query<&GlobalTransform, With<Player>>,
+    mobs: Query<(Entity, &GlobalTransform), (With<Mob>, Without<Companion>)>,
+    mut hit_event: EventWriter<HitEvent>,
+) {
+    let is_dashing = game.player_state.is_dashing;
+    let just_dashed = is_dashing && !*was_dashing;
+    *was_dashing = is_dashing;
+    if !just_dashed {
+        return;
+    }
+    let player_pos = player_t.single().translation().truncate();
+    for (ability, def) in abilities.proc_abilities(AbilityTrigger::OnDash, |_| true) {
+        let ItemAbility::DashShockwave(dmg) = ability else {
+            continue;
+        };
+        for (mob_e, mob_t) in mobs.iter() {
+            let delta = mob_t.translation().truncate() - player_pos;
+            if delta.length() > def.radius {
+                continue;
+            }
+            hit_event.send(HitEvent {
+                hit_entity: mob_e,
+                damage: dmg,
+                dir: delta.normalize_or_zero(),
+                hit_with_melee: None,
+                hit_with_projectile: None,
+            });
+        }
+    }
+}
+
+pub fn handle_passive_item_abilities(
+    mut commands: Commands,
+    mut abilities: ItemAbilityParam,
+    player_t: Query<&GlobalTransform, With<Player>>,
+    mobs: Query<(Entity, &GlobalTransform), (With<Mob>, Without<Companion>, Without<Burning>)>,
+) {
+    let player_pos = player_t.single().translation().truncate();
+    for (ability, def) in abilities.proc_abilities(AbilityTrigger::Passive, |_| true) {
+        let ItemAbility::BurningAura(dmg) = ability else {
+            continue;
+        };
+        for (mob_e, mob_t) in mobs.iter() {
+            if mob_t.translation().truncate().distance(player_pos) > def.radius {
+                continue;
             }
+            commands.entity(mob_e).insert(Burning {
+                tick_timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+                duration_timer: Timer::from_seconds(def.duration, TimerMode::Once),
+                damage: dmg.clamp(0, u8::MAX as i32) as u8,
+            });
         }
     }
 }
@@ -1,19 +1,22 @@
-use item_abilities::{ add_ability_to_item_drops, handle_item_abilitiy_on_attack };
+use item_abilities::ItemAbilityPlugin;
 use rand::Rng;
 use serde::{ Deserialize, Serialize };
 use std::ops::{ Range, RangeInclusive };
 
 use bevy::{ ecs::system::EntityCommands, prelude::* };
 use bevy_proto::prelude::{ ReflectSchematic, Schematic };
+pub mod affixes;
+pub mod gems;
 pub mod health_regen;
 pub mod modifiers;
+pub mod set_bonuses;
 use crate::{
     animations::AnimatedTextureMaterial,
     attributes::attribute_helpers::{ build_item_stack_with_parsed_attributes, get_rarity_rng },
     client::GameOverEvent,
     colors::{ LIGHT_BLUE, LIGHT_GREEN, LIGHT_GREY, LIGHT_RED },
     inventory::{ Inventory, ItemStack },
-    item::{ Equipment, EquipmentType },
+    item::{ ActiveMainHandState, Equipment, EquipmentType, WorldObject },
     player::{ stats::PlayerStats, Limb },
     proto::proto_param::ProtoParam,
     ui::{
@@ -28,6 +31,9 @@ use crate::{
     GameState,
     Player,
 };
+use affixes::AffixPools;
+use gems::Gems;
+use set_bonuses::EquipmentSets;
 use modifiers::*;
 pub mod attribute_helpers;
 pub mod hunger;
@@ -248,8 +254,22 @@ impl ItemAttributes {
         tooltips
     }
     pub fn get_durability_tooltip(&self) -> String {
+        if self.is_broken() {
+            return "Broken".to_string();
+        }
         format!("{}/{}", self.durability, self.max_durability)
     }
+    /// Items without a max durability never wear down
+    pub fn has_durability(&self) -> bool {
+        self.max_durability > 0
+    }
+    pub fn is_damaged(&self) -> bool {
+        self.has_durability() && self.durability < self.max_durability
+    }
+    /// Broken items stay in the inventory, but give no stats and can not be used until repaired
+    pub fn is_broken(&self) -> bool {
+        self.has_durability() && self.durability <= 0
+    }
     pub fn add_attribute_components(&self, entity: &mut EntityCommands) {
         if self.health > 0 {
             entity.insert(MaxHealth(self.health));
@@ -329,47 +349,30 @@ macro_rules! setup_raw_bonus_attributes {
         }
 
         impl $name {
-
-            pub fn into_item_attributes(
-                &self,
-                rarity: ItemRarity,
-                item_type: &EquipmentType
-            ) -> ItemAttributes {
-                // take fields of Range<i32> into one i32
-                let mut rng = rand::thread_rng();
-                let num_bonus_attributes = rarity.get_num_bonus_attributes(item_type);
-                let num_attributes = rng.gen_range(num_bonus_attributes);
-                let mut item_attributes = ItemAttributes::default();
-                let valid_attributes = {
-                    let mut v = Vec::new();
-                    $(
-                        if self.$field_name.is_some() {
-                            v.push(stringify!($field_name))
-                        }
-                    )*
-                    v
-                };
-                let num_valid_attributes = valid_attributes.len();
-                let mut already_picked_attributes = Vec::new();
-                for _ in 0..num_attributes {
-                    let picked_attribute_index = rng.gen_range(0..num_valid_attributes);
-                    let mut picked_attribute = valid_attributes[picked_attribute_index];
-                    while already_picked_attributes.contains(&picked_attribute) {
-                        let picked_attribute_index = rng.gen_range(0..num_valid_attributes);
-                        picked_attribute = valid_attributes[picked_attribute_index];
+            /// Names of the bonus attributes this item can roll affixes for
+            pub fn get_valid_attributes(&self) -> Vec<&'static str> {
+                let mut v = Vec::new();
+                $(
+                    if self.$field_name.is_some() {
+                        v.push(stringify!($field_name))
                     }
-                    already_picked_attributes.push(picked_attribute);
-                    $(
-                        {
-                            if stringify!($field_name) == picked_attribute {
-                                let value = rng.gen_range(self.$field_name.clone().unwrap());
-                                item_attributes.$field_name = value + rarity.get_rarity_attributes_bonus();
-                            }
-                        }
-                    )*
-                }
-
-                item_attributes
+                )*
+                v
+            }
+            /// Adds `value` to the `ItemAttributes` field named `attribute`,
+            /// returns false if there is no bonus attribute with that name
+            pub fn add_to_item_attributes(
+                attribute: &str,
+                value: i32,
+                item_attributes: &mut ItemAttributes
+            ) -> bool {
+                $(
+                    if stringify!($field_name) == attribute {
+                        item_attributes.$field_name += value;
+                        return true;
+                    }
+                )*
+                false
             }
         }
     };
@@ -493,6 +496,15 @@ impl ItemRarity {
             ItemRarity::Legendary => 4 + acc_offset..=5 + acc_offset,
         }
     }
+    /// How many gems equipment of this rarity can have socketed
+    pub fn get_num_sockets(&self) -> usize {
+        match self {
+            ItemRarity::Common => 1,
+            ItemRarity::Uncommon => 1,
+            ItemRarity::Rare => 2,
+            ItemRarity::Legendary => 3,
+        }
+    }
     fn get_rarity_attributes_bonus(&self) -> i32 {
         match self {
             ItemRarity::Common => 1,
@@ -637,6 +649,10 @@ pub struct ManaRegen(pub i32);
 impl Plugin for AttributesPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<AttributeChangeEvent>()
+            .init_resource::<AffixPools>()
+            .init_resource::<EquipmentSets>()
+            .init_resource::<Gems>()
+            .add_plugin(ItemAbilityPlugin)
             .add_event::<ModifyHealthEvent>()
             .add_event::<ModifyManaEvent>()
             .add_systems(
@@ -653,7 +669,6 @@ impl Plugin for AttributesPlugin {
                     update_attributes_with_held_item_change,
                     update_attributes_and_sprite_with_equipment_change,
                     update_sprite_with_equipment_removed,
-                    handle_item_abilitiy_on_attack,
                     handle_new_items_raw_attributes.before(CustomFlush),
                     handle_player_item_attribute_change_events.after(CustomFlush),
                 ).in_set(OnUpdate(GameState::Main))
@@ -689,7 +704,8 @@ fn handle_player_item_attribute_change_events(
     eqp_attributes: Query<&ItemAttributes, With<Equipment>>,
     mut att_events: EventReader<AttributeChangeEvent>,
     mut stats_event: EventWriter<ShowInvPlayerStatsEvent>,
-    player_atts: Query<&ItemAttributes, With<Player>>
+    player_atts: Query<&ItemAttributes, With<Player>>,
+    equipment_sets: Res<EquipmentSets>
 ) {
     for _event in att_events.iter() {
         let mut new_att = player_atts.single().clone();
@@ -701,9 +717,20 @@ fn handle_player_item_attribute_change_events(
             .map(|e| e.item_stack.attributes.clone())
             .collect();
 
-        for a in eqp_attributes.iter().chain(equips.iter()) {
+        // broken items give no stats until they are repaired
+        for a in eqp_attributes.iter().chain(equips.iter()).filter(|a| !a.is_broken()) {
             new_att = new_att.combine(a);
         }
+        let worn_pieces: Vec<WorldObject> = inv.equipment_items.items
+            .iter()
+            .chain(inv.accessory_items.items.iter())
+            .flatten()
+            .filter(|e| !e.item_stack.attributes.is_broken())
+            .map(|e| e.item_stack.obj_type)
+            .collect();
+        let set_bonuses = equipment_sets.get_active_bonuses(&worn_pieces);
+        new_att = new_att.combine(&set_bonuses.attributes);
+        commands.entity(player).insert(set_bonuses);
         if new_att.attack_cooldown == 0.0 {
             new_att.attack_cooldown = 0.4;
         }
@@ -742,11 +769,24 @@ fn update_attributes_with_held_item_change(
         if let Some(current_item) = prev_held_item_data {
             let curr_attributes = item_stack_query.get(current_item.entity).unwrap();
             let new_attributes = &new_item.item_stack.attributes;
-            if new_item_stack != current_item.item_stack {
+            // wearing down or repairing the held item only changes its attributes,
+            // so the held item entity is kept instead of respawned
+            let is_same_item =
+                new_item_stack.copy_with_attributes(&current_item.item_stack.attributes) ==
+                current_item.item_stack;
+            if !is_same_item {
                 new_item.spawn_item_on_hand(&mut commands, &mut game_param, &proto);
                 att_event.send(AttributeChangeEvent);
             } else if curr_attributes != new_attributes {
-                commands.entity(current_item.entity).insert(new_attributes.clone());
+                let held_e = current_item.entity;
+                commands
+                    .entity(held_e)
+                    .insert(new_attributes.clone())
+                    .insert(new_item_stack.clone());
+                game_param.player_mut().main_hand_slot = Some(ActiveMainHandState {
+                    entity: held_e,
+                    item_stack: new_item_stack,
+                });
                 att_event.send(AttributeChangeEvent);
             }
         } else {
@@ -842,21 +882,20 @@ fn handle_new_items_raw_attributes(
             Option<&ItemLevel>,
         ),
         Or<(Added<RawItemBaseAttributes>, Added<RawItemBonusAttributes>)>
-    >
+    >,
+    affix_pools: Res<AffixPools>
 ) {
     for (e, stack, raw_bonus_att_option, raw_base_att, eqp_type, item_level) in new_items.iter() {
         let rarity = get_rarity_rng(rand::thread_rng());
-        let mut new_stack = build_item_stack_with_parsed_attributes(
+        let new_stack = build_item_stack_with_parsed_attributes(
             stack,
             raw_base_att,
             raw_bonus_att_option,
             rarity,
             eqp_type,
-            item_level.map(|l| l.0)
+            item_level.map(|l| l.0),
+            &affix_pools
         );
-        if new_stack.obj_type.is_weapon() {
-            add_ability_to_item_drops(&mut new_stack);
-        }
         commands.entity(e).insert(new_stack);
     }
 }
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::item::WorldObject;
+
+use super::{item_abilities::ItemAbility, ItemAttributes, RawItemBonusAttributes};
+
+/// A bonus that turns on once enough pieces of its set are worn
+#[derive(Clone, Debug, Deserialize)]
+pub struct SetBonus {
+    pub pieces: usize,
+    /// Bonus attributes by their field name in `ItemAttributes`, and how much they add
+    #[serde(default)]
+    pub attributes: Vec<(String, i32)>,
+    #[serde(default)]
+    pub ability: Option<ItemAbility>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EquipmentSet {
+    pub name: String,
+    pub pieces: Vec<WorldObject>,
+    pub bonuses: Vec<SetBonus>,
+}
+
+/// Loaded from sets.ron, every armor set and the bonuses for wearing its pieces together
+#[derive(Resource, Default, Clone, Debug, Deserialize)]
+pub struct EquipmentSets(pub Vec<EquipmentSet>);
+
+#[derive(Clone, Debug)]
+pub struct ActiveSet {
+    pub name: String,
+    pub equipped: usize,
+    pub total: usize,
+}
+
+/// The sets the player is wearing at least one bonus of,
+/// updated with the player's attributes whenever their equipment changes
+#[derive(Component, Clone, Debug, Default)]
+pub struct ActiveSetBonuses {
+    pub sets: Vec<ActiveSet>,
+    pub attributes: ItemAttributes,
+    pub abilities: Vec<ItemAbility>,
+}
+
+impl EquipmentSets {
+    /// Counts the worn pieces of each set and adds up every bonus they have unlocked
+    pub fn get_active_bonuses(&self, worn: &[WorldObject]) -> ActiveSetBonuses {
+        let mut active = ActiveSetBonuses::default();
+        for set in self.0.iter() {
+            let equipped = set.pieces.iter().filter(|p| worn.contains(p)).count();
+            let unlocked: Vec<&SetBonus> = set
+                .bonuses
+                .iter()
+                .filter(|bonus| bonus.pieces <= equipped)
+                .collect();
+            if unlocked.is_empty() {
+                continue;
+            }
+            for bonus in unlocked {
+                for (attribute, value) in bonus.attributes.iter() {
+                    RawItemBonusAttributes::add_to_item_attributes(
+                        attribute,
+                        *value,
+                        &mut active.attributes,
+                    );
+                }
+                if let Some(ability) = &bonus.ability {
+                    active.abilities.push(ability.clone());
+                }
+            }
+            active.sets.push(ActiveSet {
+                name: set.name.clone(),
+                equipped,
+                total: set.pieces.len(),
+            });
+        }
+        active
+    }
+}
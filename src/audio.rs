@@ -2,12 +2,14 @@ use bevy::prelude::*;
 use rand::seq::IteratorRandom;
 
 use crate::{
+    action_map::{ActionInput, InputAction},
     combat::{AttackTimer, HitEvent, ObjBreakEvent},
     enemy::Mob,
     handle_attack_cooldowns,
     item::WorldObject,
     juice::UseItemEvent,
     player::Player,
+    settings::Settings,
     ui::UIState,
     GameState,
 };
@@ -49,11 +51,12 @@ impl Plugin for AudioPlugin {
 pub fn sword_swing_sound(
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
-    mouse_button_input: Res<Input<MouseButton>>,
+    settings: Res<Settings>,
+    actions: ActionInput,
     player_query: Query<Option<&AttackTimer>, With<Player>>,
     curr_ui_state: Res<State<UIState>>,
 ) {
-    if mouse_button_input.pressed(MouseButton::Left) && curr_ui_state.0 == UIState::Closed {
+    if actions.pressed(InputAction::Attack) && curr_ui_state.0 == UIState::Closed {
         let attack_timer_option = player_query.single();
         if attack_timer_option.is_some() {
             return;
@@ -65,13 +68,17 @@ pub fn sword_swing_sound(
         let swing3 = asset_server.load("sounds/swing3.ogg");
         let swings = vec![swing1, swing2, swing3];
         swings.iter().choose(&mut rand::thread_rng()).map(|sound| {
-            audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5))
+            audio.play_with_settings(
+                sound.clone(),
+                PlaybackSettings::ONCE.with_volume(0.5 * settings.get_sfx_volume()),
+            )
         });
     }
 }
 pub fn bgm_audio(
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
     mut bgm_tracker: ResMut<BGMPicker>,
     audio_handles: Res<Assets<AudioSink>>,
     mut bgm_update_events: EventReader<UpdateBGMTrackEvent>,
@@ -87,9 +94,10 @@ pub fn bgm_audio(
         bgm_tracker.current_track = path.clone();
         let bgm1 = asset_server.load(path);
 
-        let new_handle = audio_handles.get_handle(
-            audio.play_with_settings(bgm1.clone(), PlaybackSettings::LOOP.with_volume(1.0)),
-        );
+        let new_handle = audio_handles.get_handle(audio.play_with_settings(
+            bgm1.clone(),
+            PlaybackSettings::LOOP.with_volume(settings.get_music_volume()),
+        ));
         bgm_tracker.current_handle = Some(new_handle);
     }
 }
@@ -97,6 +105,7 @@ pub fn bgm_audio(
 pub fn use_item_audio(
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
     mut use_item_event: EventReader<UseItemEvent>,
 ) {
     for item in use_item_event.iter() {
@@ -116,11 +125,17 @@ pub fn use_item_audio(
             let crunch3 = asset_server.load("sounds/crunch3.ogg");
             let crunchs = vec![crunch1, crunch2, crunch3];
             crunchs.iter().choose(&mut rand::thread_rng()).map(|sound| {
-                audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5))
+                audio.play_with_settings(
+                    sound.clone(),
+                    PlaybackSettings::ONCE.with_volume(0.5 * settings.get_sfx_volume()),
+                )
             });
         } else {
             let sound = asset_server.load(format!("sounds/{}.ogg", item.0));
-            audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5));
+            audio.play_with_settings(
+                sound.clone(),
+                PlaybackSettings::ONCE.with_volume(0.5 * settings.get_sfx_volume()),
+            );
         }
     }
 }
@@ -128,6 +143,7 @@ pub fn use_item_audio(
 pub fn break_item_audio(
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
     mut obj_break_events: EventReader<ObjBreakEvent>,
 ) {
     for item in obj_break_events.iter() {
@@ -154,11 +170,17 @@ pub fn break_item_audio(
                 rustle1, rustle2, rustle3, rustle4, rustle5, rustle6, rustle7,
             ];
             rustles.iter().choose(&mut rand::thread_rng()).map(|sound| {
-                audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5))
+                audio.play_with_settings(
+                    sound.clone(),
+                    PlaybackSettings::ONCE.with_volume(0.5 * settings.get_sfx_volume()),
+                )
             });
         } else {
             let sound = asset_server.load(format!("sounds/{}.ogg", item.obj));
-            audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5));
+            audio.play_with_settings(
+                sound.clone(),
+                PlaybackSettings::ONCE.with_volume(0.5 * settings.get_sfx_volume()),
+            );
         }
     }
 }
@@ -166,6 +188,7 @@ pub fn break_item_audio(
 pub fn hit_collision_audio(
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
     mut hit_events: EventReader<HitEvent>,
     world_objects: Query<&WorldObject>,
     mobs: Query<&Mob>,
@@ -173,10 +196,16 @@ pub fn hit_collision_audio(
     for hit in hit_events.iter() {
         if let Ok(obj) = world_objects.get(hit.hit_entity) {
             let sound = asset_server.load(format!("sounds/{}.ogg", obj));
-            audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5));
+            audio.play_with_settings(
+                sound.clone(),
+                PlaybackSettings::ONCE.with_volume(0.5 * settings.get_sfx_volume()),
+            );
         } else if let Ok(mob) = mobs.get(hit.hit_entity) {
             let sound = asset_server.load(format!("sounds/{}.ogg", mob));
-            audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5));
+            audio.play_with_settings(
+                sound.clone(),
+                PlaybackSettings::ONCE.with_volume(0.5 * settings.get_sfx_volume()),
+            );
         }
     }
 }
@@ -0,0 +1,309 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_rapier2d::prelude::PhysicsSet;
+use noise::{NoiseFn, Perlin};
+
+use crate::{
+    action_map::{ActionInput, InputAction},
+    attributes::MaxHealth,
+    combat::{HitEvent, InvincibilityTimer},
+    enemy::EliteMob,
+    item::{combat_shrine::CombatShrine, projectile::Projectile},
+    player::Player,
+    settings::Settings,
+    world::{dimension::ActiveDimension, dungeon::Dungeon},
+    GameState, GameUpscale, GameViewport, MainCamera, RawPosition, TextureCamera, UICamera,
+};
+
+/// How quickly the camera catches up to what it's following, per second
+const CAMERA_FOLLOW_SPEED: f32 = 4.;
+/// How many times bigger the world is drawn at each zoom level, cycled with `InputAction::Zoom`.
+/// Zooming out would show past the chunks loaded around the camera.
+const ZOOM_LEVELS: [f32; 2] = [1., 2.];
+const ZOOM_SPEED: f32 = 8.;
+
+/// How far the camera moves at full trauma, in game pixels
+const MAX_SHAKE_OFFSET: f32 = 6.;
+/// Trauma lost per second, so a full shake settles in under a second
+const TRAUMA_DECAY: f32 = 1.5;
+const SHAKE_FREQUENCY: f64 = 20.;
+/// Hits on the player that take at least this much of their max health shake the camera
+const BIG_HIT_FRACTION: f32 = 0.15;
+const EXPLOSION_TRAUMA: f32 = 0.4;
+const ELITE_ROAR_TRAUMA: f32 = 0.5;
+const SHRINE_TRAUMA: f32 = 0.3;
+
+/// Elites closer than this to the player get a look when they appear
+const ELITE_FOCUS_RANGE: f32 = 160.;
+const ELITE_FOCUS_SECS: f32 = 1.5;
+const SHRINE_FOCUS_SECS: f32 = 1.5;
+pub const DUNGEON_EXIT_FOCUS_SECS: f32 = 2.5;
+
+pub struct CameraPlugin;
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraController>()
+            .add_event::<CameraShakeEvent>()
+            .add_event::<CameraFocusEvent>()
+            .add_system(reset_camera_effects.in_schedule(OnEnter(GameState::Main)))
+            .add_systems(
+                (
+                    handle_zoom_input,
+                    shake_on_big_hits,
+                    shake_on_explosions,
+                    focus_on_nearby_elites,
+                    focus_on_activated_shrines,
+                    handle_camera_events,
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            )
+            .add_system(
+                move_camera_with_player
+                    .after(PhysicsSet::SyncBackendFlush)
+                    .before(TransformSystem::TransformPropagate)
+                    .in_base_set(CoreSet::PostUpdate)
+                    .run_if(in_state(GameState::Main)),
+            );
+    }
+}
+
+/// Adds trauma to the camera. It shakes by the square of its trauma,
+/// so small hits barely move it, and big ones stack up to a full shake.
+pub struct CameraShakeEvent(pub f32);
+
+/// Pans the camera to a point of interest for a moment, then back to the player
+pub struct CameraFocusEvent {
+    pub pos: Vec2,
+    pub secs: f32,
+}
+
+/// What the game camera is doing on top of following the player
+#[derive(Resource)]
+pub struct CameraController {
+    /// Index into `ZOOM_LEVELS`, kept between runs
+    pub zoom_level: usize,
+    /// Eases towards the zoom level, so changing it isn't a jump cut
+    zoom: f32,
+    /// From 0 to 1, decaying over time
+    trauma: f32,
+    focus: Option<(Vec2, Timer)>,
+    noise: Perlin,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            zoom_level: 0,
+            zoom: ZOOM_LEVELS[0],
+            trauma: 0.,
+            focus: None,
+            noise: Perlin::new(0),
+        }
+    }
+}
+
+impl CameraController {
+    /// How much of the world is on screen at the current zoom, in game pixels
+    pub fn get_view_size(&self, viewport: &GameViewport) -> Vec2 {
+        viewport.size / self.zoom
+    }
+    /// Smooth noise rather than random jumps, so the shake reads as a rumble
+    fn get_shake_offset(&self, elapsed_secs: f64) -> Vec2 {
+        let shake = self.trauma * self.trauma;
+        if shake == 0. {
+            return Vec2::ZERO;
+        }
+        let t = elapsed_secs * SHAKE_FREQUENCY;
+        Vec2::new(
+            self.noise.get([t, 0.]) as f32,
+            self.noise.get([0., t]) as f32,
+        ) * MAX_SHAKE_OFFSET
+            * shake
+    }
+}
+
+fn reset_camera_effects(mut camera: ResMut<CameraController>) {
+    camera.trauma = 0.;
+    camera.focus = None;
+}
+
+fn handle_zoom_input(actions: ActionInput, mut camera: ResMut<CameraController>) {
+    if actions.just_pressed(InputAction::Zoom) {
+        camera.zoom_level = (camera.zoom_level + 1) % ZOOM_LEVELS.len();
+    }
+}
+
+fn handle_camera_events(
+    mut shake_events: EventReader<CameraShakeEvent>,
+    mut focus_events: EventReader<CameraFocusEvent>,
+    mut camera: ResMut<CameraController>,
+    settings: Res<Settings>,
+) {
+    for shake in shake_events.iter() {
+        if settings.screen_shake {
+            camera.trauma = (camera.trauma + shake.0).min(1.);
+        }
+    }
+    // a newer point of interest takes over from the one being looked at
+    for focus in focus_events.iter() {
+        camera.focus = Some((focus.pos, Timer::from_seconds(focus.secs, TimerMode::Once)));
+    }
+}
+
+fn shake_on_big_hits(
+    mut hits: EventReader<HitEvent>,
+    // hits during invincibility frames are ignored by handle_hits
+    player: Query<&MaxHealth, (With<Player>, Without<InvincibilityTimer>)>,
+    mut shake_event: EventWriter<CameraShakeEvent>,
+) {
+    for hit in hits.iter() {
+        let Ok(max_health) = player.get(hit.hit_entity) else {
+            continue;
+        };
+        let fraction = hit.damage as f32 / max_health.0.max(1) as f32;
+        if fraction >= BIG_HIT_FRACTION {
+            shake_event.send(CameraShakeEvent(fraction * 2.));
+        }
+    }
+}
+
+fn shake_on_explosions(
+    new_projectiles: Query<&Projectile, Added<Projectile>>,
+    mut shake_event: EventWriter<CameraShakeEvent>,
+) {
+    for projectile in new_projectiles.iter() {
+        if projectile == &Projectile::FireExplosionAOE {
+            shake_event.send(CameraShakeEvent(EXPLOSION_TRAUMA));
+        }
+    }
+}
+
+/// Elites are the closest thing to a boss, so one showing up nearby roars and gets a look
+fn focus_on_nearby_elites(
+    new_elites: Query<&Transform, Added<EliteMob>>,
+    player: Query<&GlobalTransform, With<Player>>,
+    mut shake_event: EventWriter<CameraShakeEvent>,
+    mut focus_event: EventWriter<CameraFocusEvent>,
+) {
+    let Ok(player_t) = player.get_single() else {
+        return;
+    };
+    let player_pos = player_t.translation().truncate();
+    for elite_t in new_elites.iter() {
+        let pos = elite_t.translation.truncate();
+        if pos.distance(player_pos) > ELITE_FOCUS_RANGE {
+            continue;
+        }
+        shake_event.send(CameraShakeEvent(ELITE_ROAR_TRAUMA));
+        focus_event.send(CameraFocusEvent {
+            pos,
+            secs: ELITE_FOCUS_SECS,
+        });
+    }
+}
+
+fn focus_on_activated_shrines(
+    activated_shrines: Query<&GlobalTransform, Added<CombatShrine>>,
+    mut shake_event: EventWriter<CameraShakeEvent>,
+    mut focus_event: EventWriter<CameraFocusEvent>,
+) {
+    for shrine_t in activated_shrines.iter() {
+        shake_event.send(CameraShakeEvent(SHRINE_TRAUMA));
+        focus_event.send(CameraFocusEvent {
+            pos: shrine_t.translation().truncate(),
+            secs: SHRINE_FOCUS_SECS,
+        });
+    }
+}
+
+/// Keeps the view inside `bounds`, centring it on any axis the bounds are smaller than
+fn clamp_to_bounds(pos: Vec2, bounds: Rect, view_size: Vec2) -> Vec2 {
+    let min = bounds.min + view_size / 2.;
+    let max = bounds.max - view_size / 2.;
+    let center = bounds.center();
+    Vec2::new(
+        if min.x < max.x {
+            pos.x.clamp(min.x, max.x)
+        } else {
+            center.x
+        },
+        if min.y < max.y {
+            pos.y.clamp(min.y, max.y)
+        } else {
+            center.y
+        },
+    )
+}
+
+/// Follows the player, or a point of interest while focused on one, staying inside the
+/// dungeon's edges. The game camera only moves by whole pixels, and the screen camera
+/// makes up the rest, so the pixel art doesn't shimmer.
+pub fn move_camera_with_player(
+    player_query: Query<
+        &RawPosition,
+        (
+            With<Player>,
+            Without<MainCamera>,
+            Without<TextureCamera>,
+            Without<UICamera>,
+        ),
+    >,
+    mut game_camera: Query<
+        (
+            &mut Transform,
+            &mut RawPosition,
+            &mut OrthographicProjection,
+        ),
+        (Without<MainCamera>, Without<UICamera>, With<TextureCamera>),
+    >,
+    mut screen_camera: Query<
+        (&mut Transform, &GameUpscale),
+        (With<MainCamera>, Without<UICamera>, Without<TextureCamera>),
+    >,
+    mut camera: ResMut<CameraController>,
+    dungeon: Query<&Dungeon, With<ActiveDimension>>,
+    viewport: Res<GameViewport>,
+    time: Res<Time>,
+) {
+    let camera = &mut *camera;
+    let (mut game_camera_transform, mut raw_camera_pos, mut projection) = game_camera.single_mut();
+    let raw_player_pos = player_query.single();
+    let dt = time.delta_seconds();
+
+    let target_zoom = ZOOM_LEVELS[camera.zoom_level];
+    camera.zoom += (target_zoom - camera.zoom) * (ZOOM_SPEED * dt).min(1.);
+    projection.scale = 1. / camera.zoom;
+
+    let mut target = raw_player_pos.0;
+    if let Some((pos, timer)) = camera.focus.as_mut() {
+        target = *pos;
+        if timer.tick(time.delta()).finished() {
+            camera.focus = None;
+        }
+    }
+    let delta = target - raw_camera_pos.0;
+    raw_camera_pos.0 += delta * CAMERA_FOLLOW_SPEED * dt;
+    if let Ok(dungeon) = dungeon.get_single() {
+        raw_camera_pos.0 = clamp_to_bounds(
+            raw_camera_pos.0,
+            dungeon.get_world_bounds(),
+            camera.get_view_size(&viewport),
+        );
+    }
+
+    camera.trauma = (camera.trauma - TRAUMA_DECAY * dt).max(0.);
+    let camera_final_pos = raw_camera_pos.0 + camera.get_shake_offset(time.elapsed_seconds_f64());
+
+    let decimals = 10i32.pow(3) as f32;
+    let camera_final_pos = Vec2::new(
+        (camera_final_pos.x * decimals).round() / decimals,
+        (camera_final_pos.y * decimals).round() / decimals,
+    );
+
+    game_camera_transform.translation.x = camera_final_pos.x.trunc();
+    game_camera_transform.translation.y = camera_final_pos.y.trunc();
+    // the leftover part of a pixel is drawn bigger when zoomed in
+    let (mut screen_camera_transform, game_upscale) = screen_camera.single_mut();
+    let pixel_scale = game_upscale.0 * camera.zoom;
+    screen_camera_transform.translation.x = camera_final_pos.x.fract() * pixel_scale;
+    screen_camera_transform.translation.y = camera_final_pos.y.fract() * pixel_scale;
+}
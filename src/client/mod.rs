@@ -1,4 +1,8 @@
-use std::{fs::File, io::BufReader};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
 
 use bevy::{math::Vec3Swizzles, prelude::*, utils::HashMap};
 use bevy_ecs_tilemap::{
@@ -18,6 +22,11 @@ use crate::{
     assets::SpriteAnchor,
     attributes::{hunger::Hunger, CurrentHealth},
     container::{Container, ContainerRegistry},
+    enemy::{
+        companion::Companion,
+        passive::{Baby, Tamed, TamedMobData, TamedMobRegistry},
+        Mob,
+    },
     inventory::{Inventory, ItemStack},
     item::{
         projectile::Projectile, CraftingTracker, EquipmentType, Foliage, MainHand, Wall,
@@ -29,6 +38,7 @@ use crate::{
         stats::{PlayerStats, SkillPoints},
         Player,
     },
+    playtest::PLAYTEST_DIR,
     proto::proto_param::ProtoParam,
     ui::{ChestContainer, FurnaceContainer},
     vectorize::vectorize,
@@ -40,8 +50,27 @@ use crate::{
         TileMapPosition, WallTextureData, WorldGeneration,
     },
     CustomFlush, GameParam, GameState, MainCamera, RawPosition, TextureCamera, UICamera, YSort,
+    PLAYTEST,
 };
 
+/// The current run, removed when it ends
+pub const SAVE_PATH: &str = "save_state.json";
+/// Stats kept across runs, and gear seen in them for the essence shop
+pub const GAME_DATA_PATH: &str = "game_data.json";
+
+/// Playtests keep their saves and run history in their own folder, away from the player's
+pub fn get_data_path(file: &str) -> PathBuf {
+    if PLAYTEST.is_some() {
+        Path::new(PLAYTEST_DIR).join(file)
+    } else {
+        PathBuf::from(file)
+    }
+}
+
+/// Starts the next run on this seed instead of a random one
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct NextRunSeed(pub u64);
+
 #[derive(Component, Reflect, Default)]
 #[reflect(Component)]
 pub struct ColliderReflect {
@@ -108,11 +137,14 @@ impl Plugin for ClientPlugin {
             .insert_resource(CurrentRunSaveData::default())
             .insert_resource(SaveTimer {
                 timer: Timer::from_seconds(15., TimerMode::Repeating),
+                save_requested: false,
+                save_written: false,
             })
             .add_system(load_state.in_schedule(OnExit(GameState::MainMenu)))
-            .add_systems(
-                (save_state, handle_append_run_data_after_death).in_set(OnUpdate(GameState::Main)),
-            )
+            // not paused with the rest of the game, so the pause menu can save before quitting.
+            // the timer does not tick while paused, since `Time` is paused too
+            .add_system(save_state.run_if(in_state(GameState::Main)))
+            .add_system(handle_append_run_data_after_death.in_set(OnUpdate(GameState::Main)))
             .add_system(apply_system_buffers.in_set(CustomFlush));
     }
 }
@@ -128,6 +160,8 @@ pub struct CurrentRunSaveData {
     container_reg: HashMap<TileMapPosition, Container>,
     craft_tracker: CraftingTracker,
     night_tracker: NightTracker,
+    #[serde(default)]
+    tamed_mobs: Vec<TamedMobData>,
 
     //Player Data
     pub inventory: Inventory,
@@ -145,6 +179,20 @@ pub struct GameOverEvent;
 #[derive(Resource, Default)]
 pub struct SaveTimer {
     timer: Timer,
+    save_requested: bool,
+    /// If the last update wrote the save file
+    save_written: bool,
+}
+
+impl SaveTimer {
+    /// Saves on the next update instead of waiting for the timer,
+    /// unless the player is in a dungeon
+    pub fn request_save(&mut self) {
+        self.save_requested = true;
+    }
+    pub fn was_save_written(&self) -> bool {
+        self.save_written
+    }
 }
 
 #[derive(Resource, Clone, Serialize, Deserialize, Default)]
@@ -165,7 +213,7 @@ pub fn handle_append_run_data_after_death(
     println!("GAME OVER! Storing run data in game_data.json...");
     let mut game_data: GameData = GameData::default();
 
-    if let Ok(file_file) = File::open("game_data.json") {
+    if let Ok(file_file) = File::open(get_data_path(GAME_DATA_PATH)) {
         let reader = BufReader::new(file_file);
 
         // Read the JSON contents of the file as an instance of `User`.
@@ -194,9 +242,8 @@ pub fn handle_append_run_data_after_death(
         game_data.seen_gear.push(item.item_stack.clone());
     }
 
-    const PATH: &str = "game_data.json";
-
-    let file = File::create(PATH).expect("Could not create game data file for serialization");
+    let file = File::create(get_data_path(GAME_DATA_PATH))
+        .expect("Could not create game data file for serialization");
 
     // let json_Data: String = serde_json::to_string(&save_data).unwrap();
     if let Err(result) = serde_json::to_writer(file, &game_data.clone()) {
@@ -238,10 +285,14 @@ pub fn save_state(
     check_open_chest: Option<Res<ChestContainer>>,
     check_open_furnace: Option<Res<FurnaceContainer>>,
     key_input: ResMut<Input<KeyCode>>,
+    tamed_mobs: Query<(&Mob, &Tamed, Option<&Baby>, Option<&Companion>)>,
+    tamed_registry: Res<TamedMobRegistry>,
 ) {
     timer.timer.tick(time.delta());
-    // only save if the timer is done and we are not in a dungeon
-    if (!timer.timer.just_finished() || dungeon_check.get_single().is_ok())
+    timer.save_written = false;
+    let save_requested = std::mem::take(&mut timer.save_requested);
+    // only save if the timer is done or a save was requested, and we are not in a dungeon
+    if (!(timer.timer.just_finished() || save_requested) || dungeon_check.get_single().is_ok())
         && !key_input.just_pressed(KeyCode::U)
     {
         return;
@@ -319,16 +370,21 @@ pub fn save_state(
     save_data.container_reg = container_reg.containers.clone();
     save_data.night_tracker = night_tracker.clone();
     save_data.seed = seed.seed;
+    let mut all_tamed_mobs = tamed_registry.clone();
+    for (mob, tamed, baby_option, companion_option) in tamed_mobs.iter() {
+        all_tamed_mobs.store(TamedMobData::new(mob, tamed, baby_option, companion_option));
+    }
+    save_data.tamed_mobs = all_tamed_mobs.mobs;
 
-    const PATH: &str = "save_state.json";
-
-    let file = File::create(PATH).expect("Could not open file for serialization");
+    let file =
+        File::create(get_data_path(SAVE_PATH)).expect("Could not open file for serialization");
 
     // let json_Data: String = serde_json::to_string(&save_data).unwrap();
     if let Err(result) = serde_json::to_writer(file, &save_data.clone()) {
         println!("Failed to save game state: {result:?}");
     } else {
         println!("SAVED GAME STATE!");
+        timer.save_written = true;
     }
 }
 
@@ -340,12 +396,13 @@ pub fn load_state(
         (&mut Transform, &mut RawPosition),
         (Without<MainCamera>, Without<UICamera>, With<TextureCamera>),
     >,
+    next_run_seed: Option<Res<NextRunSeed>>,
 ) {
     let mut rng = rand::thread_rng();
     let mut seed = rng.gen_range(0..100000);
 
     // Load data if it exists
-    if let Ok(file_file) = File::open("save_state.json") {
+    if let Ok(file_file) = File::open(get_data_path(SAVE_PATH)) {
         let reader = BufReader::new(file_file);
 
         // Read the JSON contents of the file as an instance of `User`.
@@ -363,6 +420,9 @@ pub fn load_state(
                     containers: data.containers,
                 });
                 commands.insert_resource(data.craft_tracker);
+                commands.insert_resource(TamedMobRegistry {
+                    mobs: data.tamed_mobs,
+                });
 
                 // PRE-MOVE CAMERAS TO PLAYER
                 let (mut game_camera_transform, mut raw_camera_pos) = game_camera.single_mut();
@@ -374,6 +434,10 @@ pub fn load_state(
             Err(err) => println!("Failed to load data from file {err:?}"),
         }
     }
+    if let Some(next_run_seed) = next_run_seed {
+        seed = next_run_seed.0;
+        commands.remove_resource::<NextRunSeed>();
+    }
     commands.insert_resource(GenerationSeed { seed });
 
     dim_event.send(DimensionSpawnEvent {
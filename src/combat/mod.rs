@@ -56,6 +56,9 @@ pub struct AttackTimer(pub Timer);
 
 #[derive(Component, Debug, Clone)]
 pub struct InvincibilityTimer(pub Timer);
+/// Ignores all hits, toggled with the dev console's `god` command
+#[derive(Component, Debug, Clone)]
+pub struct GodMode;
 #[derive(Component, Debug, Clone)]
 
 pub struct HitMarker;
@@ -109,7 +112,7 @@ pub fn handle_attack_cooldowns(
         }
     }
 }
-fn handle_enemy_death(
+pub fn handle_enemy_death(
     _commands: Commands,
     proto_param: ProtoParam,
     mut death_events: EventReader<EnemyDeathEvent>,
@@ -164,7 +167,7 @@ fn handle_enemy_death(
         player_level.add_xp(mob_xp.0);
     }
 }
-fn handle_invincibility_frames(
+pub fn handle_invincibility_frames(
     mut commands: Commands,
     mut i_frames: Query<(Entity, &mut InvincibilityTimer)>,
     time: Res<Time>,
@@ -240,6 +243,7 @@ pub fn handle_hits(
     mut shrine_mob_death_event: EventWriter<CombatShrineMobDeathEvent>,
     mut obj_death_events: EventWriter<ObjBreakEvent>,
     in_i_frame: Query<&InvincibilityTimer>,
+    in_god_mode: Query<(), With<GodMode>>,
     proto_param: ProtoParam,
 ) {
     for hit in hit_events.iter() {
@@ -247,6 +251,9 @@ pub fn handle_hits(
         if in_i_frame.get(hit.hit_entity).is_ok() {
             return;
         }
+        if in_god_mode.contains(hit.hit_entity) {
+            continue;
+        }
         if let Ok((
             e,
             mut hit_health,
@@ -264,8 +264,8 @@ impl Container {
                 .get_slot_for_item_in_container(&item)
                 .expect("player crafted item but does not have the required ingredients?");
             let stack = self.items[ingredient_slot].as_mut().unwrap();
-            if stack.item_stack.count >= amount {
-                self.items[ingredient_slot] = stack.modify_count(-(amount as i8));
+            if stack.item_stack.count >= remaining_cost {
+                self.items[ingredient_slot] = stack.modify_count(-(remaining_cost as i8));
                 remaining_cost = 0 as usize;
             } else {
                 let count = stack.item_stack.count;
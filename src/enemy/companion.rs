@@ -0,0 +1,564 @@
+use bevy::prelude::*;
+use bevy_proto::prelude::{ProtoCommands, Prototypes};
+use bevy_rapier2d::prelude::KinematicCharacterController;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action_map::{ActionInput, InputAction},
+    ai::{
+        FleeState, FollowState, IdleState, LeapAttackState, MobBehaviour, MobStateProto,
+        MobStateType, MobTarget, MobTransition, MobTrigger, ProjectileAttackState,
+    },
+    animations::enemy_sprites::EnemyAnimationState,
+    colors::{LIGHT_GREEN, YELLOW},
+    combat::{EnemyDeathEvent, HitEvent},
+    inputs::{CursorPos, FacingDirection},
+    inventory::{Inventory, InventoryItemStack, ItemStack},
+    player::levels::ExperienceReward,
+    proto::proto_param::ProtoParam,
+    ui::{damage_numbers::spawn_floating_text_with_shadow, InventoryState, UIState},
+    world::world_helpers::world_pos_to_tile_pos,
+    Game, PLAYER_MOVE_SPEED,
+};
+
+use super::{passive::Tamed, CombatAlignment, Mob};
+
+/// Orders the player can give their companion with the companion hotkey
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompanionCommand {
+    #[default]
+    Follow,
+    Stay,
+    Guard,
+}
+
+impl CompanionCommand {
+    pub fn next(&self) -> Self {
+        match self {
+            CompanionCommand::Follow => CompanionCommand::Stay,
+            CompanionCommand::Stay => CompanionCommand::Guard,
+            CompanionCommand::Guard => CompanionCommand::Follow,
+        }
+    }
+}
+
+/// A tamed mob that fights alongside the player.
+/// Only one companion can be recruited at a time.
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+pub struct Companion {
+    pub command: CompanionCommand,
+    pub guard_pos: Vec2,
+    pub level: u8,
+    pub xp: u32,
+    pub next_level_xp: u32,
+    pub equipment: Option<ItemStack>,
+}
+
+impl Default for Companion {
+    fn default() -> Self {
+        Self {
+            command: CompanionCommand::Follow,
+            guard_pos: Vec2::ZERO,
+            level: 1,
+            xp: 0,
+            next_level_xp: COMPANION_BASE_LEVEL_EXP_REQ,
+            equipment: None,
+        }
+    }
+}
+
+impl Companion {
+    pub fn get_damage(&self) -> i32 {
+        COMPANION_BASE_DAMAGE
+            + self.level as i32
+            + self
+                .equipment
+                .as_ref()
+                .map(|e| e.attributes.attack)
+                .unwrap_or(0)
+    }
+    /// Returns true if the companion levelled up
+    pub fn add_xp(&mut self, xp: u32) -> bool {
+        self.xp += xp;
+        if self.xp >= self.next_level_xp {
+            self.level += 1;
+            self.xp -= self.next_level_xp;
+            self.next_level_xp = COMPANION_BASE_LEVEL_EXP_REQ * self.level as u32;
+            return true;
+        }
+        false
+    }
+    /// The transitions for the companion's current command. Following companions
+    /// wander near the player once they catch up, and both following and guarding
+    /// companions break off to fight whatever `pick_companion_targets` finds.
+    pub fn get_behaviour(&self) -> MobBehaviour {
+        let follow = MobStateProto::Follow {
+            speed: COMPANION_SPEED,
+        };
+        let attack = MobStateProto::CompanionAttack {
+            cooldown: COMPANION_ATTACK_COOLDOWN,
+        };
+        let (initial_state, transitions) = match self.command {
+            CompanionCommand::Follow => (
+                follow.clone(),
+                vec![
+                    MobTransition {
+                        from: MobStateType::Follow,
+                        trigger: MobTrigger::HasTarget,
+                        to: attack.clone(),
+                    },
+                    MobTransition {
+                        from: MobStateType::Idle,
+                        trigger: MobTrigger::HasTarget,
+                        to: attack,
+                    },
+                    MobTransition {
+                        from: MobStateType::CompanionAttack,
+                        trigger: MobTrigger::LostTarget,
+                        to: follow.clone(),
+                    },
+                    MobTransition {
+                        from: MobStateType::Follow,
+                        trigger: MobTrigger::AttackDistance(FOLLOW_STOP_DISTANCE),
+                        to: MobStateProto::default(),
+                    },
+                    MobTransition {
+                        from: MobStateType::Idle,
+                        trigger: MobTrigger::OutOfAttackDistance(FOLLOW_START_DISTANCE),
+                        to: follow,
+                    },
+                ],
+            ),
+            CompanionCommand::Stay => (
+                MobStateProto::Guard {
+                    radius: STAY_RADIUS,
+                },
+                vec![],
+            ),
+            CompanionCommand::Guard => {
+                let guard = MobStateProto::Guard {
+                    radius: GUARD_RADIUS,
+                };
+                (
+                    guard.clone(),
+                    vec![
+                        MobTransition {
+                            from: MobStateType::Guard,
+                            trigger: MobTrigger::HasTarget,
+                            to: attack,
+                        },
+                        MobTransition {
+                            from: MobStateType::CompanionAttack,
+                            trigger: MobTrigger::LostTarget,
+                            to: guard,
+                        },
+                    ],
+                )
+            }
+        };
+        MobBehaviour {
+            initial_state: Some(initial_state),
+            transitions,
+            hostile_transitions: None,
+        }
+    }
+}
+
+/// Companions move towards their [MobTarget] and hit it with a [HitEvent] once in range
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct CompanionAttackState {
+    pub attack_cooldown_timer: Timer,
+}
+
+/// Companions told to stay or guard walk back to their guard position
+/// if they stray further than `radius`
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct GuardState {
+    pub radius: f32,
+}
+
+/// Spawns an already recruited companion, sent when the player uses an item like the golem core
+pub struct SummonCompanionEvent {
+    pub mob: Mob,
+    pub pos: Vec2,
+}
+
+/// Marks a mob as last hit by the companion, so it gets the xp if the mob dies
+#[derive(Component)]
+pub struct LastHitByCompanion(pub Entity);
+
+/// A companion summoned by an item ability, it leaves once the timer runs out.
+/// They don't take up the player's companion spot and can't be given orders.
+#[derive(Component)]
+pub struct TemporaryAlly(pub Timer);
+
+pub const COMPANION_BASE_LEVEL_EXP_REQ: u32 = 60;
+const COMPANION_BASE_DAMAGE: i32 = 2;
+const COMPANION_SPEED: f32 = 0.9;
+const COMPANION_ATTACK_RANGE: f32 = 14.;
+const COMPANION_ATTACK_COOLDOWN: f32 = 0.8;
+const COMPANION_AGGRO_RANGE: f32 = 96.;
+const GUARD_RADIUS: f32 = 64.;
+const STAY_RADIUS: f32 = 4.;
+const FOLLOW_START_DISTANCE: f32 = 48.;
+const FOLLOW_STOP_DISTANCE: f32 = 20.;
+
+/// Swaps the mob's state machine for the one for the companion's current command
+fn apply_companion_behaviour(
+    e_cmds: &mut bevy::ecs::system::EntityCommands,
+    companion: &Companion,
+    player: Entity,
+) {
+    e_cmds
+        .remove::<IdleState>()
+        .remove::<FollowState>()
+        .remove::<FleeState>()
+        .remove::<LeapAttackState>()
+        .remove::<ProjectileAttackState>()
+        .remove::<GuardState>()
+        .remove::<CompanionAttackState>();
+    let behaviour = companion.get_behaviour();
+    if let Some(initial_state) = &behaviour.initial_state {
+        initial_state.insert_state(e_cmds, player);
+    }
+    e_cmds.insert((
+        behaviour.build_state_machine(player, &CombatAlignment::Passive),
+        behaviour,
+    ));
+}
+
+/// Recruits the closest tamed mob as a companion if there are none,
+/// otherwise cycles the command of the companion closest to the player
+pub fn handle_companion_command_input(
+    mut commands: Commands,
+    actions: ActionInput,
+    ui_state: Res<State<UIState>>,
+    game: Res<Game>,
+    txfms: Query<&GlobalTransform>,
+    mut companions: Query<(Entity, &mut Companion, &GlobalTransform), Without<TemporaryAlly>>,
+    tamed_mobs: Query<(Entity, &GlobalTransform), (With<Tamed>, Without<Companion>)>,
+    asset_server: Res<AssetServer>,
+) {
+    if !actions.just_pressed(InputAction::CompanionCommand) || ui_state.0 != UIState::Closed {
+        return;
+    }
+    let Ok(player_t) = txfms.get(game.player) else {
+        return;
+    };
+    let player_pos = player_t.translation().truncate();
+    if !companions.is_empty() {
+        let Some((e, mut companion, companion_t)) =
+            companions.iter_mut().min_by(|(_, _, t1), (_, _, t2)| {
+                t1.translation()
+                    .truncate()
+                    .distance(player_pos)
+                    .total_cmp(&t2.translation().truncate().distance(player_pos))
+            })
+        else {
+            return;
+        };
+        companion.command = companion.command.next();
+        companion.guard_pos = companion_t.translation().truncate();
+        apply_companion_behaviour(&mut commands.entity(e), &companion, game.player);
+        spawn_floating_text_with_shadow(
+            &mut commands,
+            &asset_server,
+            companion_t.translation() + Vec3::new(0., 10., 0.),
+            YELLOW,
+            format!("{:?}", companion.command),
+        );
+        return;
+    }
+    if let Some((e, t)) = tamed_mobs
+        .iter()
+        .filter(|(_, t)| t.translation().truncate().distance(player_pos) <= 32.)
+        .min_by(|(_, t1), (_, t2)| {
+            t1.translation()
+                .truncate()
+                .distance(player_pos)
+                .total_cmp(&t2.translation().truncate().distance(player_pos))
+        })
+    {
+        commands.entity(e).insert(Companion::default());
+        spawn_floating_text_with_shadow(
+            &mut commands,
+            &asset_server,
+            t.translation() + Vec3::new(0., 10., 0.),
+            YELLOW,
+            "Companion!".to_string(),
+        );
+    }
+}
+
+/// Companions swap the mob's regular behaviour for one that follows the player's orders
+pub fn handle_new_companion(
+    mut commands: Commands,
+    game: Res<Game>,
+    new_companions: Query<(Entity, &Companion), Added<Companion>>,
+) {
+    for (e, companion) in new_companions.iter() {
+        let mut e_cmds = commands.entity(e);
+        e_cmds.insert(MobTarget::default());
+        apply_companion_behaviour(&mut e_cmds, companion, game.player);
+    }
+}
+
+/// Keeps each companion's [MobTarget] up to date. Following companions go after mobs
+/// attacking the player, guarding ones after anything that isn't passive near their post.
+pub fn pick_companion_targets(
+    game: Res<Game>,
+    txfms: Query<&GlobalTransform>,
+    mut companions: Query<(&Companion, &mut MobTarget)>,
+    enemies: Query<
+        (
+            Entity,
+            &CombatAlignment,
+            Option<&FollowState>,
+            Option<&LeapAttackState>,
+            Option<&ProjectileAttackState>,
+        ),
+        (With<Mob>, Without<Companion>),
+    >,
+) {
+    let Ok(player_t) = txfms.get(game.player) else {
+        return;
+    };
+    let player_pos = player_t.translation().truncate();
+    let distance_to = |e: Entity, pos: Vec2| {
+        txfms
+            .get(e)
+            .map(|t| t.translation().truncate().distance(pos))
+            .unwrap_or(f32::MAX)
+    };
+    for (companion, mut target) in companions.iter_mut() {
+        let anchor = if companion.command == CompanionCommand::Follow {
+            player_pos
+        } else {
+            companion.guard_pos
+        };
+        let keep_target = target
+            .0
+            .map(|e| distance_to(e, anchor) <= COMPANION_AGGRO_RANGE * 1.5)
+            .unwrap_or(false);
+        if keep_target && companion.command != CompanionCommand::Stay {
+            continue;
+        }
+        let threat = match companion.command {
+            CompanionCommand::Follow => enemies.iter().find(|(enemy, _, follow, leap, proj)| {
+                let targets_player = follow.map(|s| s.target == game.player).unwrap_or(false)
+                    || leap.map(|s| s.target == game.player).unwrap_or(false)
+                    || proj.map(|s| s.target == game.player).unwrap_or(false);
+                targets_player && distance_to(*enemy, anchor) <= COMPANION_AGGRO_RANGE
+            }),
+            CompanionCommand::Guard => enemies.iter().find(|(enemy, alignment, ..)| {
+                **alignment != CombatAlignment::Passive
+                    && distance_to(*enemy, anchor) <= GUARD_RADIUS
+            }),
+            CompanionCommand::Stay => None,
+        };
+        let new_target = threat.map(|(enemy, ..)| enemy);
+        if target.0 != new_target {
+            target.0 = new_target;
+        }
+    }
+}
+
+/// Crafted companions count as tamed, with their home where they were built,
+/// so they are kept across dimensions like recruited tamed mobs
+pub fn handle_summon_companion(
+    mut commands: Commands,
+    mut summon_events: EventReader<SummonCompanionEvent>,
+    mut proto_commands: ProtoCommands,
+    prototypes: Prototypes,
+    asset_server: Res<AssetServer>,
+) {
+    for summon in summon_events.iter() {
+        let Some(mob_e) =
+            proto_commands.spawn_from_proto(summon.mob.clone(), &prototypes, summon.pos)
+        else {
+            continue;
+        };
+        commands.entity(mob_e).insert((
+            Tamed::new(world_pos_to_tile_pos(summon.pos), GUARD_RADIUS),
+            Companion {
+                guard_pos: summon.pos,
+                ..default()
+            },
+        ));
+        spawn_floating_text_with_shadow(
+            &mut commands,
+            &asset_server,
+            summon.pos.extend(0.) + Vec3::new(0., 10., 0.),
+            YELLOW,
+            "Companion!".to_string(),
+        );
+    }
+}
+
+pub fn companion_attack(
+    mut commands: Commands,
+    txfms: Query<&GlobalTransform>,
+    mut attackers: Query<(
+        Entity,
+        &Companion,
+        &MobTarget,
+        &mut CompanionAttackState,
+        &mut KinematicCharacterController,
+    )>,
+    mut hit_event: EventWriter<HitEvent>,
+    time: Res<Time>,
+) {
+    for (e, companion, target, mut attack, mut kcc) in attackers.iter_mut() {
+        attack.attack_cooldown_timer.tick(time.delta());
+        let Some(target) = target.0 else {
+            continue;
+        };
+        let (Ok(companion_t), Ok(target_t)) = (txfms.get(e), txfms.get(target)) else {
+            continue;
+        };
+        let delta = target_t.translation().truncate() - companion_t.translation().truncate();
+        let dir = delta.normalize_or_zero();
+        commands
+            .entity(e)
+            .insert(FacingDirection::from_translation(dir));
+        if delta.length() > COMPANION_ATTACK_RANGE {
+            kcc.translation =
+                Some(dir * COMPANION_SPEED * PLAYER_MOVE_SPEED * time.delta_seconds());
+            continue;
+        }
+        if attack.attack_cooldown_timer.finished() {
+            hit_event.send(HitEvent {
+                hit_entity: target,
+                damage: companion.get_damage(),
+                dir,
+                hit_with_melee: None,
+                hit_with_projectile: None,
+            });
+            commands.entity(target).insert(LastHitByCompanion(e));
+            attack.attack_cooldown_timer.reset();
+        }
+    }
+}
+
+pub fn guard(
+    txfms: Query<&GlobalTransform>,
+    mut guards: Query<(
+        Entity,
+        &Companion,
+        &GuardState,
+        &mut KinematicCharacterController,
+        &EnemyAnimationState,
+    )>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (e, companion, guard, mut kcc, anim_state) in guards.iter_mut() {
+        let Ok(t) = txfms.get(e) else {
+            continue;
+        };
+        let delta = companion.guard_pos - t.translation().truncate();
+        if delta.length() <= guard.radius {
+            if anim_state == &EnemyAnimationState::Walk {
+                commands.entity(e).insert(EnemyAnimationState::Idle);
+            }
+            continue;
+        }
+        let dir = delta.normalize_or_zero();
+        kcc.translation = Some(dir * COMPANION_SPEED * PLAYER_MOVE_SPEED * time.delta_seconds());
+        commands
+            .entity(e)
+            .insert(FacingDirection::from_translation(dir));
+        if anim_state == &EnemyAnimationState::Idle {
+            commands.entity(e).insert(EnemyAnimationState::Walk);
+        }
+    }
+}
+
+pub fn give_companion_xp_on_kill(
+    mut commands: Commands,
+    mut death_events: EventReader<EnemyDeathEvent>,
+    killed_by: Query<(&LastHitByCompanion, &ExperienceReward)>,
+    mut companions: Query<(&mut Companion, &GlobalTransform)>,
+    asset_server: Res<AssetServer>,
+) {
+    for death in death_events.iter() {
+        let Ok((last_hit, xp)) = killed_by.get(death.entity) else {
+            continue;
+        };
+        let Ok((mut companion, t)) = companions.get_mut(last_hit.0) else {
+            continue;
+        };
+        if companion.add_xp(xp.0) {
+            spawn_floating_text_with_shadow(
+                &mut commands,
+                &asset_server,
+                t.translation() + Vec3::new(0., 10., 0.),
+                LIGHT_GREEN,
+                format!("Level {}!", companion.level),
+            );
+        }
+    }
+}
+
+/// Right clicking the companion with a piece of equipment swaps it with the companion's slot
+pub fn handle_give_companion_equipment(
+    mut commands: Commands,
+    actions: ActionInput,
+    cursor_pos: Res<CursorPos>,
+    ui_state: Res<State<UIState>>,
+    mut companions: Query<(&mut Companion, &GlobalTransform), Without<TemporaryAlly>>,
+    mut inv: Query<&mut Inventory>,
+    inv_state: Res<InventoryState>,
+    proto_param: ProtoParam,
+    asset_server: Res<AssetServer>,
+) {
+    if !actions.just_pressed(InputAction::UseItem) || ui_state.0 != UIState::Closed {
+        return;
+    }
+    let cursor = cursor_pos.world_coords.truncate();
+    let Some((mut companion, t)) = companions
+        .iter_mut()
+        .filter(|(_, t)| t.translation().truncate().distance(cursor) <= 12.)
+        .min_by(|(_, t1), (_, t2)| {
+            t1.translation()
+                .truncate()
+                .distance(cursor)
+                .total_cmp(&t2.translation().truncate().distance(cursor))
+        })
+    else {
+        return;
+    };
+    let hotbar_slot = inv_state.active_hotbar_slot;
+    let held_item_option = inv.single().items.items[hotbar_slot].clone();
+    let Some(held_item) = held_item_option else {
+        return;
+    };
+    if held_item.get_obj().get_equip_type(&proto_param).is_none() {
+        return;
+    }
+    let old_equipment = companion.equipment.replace(held_item.item_stack.clone());
+    inv.single_mut().items.items[hotbar_slot] =
+        old_equipment.map(|stack| InventoryItemStack::new(stack, hotbar_slot));
+    spawn_floating_text_with_shadow(
+        &mut commands,
+        &asset_server,
+        t.translation() + Vec3::new(0., 10., 0.),
+        YELLOW,
+        held_item.item_stack.metadata.name.clone(),
+    );
+}
+
+pub fn expire_temporary_allies(
+    mut commands: Commands,
+    mut allies: Query<(Entity, &mut TemporaryAlly)>,
+    time: Res<Time>,
+) {
+    for (e, mut ally) in allies.iter_mut() {
+        ally.0.tick(time.delta());
+        if ally.0.finished() {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
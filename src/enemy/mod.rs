@@ -5,30 +5,30 @@ use bevy::{
     sprite::{Material2d, Material2dPlugin},
 };
 use bevy_proto::prelude::{ReflectSchematic, Schematic};
-use seldom_state::prelude::{StateMachine, Trigger};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use strum_macros::{Display, EnumIter, IntoStaticStr};
 
 use crate::{
-    ai::{
-        AttackDistance, FollowState, HurtByPlayer, IdleState, LeapAttackState, LineOfSight,
-        NightTimeAggro, ProjectileAttackState,
-    },
-    attributes::{add_current_health_with_max_health, Attack, MaxHealth},
-    colors::{BLACK, DARK_GREEN, LIGHT_BROWN, LIGHT_GREEN, PINK, RED},
-    inputs::FacingDirection,
-    item::{projectile::Projectile, Loot, LootTable},
+    ai::MobBehaviour,
+    attributes::{add_current_health_with_max_health, gems::Gems, Attack, MaxHealth},
+    colors::{BLACK, DARK_GREEN, GREY, LIGHT_BROWN, LIGHT_GREEN, PINK, RED},
+    item::{Loot, LootTable},
     night::NightTracker,
     player::levels::ExperienceReward,
     ui::minimap::UpdateMiniMapEvent,
     world::{dungeon::Dungeon, TileMapPosition},
-    AppExt, GameParam, GameState,
+    AppExt, CustomFlush, GameParam, GameState,
 };
 
+pub mod companion;
+pub mod passive;
+pub mod raid;
 pub mod red_mushling;
 pub mod spawn_helpers;
 pub mod spawner;
-use self::spawner::SpawnerPlugin;
+use self::{raid::RaidPlugin, spawner::SpawnerPlugin};
+use companion::*;
+use passive::*;
 use red_mushling::*;
 
 pub struct EnemyPlugin;
@@ -39,9 +39,12 @@ impl Plugin for EnemyPlugin {
             .with_default_schedule(CoreSchedule::FixedUpdate, |app| {
                 app.add_event::<EnemySpawnEvent>();
             })
+            .add_event::<FeedMobEvent>()
+            .add_event::<SummonCompanionEvent>()
+            .insert_resource(TamedMobRegistry::default())
             .add_systems(
                 (
-                    handle_new_red_mushling_state_machine,
+                    handle_new_red_mushling_sprite,
                     handle_new_mob_state_machine,
                     handle_mob_move_minimap_update,
                     juice_up_spawned_elite_mobs.before(add_current_health_with_max_health),
@@ -49,13 +52,43 @@ impl Plugin for EnemyPlugin {
                 )
                     .in_set(OnUpdate(GameState::Main)),
             )
-            .add_plugin(SpawnerPlugin);
+            .add_systems(
+                (
+                    handle_feed_mob,
+                    handle_breeding,
+                    tick_breed_cooldowns,
+                    handle_new_baby_scale,
+                    grow_babies_on_new_day,
+                    handle_tamed_resource_production,
+                    steer_idle_passive_mobs,
+                    store_tamed_mobs_on_dimension_swap.before(CustomFlush),
+                    respawn_tamed_mobs_in_loaded_chunks,
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            )
+            .add_systems(
+                (
+                    handle_companion_command_input,
+                    handle_new_companion.after(handle_new_mob_state_machine),
+                    handle_summon_companion,
+                    pick_companion_targets,
+                    companion_attack,
+                    guard,
+                    give_companion_xp_on_kill,
+                    handle_give_companion_equipment,
+                    expire_temporary_allies,
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            )
+            .add_plugin(SpawnerPlugin)
+            .add_plugin(RaidPlugin);
     }
 }
 
 #[derive(
     Component,
     Default,
+    Serialize,
     Deserialize,
     Debug,
     Clone,
@@ -81,6 +114,7 @@ pub enum Mob {
     Bushling,
     Fairy,
     RedMushling,
+    Golem,
 }
 
 impl Mob {
@@ -95,6 +129,7 @@ impl Mob {
             Mob::FurDevil => PINK,
             Mob::RedMushling => RED,
             Mob::Hog => LIGHT_BROWN,
+            Mob::Golem => GREY,
         }
     }
 }
@@ -126,193 +161,35 @@ pub struct EnemySpawnEvent {
 #[reflect(Component, Schematic)]
 pub struct MobLevel(pub u8);
 
-#[derive(FromReflect, Debug, Default, Reflect, Clone, Component, Schematic)]
-#[reflect(Component, Schematic, Default)]
-pub struct LeapAttack {
-    pub activation_distance: f32,
-    pub duration: f32,
-    pub cooldown: f32,
-    pub startup: f32,
-    pub speed: f32,
-}
+/// Spawns the mob at this level instead of the current day's, used by the dev console
+#[derive(Component, Debug, Clone)]
+pub struct SpawnLevelOverride(pub u8);
 
 #[derive(Component)]
 pub struct MobIsAttacking;
 
-#[derive(FromReflect, Default, Reflect, Clone, Component, Schematic)]
-#[reflect(Component, Schematic, Default)]
-pub struct ProjectileAttack {
-    pub activation_distance: f32,
-    pub cooldown: f32,
-    pub projectile: Projectile,
-}
 pub fn handle_new_mob_state_machine(
     mut commands: Commands,
     game: GameParam,
     spawn_events: Query<
+        (Entity, &CombatAlignment, &MobBehaviour),
         (
-            Entity,
-            &Mob,
-            &CombatAlignment,
-            &FollowSpeed,
-            Option<&LeapAttack>,
-            Option<&ProjectileAttack>,
+            With<Mob>,
+            Or<(Added<Mob>, Added<CombatAlignment>, Changed<CombatAlignment>)>,
         ),
-        Or<(Added<Mob>, Added<CombatAlignment>, Changed<CombatAlignment>)>,
     >,
     dungeon_check: Query<&Dungeon>,
 ) {
-    for (e, mob, alignment, follow_speed, leap_attack_option, proj_attack_option) in
-        spawn_events.iter()
-    {
+    for (e, alignment, behaviour) in spawn_events.iter() {
         let mut alignment = alignment.clone();
         if dungeon_check.get_single().is_ok() {
             alignment = CombatAlignment::Hostile;
         }
-        if mob == &Mob::RedMushling {
-            continue;
-        }
         let mut e_cmds = commands.entity(e);
-        let mut state_machine = StateMachine::default().set_trans_logging(false);
-        match alignment {
-            CombatAlignment::Neutral => {
-                state_machine = state_machine
-                    .trans::<IdleState>(
-                        HurtByPlayer,
-                        FollowState {
-                            target: game.game.player,
-                            speed: follow_speed.0,
-                        },
-                    )
-                    .trans::<FollowState>(
-                        Trigger::not(LineOfSight {
-                            target: game.game.player,
-                            range: 130.,
-                        }),
-                        IdleState {
-                            walk_timer: Timer::from_seconds(2., TimerMode::Repeating),
-                            direction: FacingDirection::new_rand_dir(rand::thread_rng()),
-                            speed: 0.5,
-                            is_stopped: false,
-                        },
-                    );
-            }
-            CombatAlignment::Hostile => {
-                state_machine = state_machine
-                    .trans::<IdleState>(
-                        LineOfSight {
-                            target: game.game.player,
-                            range: 130.,
-                        },
-                        FollowState {
-                            target: game.game.player,
-                            speed: follow_speed.0,
-                        },
-                    )
-                    .trans::<FollowState>(
-                        Trigger::not(LineOfSight {
-                            target: game.game.player,
-                            range: 160.,
-                        }),
-                        IdleState {
-                            walk_timer: Timer::from_seconds(2., TimerMode::Repeating),
-                            direction: FacingDirection::new_rand_dir(rand::thread_rng()),
-                            speed: 0.5,
-                            is_stopped: false,
-                        },
-                    );
-            }
-            CombatAlignment::Passive => {
-                //TODO: impl run away
-            }
+        if let Some(initial_state) = &behaviour.initial_state {
+            initial_state.insert_state(&mut e_cmds, game.game.player);
         }
-        if let Some(leap_attack) = leap_attack_option {
-            state_machine = state_machine
-                .trans::<FollowState>(
-                    AttackDistance {
-                        target: game.game.player,
-                        range: leap_attack.activation_distance,
-                    },
-                    LeapAttackState {
-                        target: game.game.player,
-                        attack_startup_timer: Timer::from_seconds(
-                            leap_attack.startup,
-                            TimerMode::Once,
-                        ),
-                        attack_duration_timer: Timer::from_seconds(
-                            leap_attack.duration,
-                            TimerMode::Once,
-                        ),
-                        attack_cooldown_timer: Timer::from_seconds(
-                            leap_attack.cooldown,
-                            TimerMode::Once,
-                        ),
-                        dir: None,
-                        speed: leap_attack.speed,
-                    },
-                )
-                .trans::<LeapAttackState>(
-                    Trigger::not(AttackDistance {
-                        target: game.game.player,
-                        range: leap_attack.activation_distance + 32.,
-                    }),
-                    FollowState {
-                        target: game.game.player,
-                        speed: follow_speed.0,
-                    },
-                );
-        }
-        if let Some(proj_attack) = proj_attack_option {
-            state_machine = state_machine
-                .trans::<FollowState>(
-                    AttackDistance {
-                        target: game.game.player,
-                        range: proj_attack.activation_distance,
-                    },
-                    ProjectileAttackState {
-                        target: game.game.player,
-                        attack_startup_timer: Timer::from_seconds(0.3, TimerMode::Once),
-                        attack_cooldown_timer: Timer::from_seconds(
-                            proj_attack.cooldown,
-                            TimerMode::Once,
-                        ),
-                        dir: None,
-                        projectile: proj_attack.projectile.clone(),
-                    },
-                )
-                .trans::<ProjectileAttackState>(
-                    Trigger::not(AttackDistance {
-                        target: game.game.player,
-                        range: proj_attack.activation_distance + 30.,
-                    }),
-                    FollowState {
-                        target: game.game.player,
-                        speed: follow_speed.0,
-                    },
-                );
-            if let Some(leap_attack) = leap_attack_option {
-                state_machine = state_machine.trans::<ProjectileAttackState>(
-                    AttackDistance {
-                        target: game.game.player,
-                        range: leap_attack.activation_distance,
-                    },
-                    FollowState {
-                        target: game.game.player,
-                        speed: follow_speed.0,
-                    },
-                );
-            }
-        }
-        if alignment != CombatAlignment::Passive {
-            state_machine = state_machine.trans::<IdleState>(
-                NightTimeAggro,
-                FollowState {
-                    target: game.game.player,
-                    speed: follow_speed.0,
-                },
-            );
-        }
-        e_cmds.insert(state_machine);
+        e_cmds.insert(behaviour.build_state_machine(game.game.player, &alignment));
     }
 }
 fn handle_mob_move_minimap_update(
@@ -337,6 +214,7 @@ fn juice_up_spawned_elite_mobs(
         ),
         Added<EliteMob>,
     >,
+    gems: Res<Gems>,
 ) {
     for (_e, mut hp, mut att, mut exp, mut loot) in elites.iter_mut() {
         hp.0 *= 2;
@@ -352,19 +230,34 @@ fn juice_up_spawned_elite_mobs(
                 rate: l.rate * 2.5,
             })
             .collect();
+        // elites have a small chance to drop each gem
+        loot.drops.extend(gems.0.keys().map(|gem| Loot::new(*gem, 1, 1, 0.05)));
     }
 }
 
 fn juice_up_spawned_mobs_per_day(
-    mut elites: Query<(Entity, &mut MaxHealth, &mut Attack, &mut ExperienceReward), Added<Mob>>,
+    mut elites: Query<
+        (
+            Entity,
+            &mut MaxHealth,
+            &mut Attack,
+            &mut ExperienceReward,
+            Option<&SpawnLevelOverride>,
+        ),
+        Added<Mob>,
+    >,
     night_tracker: Res<NightTracker>,
     mut commands: Commands,
 ) {
-    for (e, mut hp, mut att, mut exp) in elites.iter_mut() {
-        hp.0 = (hp.0 as f32 * (1. + night_tracker.days as f32 * 0.1)) as i32;
-        att.0 = (att.0 as f32 * (1. + night_tracker.days as f32 * 0.1)) as i32;
-        exp.0 = (exp.0 as f32 * (1. + night_tracker.days as f32 * 0.1)) as u32;
-        commands.entity(e).insert(MobLevel(night_tracker.days + 1));
+    for (e, mut hp, mut att, mut exp, level_override) in elites.iter_mut() {
+        let level = level_override
+            .map(|l| l.0.max(1))
+            .unwrap_or(night_tracker.days + 1);
+        let scale = 1. + (level - 1) as f32 * 0.1;
+        hp.0 = (hp.0 as f32 * scale) as i32;
+        att.0 = (att.0 as f32 * scale) as i32;
+        exp.0 = (exp.0 as f32 * scale) as u32;
+        commands.entity(e).insert(MobLevel(level));
     }
 }
 
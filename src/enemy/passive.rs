@@ -0,0 +1,450 @@
+use bevy::prelude::*;
+use bevy_proto::prelude::{ProtoCommands, Prototypes, ReflectSchematic, Schematic};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::IdleState,
+    colors::{LIGHT_BROWN, PINK},
+    custom_commands::CommandsExt,
+    inputs::FacingDirection,
+    inventory::Inventory,
+    item::WorldObject,
+    night::NewDayEvent,
+    proto::proto_param::ProtoParam,
+    ui::{damage_numbers::spawn_floating_text_with_shadow, InventoryState},
+    world::{
+        dimension::SpawnDimension,
+        dungeon::Dungeon,
+        world_helpers::{tile_pos_to_world_pos, world_pos_to_tile_pos},
+        TileMapPosition,
+    },
+    GameParam,
+};
+
+use super::{companion::Companion, Mob};
+
+/// Passive mobs with this stay close to others of the same type while wandering
+#[derive(Component, Reflect, FromReflect, Schematic, Default, Clone, Debug)]
+#[reflect(Component, Schematic)]
+pub struct Herd {
+    pub radius: f32,
+}
+
+/// Mobs that can be fed to tame them, and once tamed, fed again to breed.
+/// Babies take `days_to_grow` new days to become adults.
+#[derive(Component, Reflect, FromReflect, Schematic, Default, Clone, Debug)]
+#[reflect(Component, Schematic)]
+pub struct Breedable {
+    pub food: WorldObject,
+    pub days_to_grow: u8,
+    pub cooldown: f32,
+}
+
+/// Tamed adult mobs periodically drop this item
+#[derive(Component, Reflect, FromReflect, Schematic, Default, Clone, Debug)]
+#[reflect(Component, Schematic)]
+pub struct ResourceProducer {
+    pub item: WorldObject,
+    pub secs: f32,
+}
+
+/// Placed objects with this let nearby mobs be tamed, who will then wander around it
+#[derive(Component, Reflect, FromReflect, Schematic, Default, Clone, Debug)]
+#[reflect(Component, Schematic)]
+pub struct AnimalPen {
+    pub radius: f32,
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Tamed {
+    /// Stays the same while the mob is stored and respawned, so it is only ever stored once
+    pub id: u64,
+    pub home: TileMapPosition,
+    pub radius: f32,
+}
+
+impl Tamed {
+    pub fn new(home: TileMapPosition, radius: f32) -> Self {
+        Self {
+            id: rand::random(),
+            home,
+            radius,
+        }
+    }
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Baby {
+    pub days_left: u8,
+}
+#[derive(Component)]
+pub struct InLove(pub Timer);
+#[derive(Component)]
+pub struct BreedCooldown(pub Timer);
+#[derive(Component)]
+pub struct ProduceTimer(pub Timer);
+
+pub struct FeedMobEvent {
+    pub mob: Entity,
+}
+
+/// Tamed mobs that are not currently spawned, because their pen's chunk
+/// is unloaded or the player is in another dimension.
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
+pub struct TamedMobRegistry {
+    pub mobs: Vec<TamedMobData>,
+}
+
+impl TamedMobRegistry {
+    /// Replaces any copy of the mob already stored
+    pub fn store(&mut self, data: TamedMobData) {
+        self.mobs.retain(|stored| stored.id != data.id);
+        self.mobs.push(data);
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TamedMobData {
+    /// Saves from before tamed mobs had ids get a new one when loaded
+    #[serde(default = "rand::random")]
+    pub id: u64,
+    pub mob: Mob,
+    pub home: TileMapPosition,
+    pub radius: f32,
+    pub baby_days_left: Option<u8>,
+    #[serde(default)]
+    pub companion: Option<Companion>,
+}
+
+impl TamedMobData {
+    pub fn new(
+        mob: &Mob,
+        tamed: &Tamed,
+        baby_option: Option<&Baby>,
+        companion_option: Option<&Companion>,
+    ) -> Self {
+        Self {
+            id: tamed.id,
+            mob: mob.clone(),
+            home: tamed.home,
+            radius: tamed.radius,
+            baby_days_left: baby_option.map(|b| b.days_left),
+            companion: companion_option.cloned(),
+        }
+    }
+}
+
+const BABY_SCALE: f32 = 0.6;
+const IN_LOVE_DURATION: f32 = 10.;
+const BREED_DISTANCE: f32 = 24.;
+
+pub fn handle_feed_mob(
+    mut commands: Commands,
+    mut feed_events: EventReader<FeedMobEvent>,
+    mobs: Query<
+        (
+            &GlobalTransform,
+            &Breedable,
+            Option<&Tamed>,
+            Option<&InLove>,
+            Option<&BreedCooldown>,
+        ),
+        Without<Baby>,
+    >,
+    pens: Query<(&GlobalTransform, &AnimalPen)>,
+    mut inv: Query<&mut Inventory>,
+    inv_state: Res<InventoryState>,
+    asset_server: Res<AssetServer>,
+) {
+    for feed in feed_events.iter() {
+        let Ok((txfm, breedable, tamed_option, in_love_option, cooldown_option)) =
+            mobs.get(feed.mob)
+        else {
+            continue;
+        };
+        let pos = txfm.translation().truncate();
+        if tamed_option.is_none() {
+            let Some((pen_txfm, pen)) = pens
+                .iter()
+                .find(|(t, pen)| t.translation().truncate().distance(pos) <= pen.radius)
+            else {
+                spawn_floating_text_with_shadow(
+                    &mut commands,
+                    &asset_server,
+                    txfm.translation() + Vec3::new(0., 10., 0.),
+                    LIGHT_BROWN,
+                    "Needs a pen".to_string(),
+                );
+                continue;
+            };
+            commands.entity(feed.mob).insert(Tamed::new(
+                world_pos_to_tile_pos(pen_txfm.translation().truncate()),
+                pen.radius,
+            ));
+            spawn_floating_text_with_shadow(
+                &mut commands,
+                &asset_server,
+                txfm.translation() + Vec3::new(0., 10., 0.),
+                PINK,
+                "Tamed!".to_string(),
+            );
+        } else if in_love_option.is_none() && cooldown_option.is_none() {
+            commands.entity(feed.mob).insert(InLove(Timer::from_seconds(
+                IN_LOVE_DURATION,
+                TimerMode::Once,
+            )));
+            spawn_floating_text_with_shadow(
+                &mut commands,
+                &asset_server,
+                txfm.translation() + Vec3::new(0., 10., 0.),
+                PINK,
+                "<3".to_string(),
+            );
+        } else {
+            continue;
+        }
+        let hotbar_slot = inv_state.active_hotbar_slot;
+        let held_item_option = inv.single().items.items[hotbar_slot].clone();
+        if let Some(held_item) = held_item_option {
+            if held_item.get_obj() == &breedable.food {
+                inv.single_mut().items.items[hotbar_slot] = held_item.modify_count(-1);
+            }
+        }
+    }
+}
+
+pub fn handle_breeding(
+    mut commands: Commands,
+    mut proto_commands: ProtoCommands,
+    prototypes: Prototypes,
+    mut lovers: Query<(
+        Entity,
+        &Mob,
+        &GlobalTransform,
+        &Breedable,
+        &Tamed,
+        &mut InLove,
+    )>,
+    time: Res<Time>,
+) {
+    let mut paired: Vec<Entity> = vec![];
+    let mut combinations = lovers.iter_combinations_mut();
+    while let Some([(e1, mob1, t1, breedable, tamed, in_love1), (e2, mob2, t2, _, _, in_love2)]) =
+        combinations.fetch_next()
+    {
+        if paired.contains(&e1) || paired.contains(&e2) || mob1 != mob2 {
+            continue;
+        }
+        let (p1, p2) = (t1.translation().truncate(), t2.translation().truncate());
+        if p1.distance(p2) > BREED_DISTANCE || in_love1.0.finished() || in_love2.0.finished() {
+            continue;
+        }
+        paired.push(e1);
+        paired.push(e2);
+        for e in [e1, e2] {
+            commands
+                .entity(e)
+                .remove::<InLove>()
+                .insert(BreedCooldown(Timer::from_seconds(
+                    breedable.cooldown,
+                    TimerMode::Once,
+                )));
+        }
+        if let Some(baby) =
+            proto_commands.spawn_from_proto(mob1.clone(), &prototypes, (p1 + p2) / 2.)
+        {
+            commands.entity(baby).insert((
+                Baby {
+                    days_left: breedable.days_to_grow,
+                },
+                Tamed::new(tamed.home, tamed.radius),
+            ));
+        }
+    }
+
+    for (e, _, _, _, _, mut in_love) in lovers.iter_mut() {
+        if paired.contains(&e) {
+            continue;
+        }
+        in_love.0.tick(time.delta());
+        if in_love.0.finished() {
+            commands.entity(e).remove::<InLove>();
+        }
+    }
+}
+
+pub fn tick_breed_cooldowns(
+    mut commands: Commands,
+    mut cooldowns: Query<(Entity, &mut BreedCooldown)>,
+    time: Res<Time>,
+) {
+    for (e, mut cooldown) in cooldowns.iter_mut() {
+        cooldown.0.tick(time.delta());
+        if cooldown.0.finished() {
+            commands.entity(e).remove::<BreedCooldown>();
+        }
+    }
+}
+
+pub fn handle_new_baby_scale(mut babies: Query<&mut Transform, Added<Baby>>) {
+    for mut t in babies.iter_mut() {
+        t.scale = Vec3::new(BABY_SCALE, BABY_SCALE, 1.);
+    }
+}
+
+pub fn grow_babies_on_new_day(
+    mut commands: Commands,
+    mut babies: Query<(Entity, &mut Baby, &mut Transform)>,
+    mut new_day_events: EventReader<NewDayEvent>,
+) {
+    for _ in new_day_events.iter() {
+        for (e, mut baby, mut t) in babies.iter_mut() {
+            baby.days_left = baby.days_left.saturating_sub(1);
+            if baby.days_left == 0 {
+                t.scale = Vec3::ONE;
+                commands.entity(e).remove::<Baby>();
+            }
+        }
+    }
+}
+
+pub fn handle_tamed_resource_production(
+    mut commands: Commands,
+    mut producers: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &ResourceProducer,
+            Option<&mut ProduceTimer>,
+        ),
+        (With<Tamed>, Without<Baby>),
+    >,
+    mut proto_commands: ProtoCommands,
+    proto: ProtoParam,
+    time: Res<Time>,
+) {
+    for (e, txfm, producer, timer_option) in producers.iter_mut() {
+        let Some(mut timer) = timer_option else {
+            commands.entity(e).insert(ProduceTimer(Timer::from_seconds(
+                producer.secs,
+                TimerMode::Repeating,
+            )));
+            continue;
+        };
+        timer.0.tick(time.delta());
+        if timer.0.just_finished() {
+            proto_commands.spawn_item_from_proto(
+                producer.item,
+                &proto,
+                txfm.translation().truncate() + Vec2::new(0., -8.),
+                1,
+                None,
+            );
+        }
+    }
+}
+
+/// While wandering, herd mobs drift back toward others of their kind,
+/// and tamed mobs drift back toward their pen.
+pub fn steer_idle_passive_mobs(
+    mut commands: Commands,
+    mut idle_mobs: Query<
+        (
+            Entity,
+            &Mob,
+            &GlobalTransform,
+            &mut IdleState,
+            Option<&Herd>,
+            Option<&Tamed>,
+        ),
+        Without<Companion>,
+    >,
+    herd_members: Query<(Entity, &Mob, &GlobalTransform), With<Herd>>,
+) {
+    for (e, mob, txfm, mut idle, herd_option, tamed_option) in idle_mobs.iter_mut() {
+        if idle.is_stopped {
+            continue;
+        }
+        let pos = txfm.translation().truncate();
+        let target = if let Some(tamed) = tamed_option {
+            let home = tile_pos_to_world_pos(tamed.home, true);
+            if home.distance(pos) <= tamed.radius {
+                continue;
+            }
+            home
+        } else if let Some(herd) = herd_option {
+            let neighbours = herd_members
+                .iter()
+                .filter(|(other_e, other_mob, other_t)| {
+                    *other_e != e
+                        && *other_mob == mob
+                        && other_t.translation().truncate().distance(pos) <= herd.radius * 3.
+                })
+                .map(|(_, _, t)| t.translation().truncate())
+                .collect::<Vec<_>>();
+            if neighbours.is_empty() {
+                continue;
+            }
+            let center = neighbours.iter().sum::<Vec2>() / neighbours.len() as f32;
+            if center.distance(pos) <= herd.radius {
+                continue;
+            }
+            center
+        } else {
+            continue;
+        };
+        let new_dir = FacingDirection::from_translation(target - pos);
+        if new_dir != idle.direction {
+            idle.direction = new_dir.clone();
+            commands.entity(e).insert(new_dir);
+        }
+    }
+}
+
+pub fn store_tamed_mobs_on_dimension_swap(
+    new_dim: Query<Entity, Added<SpawnDimension>>,
+    tamed_mobs: Query<(&Mob, &Tamed, Option<&Baby>, Option<&Companion>)>,
+    mut registry: ResMut<TamedMobRegistry>,
+) {
+    if new_dim.is_empty() {
+        return;
+    }
+    for (mob, tamed, baby_option, companion_option) in tamed_mobs.iter() {
+        registry.store(TamedMobData::new(mob, tamed, baby_option, companion_option));
+    }
+}
+
+pub fn respawn_tamed_mobs_in_loaded_chunks(
+    mut commands: Commands,
+    game: GameParam,
+    mut registry: ResMut<TamedMobRegistry>,
+    mut proto_commands: ProtoCommands,
+    prototypes: Prototypes,
+    dungeon_check: Query<&Dungeon>,
+) {
+    if registry.mobs.is_empty() || dungeon_check.get_single().is_ok() {
+        return;
+    }
+    let (to_spawn, waiting): (Vec<_>, Vec<_>) = registry
+        .mobs
+        .drain(..)
+        .partition(|data| game.get_chunk_entity(data.home.chunk_pos).is_some());
+    registry.mobs = waiting;
+    for data in to_spawn {
+        let pos = tile_pos_to_world_pos(data.home, true);
+        if let Some(mob_e) = proto_commands.spawn_from_proto(data.mob.clone(), &prototypes, pos) {
+            let mut mob_cmds = commands.entity(mob_e);
+            mob_cmds.insert(Tamed {
+                id: data.id,
+                home: data.home,
+                radius: data.radius,
+            });
+            if let Some(days_left) = data.baby_days_left {
+                mob_cmds.insert(Baby { days_left });
+            }
+            if let Some(companion) = data.companion {
+                mob_cmds.insert(companion);
+            }
+        }
+    }
+}
@@ -0,0 +1,485 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::tiles::TilePos;
+use bevy_proto::prelude::{ProtoCommands, Prototypes};
+use bevy_rapier2d::prelude::KinematicCharacterController;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    ai::{
+        FollowState, IdleState, LeapAttackState, MobBehaviour, MobStateProto, MobStateType,
+        MobTarget, MobTransition, MobTrigger, ProjectileAttackState,
+    },
+    animations::enemy_sprites::EnemyAnimationState,
+    attributes::Attack,
+    colors::{RED, YELLOW},
+    combat::HitEvent,
+    inputs::FacingDirection,
+    inventory::ItemStack,
+    item::{Loot, LootTable, LootTablePlugin, Wall, WorldObject},
+    night::{NewDayEvent, NightTracker},
+    player::{levels::PlayerLevel, Player},
+    proto::proto_param::ProtoParam,
+    ui::damage_numbers::spawn_floating_text_with_shadow,
+    world::{
+        dimension::ActiveDimension,
+        dungeon::Dungeon,
+        world_helpers::{camera_pos_to_chunk_pos, tile_pos_to_world_pos, world_pos_to_tile_pos},
+        TileMapPosition, CHUNK_SIZE, TILE_SIZE,
+    },
+    Game, GameParam, GameState, PLAYER_MOVE_SPEED,
+};
+
+use super::{
+    handle_new_mob_state_machine,
+    spawn_helpers::{can_spawn_mob_here, is_tile_allowed_for_rule},
+    spawner::{ChunkSpawners, MobSpawnRule, SpawnTable},
+    CombatAlignment, EliteMob, FollowSpeed,
+};
+
+/// Raiders switch from the base to the player if they get this close,
+/// and go back to the base if the player gets twice as far
+const RAIDER_AGGRO_RADIUS: f32 = 64.;
+const RAIDER_ATTACK_RANGE: f32 = 18.;
+const RAIDER_ATTACK_COOLDOWN: f32 = 1.;
+const RAIDER_RETARGET_SECS: f32 = 1.;
+
+pub struct RaidPlugin;
+impl Plugin for RaidPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RaidTracker::default())
+            .add_system(reset_raid_tracker.in_schedule(OnEnter(GameState::Main)))
+            .add_systems(
+                (
+                    start_raid_at_night,
+                    spawn_raid_waves.after(start_raid_at_night),
+                    handle_new_raiders.after(handle_new_mob_state_machine),
+                    retarget_raiders,
+                    raid_march,
+                    update_raiders_left,
+                    end_raid_at_dawn,
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            );
+    }
+}
+
+/// Every `every_n_days` nights, waves of mobs spawn at the edge of the loaded
+/// chunks and march on the player's base. Surviving until dawn drops the rewards.
+#[derive(Reflect, FromReflect, Clone, Debug, Default)]
+pub struct RaidTable {
+    pub every_n_days: u8,
+    pub num_waves: u8,
+    pub wave_interval: f32,
+    pub mobs_per_wave: u8,
+    /// Added to the wave size for each wave after the first
+    pub extra_mobs_per_wave: u8,
+    pub spawns: Vec<MobSpawnRule>,
+    /// Placed objects raiders path toward and break
+    pub target_objects: Vec<WorldObject>,
+    pub rewards: Vec<Loot>,
+    pub reward_xp: u32,
+}
+
+impl RaidTable {
+    pub fn is_raid_night(&self, night_tracker: &NightTracker) -> bool {
+        self.every_n_days > 0
+            && self.num_waves > 0
+            && night_tracker.days > 0
+            && night_tracker.days % self.every_n_days == 0
+            && night_tracker.is_night()
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct RaidTracker {
+    pub active: bool,
+    pub last_raid_day: Option<u8>,
+    pub wave: u8,
+    pub num_waves: u8,
+    pub wave_timer: Timer,
+    pub raiders_left: usize,
+}
+
+/// A mob spawned by a raid. It keeps its usual attacks, but marches on the
+/// closest player structure instead of wandering, see [get_raider_behaviour].
+#[derive(Component, Debug)]
+pub struct Raider {
+    pub retarget_timer: Timer,
+    /// The chunk whose mob count this raider was added to
+    pub counted_chunk: Option<IVec2>,
+}
+
+impl Default for Raider {
+    fn default() -> Self {
+        Self {
+            retarget_timer: Timer::from_seconds(RAIDER_RETARGET_SECS, TimerMode::Repeating),
+            counted_chunk: None,
+        }
+    }
+}
+
+/// Raiders in this state walk toward their [MobTarget], or the player if they have none.
+/// Walls and doors in their way are hit until they break, which sends the usual
+/// `WallBreakEvent` through `handle_break_object`.
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct RaidState {
+    pub attack_timer: Timer,
+}
+
+/// Swaps the wandering in a mob's behaviour for marching on the base. Raids happen at
+/// night, when line of sight always triggers, so raiders turn on the player by distance.
+pub fn get_raider_behaviour(behaviour: &MobBehaviour, follow_speed: f32) -> MobBehaviour {
+    let raid = MobStateProto::Raid {
+        attack_cooldown: RAIDER_ATTACK_COOLDOWN,
+    };
+    let follow = MobStateProto::Follow {
+        speed: follow_speed,
+    };
+    let mut transitions = vec![
+        MobTransition {
+            from: MobStateType::Raid,
+            trigger: MobTrigger::AttackDistance(RAIDER_AGGRO_RADIUS),
+            to: follow,
+        },
+        MobTransition {
+            from: MobStateType::Follow,
+            trigger: MobTrigger::OutOfAttackDistance(RAIDER_AGGRO_RADIUS * 2.),
+            to: raid.clone(),
+        },
+    ];
+    let mob_transitions = behaviour
+        .hostile_transitions
+        .as_ref()
+        .unwrap_or(&behaviour.transitions);
+    // raiders never wander, so anything that would go back to idle marches instead
+    transitions.extend(
+        mob_transitions
+            .iter()
+            .filter(|t| t.from != MobStateType::Idle)
+            .map(|t| MobTransition {
+                to: if t.to.get_type() == MobStateType::Idle {
+                    raid.clone()
+                } else {
+                    t.to.clone()
+                },
+                ..t.clone()
+            }),
+    );
+    MobBehaviour {
+        initial_state: Some(raid),
+        transitions,
+        hostile_transitions: None,
+    }
+}
+
+fn reset_raid_tracker(mut commands: Commands) {
+    commands.insert_resource(RaidTracker::default());
+}
+
+fn start_raid_at_night(
+    mut commands: Commands,
+    mut raid_tracker: ResMut<RaidTracker>,
+    night_tracker: Res<NightTracker>,
+    spawn_table: Res<SpawnTable>,
+    maybe_dungeon: Query<&Dungeon, With<ActiveDimension>>,
+    player_t: Query<&GlobalTransform, With<Player>>,
+    asset_server: Res<AssetServer>,
+) {
+    if raid_tracker.active
+        || maybe_dungeon.get_single().is_ok()
+        || raid_tracker.last_raid_day == Some(night_tracker.days)
+        || !spawn_table.raid.is_raid_night(&night_tracker)
+    {
+        return;
+    }
+    *raid_tracker = RaidTracker {
+        active: true,
+        last_raid_day: Some(night_tracker.days),
+        wave: 0,
+        num_waves: spawn_table.raid.num_waves,
+        wave_timer: Timer::from_seconds(spawn_table.raid.wave_interval, TimerMode::Once),
+        raiders_left: 0,
+    };
+    let Ok(player_t) = player_t.get_single() else {
+        return;
+    };
+    spawn_floating_text_with_shadow(
+        &mut commands,
+        &asset_server,
+        player_t.translation() + Vec3::new(0., 16., 0.),
+        RED,
+        "A raid approaches!".to_string(),
+    );
+}
+
+/// Picks a random spawnable position in a loaded chunk that is missing at least one
+/// loaded neighbour, so raiders come in from the edge of the world the player can see.
+fn get_raid_spawn_pos(
+    game: &GameParam,
+    proto_param: &ProtoParam,
+    rule: &MobSpawnRule,
+) -> Option<Vec2> {
+    let loaded_chunks: Vec<IVec2> = game.chunk_query.iter().map(|(_, c)| c.chunk_pos).collect();
+    let edge_chunks: Vec<IVec2> = loaded_chunks
+        .iter()
+        .filter(|chunk_pos| {
+            [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+                .iter()
+                .any(|offset| !loaded_chunks.contains(&(**chunk_pos + *offset)))
+        })
+        .copied()
+        .collect();
+    let mut rng = rand::thread_rng();
+    for _ in 0..10 {
+        let chunk_pos = edge_chunks.choose(&mut rng)?;
+        let tile_pos = TilePos {
+            x: rng.gen_range(0..CHUNK_SIZE),
+            y: rng.gen_range(0..CHUNK_SIZE),
+        };
+        let pos = tile_pos_to_world_pos(TileMapPosition::new(*chunk_pos, tile_pos), true);
+        if is_tile_allowed_for_rule(pos, game, rule) && can_spawn_mob_here(pos, game, proto_param) {
+            return Some(pos);
+        }
+    }
+    None
+}
+
+fn spawn_raid_waves(
+    game: GameParam,
+    mut commands: Commands,
+    mut proto_commands: ProtoCommands,
+    prototypes: Prototypes,
+    proto_param: ProtoParam,
+    mut raid_tracker: ResMut<RaidTracker>,
+    spawn_table: Res<SpawnTable>,
+    night_tracker: Res<NightTracker>,
+    mut spawners: Query<&mut ChunkSpawners>,
+    time: Res<Time>,
+) {
+    if !raid_tracker.active || raid_tracker.wave >= raid_tracker.num_waves {
+        return;
+    }
+    raid_tracker.wave_timer.tick(time.delta());
+    if raid_tracker.wave > 0 && !raid_tracker.wave_timer.finished() {
+        return;
+    }
+    let raid = &spawn_table.raid;
+    let mut rng = rand::thread_rng();
+    let wave_size = raid.mobs_per_wave + raid.extra_mobs_per_wave * raid_tracker.wave;
+    for _ in 0..wave_size {
+        let Ok(rule) = raid.spawns.choose_weighted(&mut rng, |rule| {
+            if rule.min_days_to_spawn <= night_tracker.days {
+                rule.weight
+            } else {
+                0.
+            }
+        }) else {
+            break;
+        };
+        let Some(pos) = get_raid_spawn_pos(&game, &proto_param, rule) else {
+            continue;
+        };
+        let Some(raider) = proto_commands.spawn_from_proto(rule.mob.clone(), &prototypes, pos)
+        else {
+            continue;
+        };
+        if rng.gen::<f32>() < rule.elite_chance {
+            commands.entity(raider).insert(EliteMob);
+        }
+        // raiders count towards the chunk cap so deaths don't free up extra spawns
+        let chunk_pos = camera_pos_to_chunk_pos(&pos);
+        let mut counted_chunk = None;
+        if let Some(chunk_e) = game.get_chunk_entity(chunk_pos) {
+            if let Ok(mut chunk_spawner) = spawners.get_mut(chunk_e) {
+                chunk_spawner.spawned_mobs += 1;
+                counted_chunk = Some(chunk_pos);
+            }
+        }
+        commands.entity(raider).insert(Raider {
+            counted_chunk,
+            ..default()
+        });
+    }
+    raid_tracker.wave += 1;
+    raid_tracker.wave_timer.reset();
+}
+
+fn handle_new_raiders(
+    mut commands: Commands,
+    game: Res<Game>,
+    new_raiders: Query<(Entity, &MobBehaviour, Option<&FollowSpeed>), Added<Raider>>,
+) {
+    for (e, behaviour, speed_option) in new_raiders.iter() {
+        let behaviour = get_raider_behaviour(behaviour, speed_option.map_or(1., |s| s.0));
+        let mut e_cmds = commands.entity(e);
+        e_cmds
+            .remove::<IdleState>()
+            .remove::<FollowState>()
+            .remove::<LeapAttackState>()
+            .remove::<ProjectileAttackState>()
+            .insert((MobTarget::default(), EnemyAnimationState::Walk));
+        if let Some(initial_state) = &behaviour.initial_state {
+            initial_state.insert_state(&mut e_cmds, game.player);
+        }
+        e_cmds.insert((
+            behaviour.build_state_machine(game.player, &CombatAlignment::Hostile),
+            behaviour,
+        ));
+    }
+}
+
+/// Points each raider at the nearest target structure, checked every so often
+/// since structures can be placed or broken mid raid. Dropped items are skipped.
+fn retarget_raiders(
+    mut raiders: Query<(&GlobalTransform, &mut Raider, &mut MobTarget)>,
+    targets: Query<(Entity, &GlobalTransform, &WorldObject), Without<ItemStack>>,
+    spawn_table: Res<SpawnTable>,
+    time: Res<Time>,
+) {
+    for (t, mut raider, mut target) in raiders.iter_mut() {
+        raider.retarget_timer.tick(time.delta());
+        let target_is_gone = target.0.map_or(true, |e| targets.get(e).is_err());
+        if !raider.retarget_timer.just_finished() && !target_is_gone {
+            continue;
+        }
+        let pos = t.translation().truncate();
+        target.0 = targets
+            .iter()
+            .filter(|(_, _, obj)| spawn_table.raid.target_objects.contains(obj))
+            .min_by(|(_, a, _), (_, b, _)| {
+                let a = a.translation().truncate().distance(pos);
+                let b = b.translation().truncate().distance(pos);
+                a.total_cmp(&b)
+            })
+            .map(|(obj_e, _, _)| obj_e);
+    }
+}
+
+fn raid_march(
+    mut commands: Commands,
+    game: GameParam,
+    proto_param: ProtoParam,
+    mut raiders: Query<(
+        Entity,
+        &GlobalTransform,
+        &MobTarget,
+        &mut RaidState,
+        &mut KinematicCharacterController,
+        Option<&FollowSpeed>,
+        Option<&Attack>,
+    )>,
+    txfms: Query<&GlobalTransform>,
+    walls: Query<&Wall>,
+    mut hit_event: EventWriter<HitEvent>,
+    time: Res<Time>,
+) {
+    let player = game.game.player;
+    for (e, t, target, mut raid, mut kcc, speed_option, attack_option) in raiders.iter_mut() {
+        raid.attack_timer.tick(time.delta());
+        let target = target.0.unwrap_or(player);
+        let Ok(target_t) = txfms.get(target) else {
+            continue;
+        };
+        let pos = t.translation().truncate();
+        let delta = target_t.translation().truncate() - pos;
+        let dir = delta.normalize_or_zero();
+        commands
+            .entity(e)
+            .insert(FacingDirection::from_translation(dir));
+        let damage = attack_option.map_or(1, |a| a.0.max(1));
+
+        // near the player the raider follows them and uses its own attacks, structures are hit here
+        if target != player && delta.length() <= RAIDER_ATTACK_RANGE {
+            if raid.attack_timer.finished() {
+                hit_event.send(HitEvent {
+                    hit_entity: target,
+                    damage,
+                    dir,
+                    hit_with_melee: None,
+                    hit_with_projectile: None,
+                });
+                raid.attack_timer.reset();
+            }
+            continue;
+        }
+        let tile_ahead = world_pos_to_tile_pos(pos + dir * TILE_SIZE.x);
+        if let Some(blocking_e) = game.get_obj_entity_at_tile(tile_ahead, &proto_param) {
+            if walls.get(blocking_e).is_ok() {
+                if raid.attack_timer.finished() {
+                    hit_event.send(HitEvent {
+                        hit_entity: blocking_e,
+                        damage,
+                        dir,
+                        hit_with_melee: None,
+                        hit_with_projectile: None,
+                    });
+                    raid.attack_timer.reset();
+                }
+                continue;
+            }
+        }
+        let speed = speed_option.map_or(1., |s| s.0);
+        kcc.translation = Some(dir * speed * PLAYER_MOVE_SPEED * time.delta_seconds());
+    }
+}
+
+fn update_raiders_left(mut raid_tracker: ResMut<RaidTracker>, raiders: Query<(), With<Raider>>) {
+    let raiders_left = raiders.iter().count();
+    if raid_tracker.raiders_left != raiders_left {
+        raid_tracker.raiders_left = raiders_left;
+    }
+}
+
+/// At dawn any raiders left retreat, and the player is rewarded for surviving the night
+fn end_raid_at_dawn(
+    mut commands: Commands,
+    mut game: GameParam,
+    proto_param: ProtoParam,
+    mut raid_tracker: ResMut<RaidTracker>,
+    mut new_day_event: EventReader<NewDayEvent>,
+    spawn_table: Res<SpawnTable>,
+    raiders: Query<(Entity, &Raider)>,
+    mut spawners: Query<&mut ChunkSpawners>,
+    player_t: Query<&GlobalTransform, With<Player>>,
+    mut player_level: Query<&mut PlayerLevel>,
+    asset_server: Res<AssetServer>,
+) {
+    if new_day_event.iter().count() == 0 || !raid_tracker.active {
+        return;
+    }
+    raid_tracker.active = false;
+    for (e, raider) in raiders.iter() {
+        // raiders wander off, so give the count back to the chunk they were added to
+        let chunk_e = raider
+            .counted_chunk
+            .and_then(|chunk_pos| game.get_chunk_entity(chunk_pos));
+        if let Some(chunk_e) = chunk_e {
+            if let Ok(mut chunk_spawner) = spawners.get_mut(chunk_e) {
+                chunk_spawner.spawned_mobs = (chunk_spawner.spawned_mobs - 1).max(0);
+            }
+        }
+        commands.entity(e).despawn_recursive();
+    }
+
+    let Ok(player_t) = player_t.get_single() else {
+        return;
+    };
+    let player_pos = player_t.translation().truncate();
+    let rewards = LootTable {
+        drops: spawn_table.raid.rewards.clone(),
+    };
+    for drop in LootTablePlugin::get_drops(&rewards, &proto_param, 0, None) {
+        drop.spawn_as_drop(&mut commands, &mut game, player_pos);
+    }
+    if let Ok(mut level) = player_level.get_single_mut() {
+        level.add_xp(spawn_table.raid.reward_xp);
+    }
+    spawn_floating_text_with_shadow(
+        &mut commands,
+        &asset_server,
+        player_pos.extend(0.) + Vec3::new(0., 16., 0.),
+        YELLOW,
+        "Raid survived!".to_string(),
+    );
+}
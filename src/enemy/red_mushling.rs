@@ -1,15 +1,14 @@
 use bevy::prelude::*;
 use bevy_rapier2d::geometry::{Collider, Sensor};
-use seldom_state::prelude::StateMachine;
 
-use crate::{ai::HurtByPlayer, attributes::Attack};
+use crate::attributes::Attack;
 use bevy_aseprite::{anim::AsepriteAnimation, aseprite, AsepriteBundle};
 
 use super::{Mob, MobIsAttacking};
 
 aseprite!(pub RedMushling, "textures/redmushling/red_mushling.ase");
 
-pub fn handle_new_red_mushling_state_machine(
+pub fn handle_new_red_mushling_sprite(
     mut commands: Commands,
     spawn_events: Query<(Entity, &Mob, &Transform), Added<Mob>>,
     asset_server: Res<AssetServer>,
@@ -21,20 +20,12 @@ pub fn handle_new_red_mushling_state_machine(
         let mut e_cmds = commands.entity(e);
         let mut animation = AsepriteAnimation::from(RedMushling::tags::SPURTING);
         animation.pause();
-        e_cmds
-            .insert(AsepriteBundle {
-                aseprite: asset_server.load(RedMushling::PATH),
-                animation,
-                transform: *transform,
-                ..Default::default()
-            })
-            .insert(WaitingToSproutState);
-        let state_machine = StateMachine::default()
-            .with_state::<GasAttackState>()
-            .set_trans_logging(false)
-            .trans::<WaitingToSproutState>(HurtByPlayer, SproutingState);
-
-        e_cmds.insert(state_machine);
+        e_cmds.insert(AsepriteBundle {
+            aseprite: asset_server.load(RedMushling::PATH),
+            animation,
+            transform: *transform,
+            ..Default::default()
+        });
     }
 }
 
@@ -45,7 +36,7 @@ pub struct SproutingState;
 #[derive(Clone, Component, Reflect)]
 #[component(storage = "SparseSet")]
 pub struct GasAttackState {
-    hitbox: Option<Entity>,
+    pub hitbox: Option<Entity>,
 }
 #[derive(Clone, Component, Reflect)]
 #[component(storage = "SparseSet")]
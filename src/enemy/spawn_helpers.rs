@@ -5,6 +5,8 @@ use crate::{
     GameParam,
 };
 
+use super::spawner::MobSpawnRule;
+
 pub fn can_spawn_mob_here(pos: Vec2, game: &GameParam, proto_param: &ProtoParam) -> bool {
     let tile_pos = world_pos_to_tile_pos(pos);
     if let Some(_existing_object) = game.get_obj_entity_at_tile(tile_pos, &proto_param) {
@@ -21,3 +23,16 @@ pub fn can_spawn_mob_here(pos: Vec2, game: &GameParam, proto_param: &ProtoParam)
 
     true
 }
+
+/// Checks the tile under `pos` against the spawn rule's allowed tiles.
+/// Returns false if the tile is not loaded.
+pub fn is_tile_allowed_for_rule(pos: Vec2, game: &GameParam, rule: &MobSpawnRule) -> bool {
+    let Some(tile_data) = game.get_tile_data(world_pos_to_tile_pos(pos)) else {
+        return false;
+    };
+    rule.allowed_tiles.is_empty()
+        || tile_data
+            .block_type
+            .iter()
+            .all(|block| rule.allowed_tiles.contains(block))
+}
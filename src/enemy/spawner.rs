@@ -2,7 +2,9 @@ use std::time::Duration;
 
 use bevy::{prelude::*, render::view::RenderLayers};
 use bevy_ecs_tilemap::tiles::TilePos;
-use bevy_proto::prelude::{ProtoCommands, Prototypes};
+use bevy_proto::prelude::{
+    ProtoAssetEvent, ProtoCommands, Prototypes, ReflectSchematic, Schematic,
+};
 use rand::{seq::SliceRandom, Rng};
 
 use crate::{
@@ -24,54 +26,133 @@ use crate::{
     GameParam, GameState,
 };
 
-use super::{spawn_helpers::can_spawn_mob_here, CombatAlignment, EliteMob, Mob};
+use super::{
+    companion::Companion,
+    passive::{Baby, Tamed, TamedMobData, TamedMobRegistry},
+    raid::{RaidTable, Raider},
+    spawn_helpers::{can_spawn_mob_here, is_tile_allowed_for_rule},
+    EliteMob, Mob,
+};
 
-pub const MAX_MOB_PER_CHUNK: i32 = 6;
-pub const ELITE_SPAWN_RATE: f32 = 0.07;
+const GROUP_SPREAD: f32 = 16.;
 pub struct SpawnerPlugin;
 impl Plugin for SpawnerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<MobSpawnEvent>()
+            .insert_resource(SpawnTable::default())
             .add_systems(
                 (
                     handle_spawn_mobs,
                     tick_spawner_timers,
                     add_spawners_to_new_chunks,
-                    handle_add_fairy_spawners,
+                    rebuild_chunk_spawners_on_spawn_table_change,
+                    handle_add_new_day_spawners,
                     spawn_one_time_enemies_at_day,
                     reduce_chunk_mob_count_on_mob_death,
                     despawn_out_of_range_mobs,
                 )
                     .in_set(OnUpdate(GameState::Main)),
             )
+            .add_system(reload_spawn_table)
             .add_system(check_mob_count.in_base_set(CoreSet::PreUpdate));
     }
 }
 
+/// Which part of the day a mob is allowed to spawn in
+#[derive(Reflect, FromReflect, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SpawnTimeWindow {
+    #[default]
+    Any,
+    Day,
+    Night,
+}
+
+impl SpawnTimeWindow {
+    pub fn is_active(&self, night_tracker: &NightTracker) -> bool {
+        match self {
+            SpawnTimeWindow::Any => true,
+            SpawnTimeWindow::Day => !night_tracker.is_night(),
+            SpawnTimeWindow::Night => night_tracker.is_night(),
+        }
+    }
+}
+
+/// Describes when, where and how a mob can spawn.
+/// An empty `allowed_tiles` list lets the mob spawn on any land tile.
+#[derive(Reflect, FromReflect, Clone, Debug, Default)]
+pub struct MobSpawnRule {
+    pub mob: Mob,
+    pub weight: f32,
+    pub spawn_cooldown: f32,
+    pub min_days_to_spawn: u8,
+    pub allowed_tiles: Vec<WorldObject>,
+    pub time_window: SpawnTimeWindow,
+    pub min_group_size: u8,
+    pub max_group_size: u8,
+    pub elite_chance: f32,
+    pub num_to_spawn: Option<u32>,
+}
+
+#[derive(Reflect, FromReflect, Clone, Debug, Default)]
+pub struct MobSpawnTable {
+    pub max_mobs_per_chunk: i32,
+    pub spawns: Vec<MobSpawnRule>,
+}
+
+/// Loaded from SpawnTable.prototype.ron. Reloaded when the prototype file changes,
+/// which also rebuilds the spawners of every loaded chunk.
+#[derive(Resource, Schematic, Reflect, FromReflect, Default, Debug, Clone)]
+#[reflect(Schematic)]
+#[schematic(kind = "resource")]
+pub struct SpawnTable {
+    pub overworld: MobSpawnTable,
+    pub dungeon: MobSpawnTable,
+    /// Added to the player's chunk at the start of every new day
+    pub new_day: Vec<MobSpawnRule>,
+    pub raid: RaidTable,
+}
+
 #[derive(Clone, Debug, Default)]
 
 pub struct Spawner {
     pub chunk_pos: IVec2,
-    // pub radius: u32,
-    pub weight: f32,
+    pub rule: MobSpawnRule,
     pub spawn_timer: Timer,
-    pub min_days_to_spawn: u8,
-    pub enemy: Mob,
-    pub num_to_spawn: Option<u32>,
     pub num_spawned: u32,
+    /// Added by `handle_add_new_day_spawners` rather than the chunk's spawn table
+    pub from_new_day: bool,
+}
+impl Spawner {
+    pub fn from_rule(rule: &MobSpawnRule, chunk_pos: IVec2) -> Self {
+        Self {
+            chunk_pos,
+            rule: rule.clone(),
+            spawn_timer: Timer::from_seconds(rule.spawn_cooldown, TimerMode::Once),
+            num_spawned: 0,
+            from_new_day: false,
+        }
+    }
+    pub fn can_spawn(&self, night_tracker: &NightTracker) -> bool {
+        let no_more_spawns_left =
+            self.rule.num_to_spawn.is_some() && self.num_spawned >= self.rule.num_to_spawn.unwrap();
+        self.rule.min_days_to_spawn <= night_tracker.days
+            && self.rule.time_window.is_active(night_tracker)
+            && !no_more_spawns_left
+    }
 }
 impl PartialEq for Spawner {
     fn eq(&self, other: &Self) -> bool {
         self.chunk_pos == other.chunk_pos
-            && self.weight == other.weight
-            && self.min_days_to_spawn == other.min_days_to_spawn
-            && self.enemy == other.enemy
+            && self.rule.weight == other.rule.weight
+            && self.rule.min_days_to_spawn == other.rule.min_days_to_spawn
+            && self.rule.mob == other.rule.mob
     }
 }
 #[derive(Component, Debug)]
 pub struct ChunkSpawners {
     pub spawners: Vec<Spawner>,
     pub spawned_mobs: i32,
+    pub max_mobs: i32,
 }
 
 #[derive(Debug)]
@@ -84,118 +165,81 @@ fn add_spawners_to_new_chunks(
     mut commands: Commands,
     maybe_dungeon: Query<&Dungeon, With<ActiveDimension>>,
     new_chunk_query: Query<(Entity, &Chunk), Added<Chunk>>,
+    spawn_table: Res<SpawnTable>,
 ) {
-    for new_chunk in new_chunk_query.iter() {
-        let mut spawners = vec![];
-        if maybe_dungeon.get_single().is_err() {
-            spawners.push(Spawner {
-                enemy: Mob::SpikeSlime,
-                chunk_pos: new_chunk.1.chunk_pos,
-                weight: 100.,
-                spawn_timer: Timer::from_seconds(50., TimerMode::Once),
-                min_days_to_spawn: 0,
-                num_to_spawn: None,
-                num_spawned: 0,
-            });
-            spawners.push(Spawner {
-                enemy: Mob::FurDevil,
-                chunk_pos: new_chunk.1.chunk_pos,
-                weight: 100.,
-                spawn_timer: Timer::from_seconds(50., TimerMode::Once),
-                min_days_to_spawn: 0,
-                num_to_spawn: None,
-                num_spawned: 0,
-            });
-            spawners.push(Spawner {
-                enemy: Mob::RedMushling,
-                chunk_pos: new_chunk.1.chunk_pos,
-                weight: 200.,
-                spawn_timer: Timer::from_seconds(50., TimerMode::Once),
-                min_days_to_spawn: 0,
-                num_to_spawn: None,
-                num_spawned: 0,
-            });
-            spawners.push(Spawner {
-                enemy: Mob::Hog,
-                chunk_pos: new_chunk.1.chunk_pos,
-                weight: 100.,
-                spawn_timer: Timer::from_seconds(50., TimerMode::Once),
-                min_days_to_spawn: 0,
-                num_to_spawn: None,
-                num_spawned: 0,
-            });
-            spawners.push(Spawner {
-                enemy: Mob::StingFly,
-                chunk_pos: new_chunk.1.chunk_pos,
-                weight: 100.,
-                spawn_timer: Timer::from_seconds(50., TimerMode::Once),
-                min_days_to_spawn: 2,
-                num_to_spawn: None,
-                num_spawned: 0,
-            });
-            spawners.push(Spawner {
-                enemy: Mob::Bushling,
-                chunk_pos: new_chunk.1.chunk_pos,
-                weight: 100.,
-                spawn_timer: Timer::from_seconds(50., TimerMode::Once),
-                min_days_to_spawn: 1,
-                num_to_spawn: None,
-                num_spawned: 0,
-            });
-        } else {
-            spawners.push(Spawner {
-                enemy: Mob::SpikeSlime,
-                chunk_pos: new_chunk.1.chunk_pos,
-                weight: 100.,
-                spawn_timer: Timer::from_seconds(12., TimerMode::Once),
-                min_days_to_spawn: 0,
-                num_to_spawn: None,
-                num_spawned: 0,
-            });
-            spawners.push(Spawner {
-                enemy: Mob::FurDevil,
-                chunk_pos: new_chunk.1.chunk_pos,
-                weight: 100.,
-                spawn_timer: Timer::from_seconds(12., TimerMode::Once),
-                min_days_to_spawn: 0,
-                num_to_spawn: None,
-                num_spawned: 0,
-            });
-            spawners.push(Spawner {
-                enemy: Mob::Bushling,
-                chunk_pos: new_chunk.1.chunk_pos,
-                weight: 100.,
-                spawn_timer: Timer::from_seconds(12., TimerMode::Once),
-                min_days_to_spawn: 0,
-                num_to_spawn: None,
-                num_spawned: 0,
-            });
-        }
-        commands.entity(new_chunk.0).insert(ChunkSpawners {
-            spawners,
+    let table = if maybe_dungeon.get_single().is_err() {
+        &spawn_table.overworld
+    } else {
+        &spawn_table.dungeon
+    };
+    for (e, chunk) in new_chunk_query.iter() {
+        commands.entity(e).insert(ChunkSpawners {
+            spawners: table
+                .spawns
+                .iter()
+                .map(|rule| Spawner::from_rule(rule, chunk.chunk_pos))
+                .collect(),
             spawned_mobs: 0,
+            max_mobs: table.max_mobs_per_chunk,
         });
     }
 }
 
-fn handle_add_fairy_spawners(
+fn rebuild_chunk_spawners_on_spawn_table_change(
+    maybe_dungeon: Query<&Dungeon, With<ActiveDimension>>,
+    mut chunk_query: Query<(&Chunk, &mut ChunkSpawners)>,
+    spawn_table: Res<SpawnTable>,
+) {
+    if !spawn_table.is_changed() || spawn_table.is_added() {
+        return;
+    }
+    let table = if maybe_dungeon.get_single().is_err() {
+        &spawn_table.overworld
+    } else {
+        &spawn_table.dungeon
+    };
+    for (chunk, mut spawners) in chunk_query.iter_mut() {
+        // spawners from past new days aren't in the table, so they are kept as they are
+        let new_day_spawners: Vec<Spawner> = spawners
+            .spawners
+            .drain(..)
+            .filter(|spawner| spawner.from_new_day)
+            .collect();
+        spawners.spawners = table
+            .spawns
+            .iter()
+            .map(|rule| Spawner::from_rule(rule, chunk.chunk_pos))
+            .chain(new_day_spawners)
+            .collect();
+        spawners.max_mobs = table.max_mobs_per_chunk;
+    }
+}
+
+fn reload_spawn_table(mut events: EventReader<ProtoAssetEvent>, mut commands: ProtoCommands) {
+    for event in events.iter() {
+        if event.is_modified("SpawnTable") {
+            info!("Reloading spawn table...");
+            commands.apply("SpawnTable");
+        }
+    }
+}
+
+fn handle_add_new_day_spawners(
     mut chunk_query: Query<(&Chunk, &mut ChunkSpawners)>,
     new_day_event: EventReader<NewDayEvent>,
     player_pos: Query<&GlobalTransform, With<Player>>,
+    spawn_table: Res<SpawnTable>,
 ) {
     if !new_day_event.is_empty() {
         let player_chunk = camera_pos_to_chunk_pos(&player_pos.single().translation().truncate());
         for (chunk, mut spawners) in chunk_query.iter_mut() {
             if chunk.chunk_pos == player_chunk {
-                spawners.spawners.push(Spawner {
-                    enemy: Mob::Fairy,
-                    chunk_pos: player_chunk,
-                    weight: 9999.,
-                    spawn_timer: Timer::from_seconds(60., TimerMode::Once),
-                    min_days_to_spawn: 0,
-                    num_to_spawn: Some(1),
-                    num_spawned: 0,
-                });
+                for rule in spawn_table.new_day.iter() {
+                    spawners.spawners.push(Spawner {
+                        from_new_day: true,
+                        ..Spawner::from_rule(rule, player_chunk)
+                    });
+                }
             }
         }
     }
@@ -230,16 +274,18 @@ fn handle_spawn_mobs(
                 continue;
             }
 
-            if let Ok(picked_spawner) = chunk_spawner
-                .spawners
-                .choose_weighted_mut(&mut rng, |spawner| spawner.weight)
+            if let Ok(picked_spawner) =
+                chunk_spawner
+                    .spawners
+                    .choose_weighted_mut(&mut rng, |spawner| {
+                        if spawner.can_spawn(&night_tracker) {
+                            spawner.rule.weight
+                        } else {
+                            0.
+                        }
+                    })
             {
-                let no_more_spawns_left = picked_spawner.num_to_spawn.is_some()
-                    && picked_spawner.num_spawned >= picked_spawner.num_to_spawn.unwrap();
-                if (picked_spawner.spawn_timer.percent() == 0. || e.bypass_timers)
-                    && picked_spawner.min_days_to_spawn <= night_tracker.days
-                    && !no_more_spawns_left
-                {
+                if picked_spawner.spawn_timer.percent() == 0. || e.bypass_timers {
                     let player_pos = player_t.single().translation().truncate();
                     let mut pos = player_pos.clone();
                     let mut can_spawn_mob_here_check = false;
@@ -256,42 +302,61 @@ fn handle_spawn_mobs(
                             TileMapPosition::new(picked_spawner.chunk_pos, tile_pos),
                             true,
                         );
-                        can_spawn_mob_here_check = can_spawn_mob_here(pos, &game, &proto_param);
+                        can_spawn_mob_here_check =
+                            is_tile_allowed_for_rule(pos, &game, &picked_spawner.rule)
+                                && can_spawn_mob_here(pos, &game, &proto_param);
                         fallback_attempts -= 1;
                     }
                     picked_spawner.spawn_timer.tick(Duration::from_nanos(1));
-                    picked_mob_to_spawn = Some((picked_spawner.enemy.clone(), pos));
+                    let group_size = rng.gen_range(
+                        picked_spawner.rule.min_group_size.max(1)
+                            ..=picked_spawner
+                                .rule
+                                .max_group_size
+                                .max(picked_spawner.rule.min_group_size.max(1)),
+                    );
+                    picked_mob_to_spawn = Some((picked_spawner.rule.clone(), pos, group_size));
 
                     picked_spawner.num_spawned += 1;
                 }
             }
         }
-        if let Some((mob, pos)) = picked_mob_to_spawn {
-            spawners
-                .get_mut(game.get_chunk_entity(e.chunk_pos).unwrap())
-                .unwrap()
-                .spawned_mobs += 1;
-
-            if let Some(spawned_mob) =
-                proto_commands.spawn_from_proto(mob.clone(), &prototypes, pos)
-            {
-                if mob.clone() == Mob::Fairy {
-                    println!("SPAWNED A FAIRY!!!");
-                    spawn_screen_locked_icon(
-                        spawned_mob,
-                        &mut commands,
-                        &game.graphics,
-                        &asset_server,
-                        WorldObject::Essence,
-                    );
+        if let Some((rule, pos, group_size)) = picked_mob_to_spawn {
+            let chunk_spawners = spawners.get(chunk_e).unwrap();
+            // each group member counts towards the chunk cap, the first one always spawns
+            let room = (chunk_spawners.max_mobs - chunk_spawners.spawned_mobs).max(1) as usize;
+            let mut group_positions = vec![pos];
+            for _ in 1..(group_size as usize).min(room) {
+                let offset = Vec2::new(
+                    rng.gen_range(-GROUP_SPREAD..GROUP_SPREAD),
+                    rng.gen_range(-GROUP_SPREAD..GROUP_SPREAD),
+                );
+                // group members can land in other chunks, so check the tile exists first
+                if is_tile_allowed_for_rule(pos + offset, &game, &rule)
+                    && can_spawn_mob_here(pos + offset, &game, &proto_param)
+                {
+                    group_positions.push(pos + offset);
                 }
-                if rng.gen::<f32>() < ELITE_SPAWN_RATE
-                    && !(proto_param
-                        .get_component::<CombatAlignment, _>(mob)
-                        .expect("mob has no alignment")
-                        == &CombatAlignment::Passive)
+            }
+            spawners.get_mut(chunk_e).unwrap().spawned_mobs += group_positions.len() as i32;
+
+            for pos in group_positions {
+                if let Some(spawned_mob) =
+                    proto_commands.spawn_from_proto(rule.mob.clone(), &prototypes, pos)
                 {
-                    commands.entity(spawned_mob).insert(EliteMob);
+                    if rule.mob == Mob::Fairy {
+                        info!("Spawned a fairy at {pos:?}");
+                        spawn_screen_locked_icon(
+                            spawned_mob,
+                            &mut commands,
+                            &game.graphics,
+                            &asset_server,
+                            WorldObject::Essence,
+                        );
+                    }
+                    if rng.gen::<f32>() < rule.elite_chance {
+                        commands.entity(spawned_mob).insert(EliteMob);
+                    }
                 }
             }
         }
@@ -301,9 +366,16 @@ fn reduce_chunk_mob_count_on_mob_death(
     mut death_events: EventReader<EnemyDeathEvent>,
     game: GameParam,
     mut spawners: Query<&mut ChunkSpawners>,
+    raiders: Query<&Raider>,
 ) {
     for death in death_events.iter() {
-        let chunk = camera_pos_to_chunk_pos(&death.enemy_pos);
+        let chunk = match raiders.get(death.entity) {
+            Ok(raider) => match raider.counted_chunk {
+                Some(chunk) => chunk,
+                None => continue,
+            },
+            Err(_) => camera_pos_to_chunk_pos(&death.enemy_pos),
+        };
         if let Some(chunk_entity) = game.get_chunk_entity(chunk) {
             if let Ok(mut chunk_spawner) = spawners.get_mut(chunk_entity) {
                 chunk_spawner.spawned_mobs -= 1;
@@ -319,7 +391,7 @@ fn check_mob_count(
     // and if so, send event to spawn more
     for (_e, chunk, spawners) in chunk_query.iter() {
         let chunk_pos = chunk.chunk_pos;
-        if spawners.spawned_mobs >= MAX_MOB_PER_CHUNK {
+        if spawners.spawned_mobs >= spawners.max_mobs {
             continue;
         }
         spawn_event.send(MobSpawnEvent {
@@ -331,11 +403,23 @@ fn check_mob_count(
 fn despawn_out_of_range_mobs(
     game: GameParam,
     mut commands: Commands,
-    mut query: Query<(Entity, &Transform), With<Mob>>,
+    mut query: Query<(
+        Entity,
+        &Transform,
+        &Mob,
+        Option<&Tamed>,
+        Option<&Baby>,
+        Option<&Companion>,
+    )>,
+    mut tamed_registry: ResMut<TamedMobRegistry>,
 ) {
-    for (e, t) in query.iter_mut() {
+    for (e, t, mob, tamed_option, baby_option, companion_option) in query.iter_mut() {
         let chunk_pos = camera_pos_to_chunk_pos(&t.translation.truncate());
         if game.get_chunk_entity(chunk_pos).is_none() {
+            // tamed mobs are kept around and respawned once their pen is loaded again
+            if let Some(tamed) = tamed_option {
+                tamed_registry.store(TamedMobData::new(mob, tamed, baby_option, companion_option));
+            }
             commands.entity(e).despawn_recursive();
         }
     }
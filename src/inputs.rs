@@ -1,22 +1,20 @@
 use std::time::Duration;
 
+use crate::action_map::{
+    ActionInput, ActionMap, InputAction, InputDevices, HOTBAR_ACTIONS, MOVE_ACTIONS,
+};
 use crate::animations::enemy_sprites::{CharacterAnimationSpriteSheetData, EnemyAnimationState};
 use crate::animations::AttackEvent;
 use crate::attributes::hunger::Hunger;
 use crate::enemy::spawner::ChunkSpawners;
 use crate::juice::{DustParticles, RunDustTimer};
-use crate::player::MovePlayerEvent;
-use crate::world::dimension::DimensionSpawnEvent;
-use crate::world::dungeon::spawn_new_dungeon_dimension;
 use bevy::prelude::*;
-use bevy::transform::TransformSystem;
 use bevy::window::PrimaryWindow;
-use bevy_ecs_tilemap::tiles::TilePos;
 
 use bevy_hanabi::EffectSpawner;
-use bevy_proto::prelude::{ProtoCommands, ReflectSchematic, Schematic};
+use bevy_proto::prelude::{ReflectSchematic, Schematic};
 
-use bevy_rapier2d::prelude::{KinematicCharacterController, PhysicsSet};
+use bevy_rapier2d::prelude::KinematicCharacterController;
 use interpolation::Lerp;
 use rand::rngs::ThreadRng;
 use rand::seq::IteratorRandom;
@@ -25,37 +23,31 @@ use rand::Rng;
 use crate::attributes::Speed;
 use crate::combat::{AttackTimer, HitEvent};
 
-use crate::enemy::Mob;
+use crate::enemy::passive::{Baby, Breedable, FeedMobEvent};
+use crate::enemy::{CombatAlignment, Mob};
 use crate::inventory::Inventory;
 use crate::item::item_actions::{ItemActionParam, ItemActions, ManaCost};
-use crate::item::item_upgrades::{
-    ArrowSpeedUpgrade, BowUpgradeSpread, BurnOnHitUpgrade, ClawUpgradeMultiThrow,
-    FireStaffAOEUpgrade, LethalHitUpgrade, LightningStaffChainUpgrade, VenomOnHitUpgrade,
-};
 use crate::item::object_actions::ObjectAction;
 use crate::item::projectile::{RangedAttack, RangedAttackEvent};
 use crate::item::Equipment;
 use crate::proto::proto_param::ProtoParam;
+use crate::settings::Settings;
 use crate::ui::minimap::UpdateMiniMapEvent;
 use crate::ui::{change_hotbar_slot, EssenceShopChoices, InventoryState, UIState};
 use crate::world::chunk::Chunk;
 
 use crate::world::world_helpers::{tile_pos_to_world_pos, world_pos_to_tile_pos};
-use crate::world::TileMapPosition;
+use crate::world::TILE_SIZE;
 use crate::{
-    custom_commands::CommandsExt, AppExt, CustomFlush, GameParam, GameState, MainCamera,
-    RawPosition, TextureCamera, UICamera, PLAYER_MOVE_SPEED, WIDTH,
+    AppExt, CustomFlush, GameParam, GameState, MainCamera, TextureCamera, PLAYER_MOVE_SPEED,
 };
-use crate::{Game, GameUpscale, Player, DEBUG_MODE, HEIGHT, PLAYER_DASH_SPEED, TIME_STEP};
+use crate::{Game, GameUpscale, GameViewport, Player, DEBUG_MODE, PLAYER_DASH_SPEED, TIME_STEP};
+
+/// How far away a hostile can be for aim assist to snap to it
+const AIM_ASSIST_RANGE: f32 = 96.;
+/// How far off the stick's direction a hostile can be, either way, in radians
+const AIM_ASSIST_CONE: f32 = 0.5;
 
-const HOTBAR_KEYCODES: [KeyCode; 6] = [
-    KeyCode::Key1,
-    KeyCode::Key2,
-    KeyCode::Key3,
-    KeyCode::Key4,
-    KeyCode::Key5,
-    KeyCode::Key6,
-];
 pub struct InputsPlugin;
 
 impl Plugin for InputsPlugin {
@@ -69,8 +61,10 @@ impl Plugin for InputsPlugin {
             .add_systems(
                 (
                     move_player,
-                    turn_player,
-                    mouse_click_system.after(CustomFlush),
+                    turn_player.after(update_player_aim),
+                    mouse_click_system
+                        .after(CustomFlush)
+                        .after(update_player_aim),
                     handle_hotbar_key_input,
                     tick_dash_timer,
                     toggle_inventory,
@@ -82,11 +76,14 @@ impl Plugin for InputsPlugin {
             )
             .add_system(update_cursor_pos.after(move_player))
             .add_system(
-                move_camera_with_player
-                    .after(PhysicsSet::SyncBackendFlush)
-                    .before(TransformSystem::TransformPropagate)
-                    .in_base_set(CoreSet::PostUpdate)
-                    .run_if(in_state(GameState::Main)),
+                update_gamepad_aim
+                    .after(update_cursor_pos)
+                    .in_set(OnUpdate(GameState::Main)),
+            )
+            .add_system(
+                update_player_aim
+                    .after(update_gamepad_aim)
+                    .in_set(OnUpdate(GameState::Main)),
             );
     }
 }
@@ -96,6 +93,8 @@ pub struct CursorPos {
     pub world_coords: Vec3,
     pub screen_coords: Vec3,
     pub ui_coords: Vec3,
+    /// Set while aiming with a gamepad's right stick, until the mouse moves again
+    pub gamepad_aim: Option<Vec2>,
 }
 
 #[derive(Component, Debug, Default)]
@@ -168,41 +167,17 @@ impl FacingDirection {
     }
 }
 
+/// Faces the player the way they're aiming, not the way they're moving
 fn turn_player(
     mut game: ResMut<Game>,
     player_query: Query<&FacingDirection, With<Player>>,
-    cursor_pos: Res<CursorPos>,
     mut commands: Commands,
 ) {
-    let dir = if cursor_pos.screen_coords.x > WIDTH / 2. {
-        if cursor_pos.screen_coords.x < 3. * WIDTH / 4.
-            && cursor_pos.screen_coords.y > HEIGHT * 0.65
-        {
-            FacingDirection::Up
-        } else if cursor_pos.screen_coords.x < 3. * WIDTH / 4.
-            && cursor_pos.screen_coords.y < HEIGHT * 0.35
-        {
-            FacingDirection::Down
-        } else {
-            FacingDirection::Right
-        }
-    } else {
-        if cursor_pos.screen_coords.x > WIDTH / 4. && cursor_pos.screen_coords.y > HEIGHT * 0.65 {
-            FacingDirection::Up
-        } else if cursor_pos.screen_coords.x > WIDTH / 4.
-            && cursor_pos.screen_coords.y < HEIGHT * 0.35
-        {
-            FacingDirection::Down
-        } else {
-            FacingDirection::Left
-        }
-    };
-    //TODO: make center point based on player pos on screen?
-    //TODO: add some way for attack to know dir
+    let dir = FacingDirection::from_translation(game.player_state.aim);
     let curr_dir = player_query.single();
     if &dir != curr_dir {
         commands.entity(game.player).insert(dir.clone());
-        game.player_state.direction = dir.clone();
+        game.player_state.direction = dir;
     }
 }
 pub fn move_player(
@@ -227,12 +202,13 @@ pub fn move_player(
         ),
     >,
     time: Res<Time>,
-    key_input: ResMut<Input<KeyCode>>,
+    actions: ActionInput,
     mut minimap_event: EventWriter<UpdateMiniMapEvent>,
     mut commands: Commands,
     mut particle: Query<&mut EffectSpawner, With<DustParticles>>,
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
     mut audio_timer: Local<Timer>,
 ) {
     if audio_timer.duration() == Duration::ZERO {
@@ -260,32 +236,38 @@ pub fn move_player(
         * (1. + speed.0 as f32 / 100.)
         * (if hunger.is_starving() { 0.7 } else { 1. });
 
-    if key_input.pressed(KeyCode::A) {
+    if actions.pressed(InputAction::MoveLeft) {
         d.x -= 1.;
         player.is_moving = true;
     }
-    if key_input.pressed(KeyCode::D) {
+    if actions.pressed(InputAction::MoveRight) {
         d.x += 1.;
         player.is_moving = true;
     }
-    if key_input.pressed(KeyCode::W) {
+    if actions.pressed(InputAction::MoveUp) {
         d.y += 1.;
         player.is_moving = true;
     }
-    if key_input.pressed(KeyCode::S) {
+    if actions.pressed(InputAction::MoveDown) {
         d.y -= 1.;
         player.is_moving = true;
     }
+    // the left stick only steers when no movement keys are held
+    if d == Vec2::ZERO {
+        d = actions.get_move_stick();
+        if d != Vec2::ZERO {
+            player.is_moving = true;
+        }
+    }
     //TODO: move this tick to animations.rs
     if player.player_dash_cooldown.tick(time.delta()).finished()
-        && key_input.pressed(KeyCode::Space)
+        && actions.pressed(InputAction::Dash)
     {
         player.is_dashing = true;
 
         player.player_dash_cooldown.reset();
     }
-    if (key_input.any_just_released([KeyCode::A, KeyCode::D, KeyCode::S, KeyCode::W])
-        && !key_input.any_pressed([KeyCode::A, KeyCode::D, KeyCode::S, KeyCode::W]))
+    if (actions.any_just_released(MOVE_ACTIONS) && !actions.any_pressed(MOVE_ACTIONS))
         || (d.x == 0. && d.y == 0.)
     {
         player.is_moving = false;
@@ -358,7 +340,10 @@ pub fn move_player(
             let walk5 = asset_server.load("sounds/walk_grass5.ogg");
             let walks = vec![walk1, walk2, walk3, walk4, walk5];
             walks.iter().choose(&mut rand::thread_rng()).map(|sound| {
-                audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.35))
+                audio.play_with_settings(
+                    sound.clone(),
+                    PlaybackSettings::ONCE.with_volume(0.35 * settings.get_sfx_volume()),
+                )
             });
         }
     } else {
@@ -379,114 +364,143 @@ pub fn tick_dash_timer(mut game: GameParam, time: Res<Time>) {
         }
     }
 }
-pub fn close_container(
-    key_input: ResMut<Input<KeyCode>>,
-    mut next_inv_state: ResMut<NextState<UIState>>,
-) {
-    if key_input.just_pressed(KeyCode::Escape) {
+pub fn close_container(actions: ActionInput, mut next_inv_state: ResMut<NextState<UIState>>) {
+    if actions.just_pressed(InputAction::CloseMenu) {
         next_inv_state.set(UIState::Closed);
     }
 }
-pub fn toggle_inventory(
-    mut game: GameParam,
-    key_input: ResMut<Input<KeyCode>>,
-    mut commands: Commands,
-    mut proto_commands: ProtoCommands,
-    mut dim_event: EventWriter<DimensionSpawnEvent>,
-    proto: ProtoParam,
-    _inv: Query<&mut Inventory>,
-    mut move_player_event: EventWriter<MovePlayerEvent>,
-    mut next_ui_state: ResMut<NextState<UIState>>,
-) {
-    if key_input.just_pressed(KeyCode::I)
-        || key_input.just_pressed(KeyCode::Tab)
-        || key_input.just_pressed(KeyCode::E)
-    {
+pub fn toggle_inventory(actions: ActionInput, mut next_ui_state: ResMut<NextState<UIState>>) {
+    if actions.just_pressed(InputAction::Inventory) {
         next_ui_state.set(UIState::Inventory);
     }
-
-    if *DEBUG_MODE {
-        if key_input.just_pressed(KeyCode::P) {
-            spawn_new_dungeon_dimension(
-                &mut game,
-                &mut commands,
-                &mut proto_commands,
-                &mut move_player_event,
-            );
-        }
-        if key_input.just_pressed(KeyCode::O) {
-            dim_event.send(DimensionSpawnEvent {
-                generation_params: proto.get_world_gen().unwrap(),
-                swap_to_dim_now: true,
-            });
-        }
-        if key_input.just_pressed(KeyCode::U) {
-            commands
-                .entity(game.game.player)
-                .insert(FireStaffAOEUpgrade)
-                .insert(LightningStaffChainUpgrade)
-                .insert(BowUpgradeSpread(2))
-                .insert(ArrowSpeedUpgrade(1.))
-                .insert(BurnOnHitUpgrade)
-                .insert(VenomOnHitUpgrade)
-                .insert(LethalHitUpgrade)
-                .insert(ClawUpgradeMultiThrow(
-                    Timer::from_seconds(0.1, TimerMode::Once),
-                    2,
-                ));
-        }
-        if key_input.just_pressed(KeyCode::L) {
-            let pos = tile_pos_to_world_pos(
-                TileMapPosition::new(IVec2 { x: 0, y: 0 }, TilePos { x: 0, y: 0 }),
-                true,
-            );
-            // proto_commands.spawn_from_proto(Mob::Slime, &proto.prototypes, pos);
-            // proto_commands.spawn_from_proto(Mob::StingFly, &proto.prototypes, pos);
-            // proto_commands.spawn_from_proto(Mob::Bushling, &proto.prototypes, pos);
-            // proto_commands.spawn_from_proto(Mob::Fairy, &proto.prototypes, pos);
-            // proto_commands.spawn_from_proto(Mob::RedMushling, &proto.prototypes, pos);
-            // let f = proto_commands.spawn_from_proto(Mob::FurDevil, &proto.prototypes, pos);
-            // commands.entity(f.unwrap()).insert(MobLevel(2));
-            // proto_commands.spawn_from_proto(Mob::Slime, &proto.prototypes, pos);
-        }
-    }
 }
 fn handle_hotbar_key_input(
     mut game: GameParam,
+    action_map: Res<ActionMap>,
     mut key_input: ResMut<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
     mut inv_state: ResMut<InventoryState>,
 ) {
-    for (slot, key) in HOTBAR_KEYCODES.iter().enumerate() {
-        if key_input.just_pressed(*key) {
-            change_hotbar_slot(slot, &mut inv_state, &mut game.inv_slot_query);
-            key_input.clear();
+    let new_slot = {
+        let devices = InputDevices {
+            keys: &key_input,
+            mouse: &mouse_input,
+            gamepad: &gamepad_input,
+        };
+        let active_slot = inv_state.active_hotbar_slot;
+        let num_slots = HOTBAR_ACTIONS.len();
+        if action_map.just_pressed(InputAction::HotbarNext, &devices) {
+            Some((active_slot + 1) % num_slots)
+        } else if action_map.just_pressed(InputAction::HotbarPrev, &devices) {
+            Some((active_slot + num_slots - 1) % num_slots)
+        } else {
+            HOTBAR_ACTIONS
+                .iter()
+                .position(|action| action_map.just_pressed(*action, &devices))
         }
+    };
+    if let Some(slot) = new_slot {
+        change_hotbar_slot(slot, &mut inv_state, &mut game.inv_slot_query);
+        key_input.clear();
     }
 }
 pub fn update_cursor_pos(
     windows: Query<&Window, With<PrimaryWindow>>,
     camera_q: Query<(&Transform, &Camera), With<TextureCamera>>,
+    game_upscale: Query<&GameUpscale, With<MainCamera>>,
+    viewport: Res<GameViewport>,
     mut cursor_moved_events: EventReader<CursorMoved>,
     mut cursor_pos: ResMut<CursorPos>,
 ) {
+    // the game may be letterboxed, so the cursor is mapped onto the area it's drawn in
+    let display_size = viewport.size * game_upscale.single().0;
     for cursor_moved in cursor_moved_events.iter() {
         // To get the mouse's world position, we have to transform its window position by
         // any transforms on the camera. This is done by projecting the cursor position into
         // camera space (world space).
         for (cam_t, cam) in camera_q.iter() {
             *cursor_pos = CursorPos {
-                world_coords: cursor_pos_in_world(&windows, cursor_moved.position, cam_t, cam),
-                ui_coords: cursor_pos_in_ui(&windows, cursor_moved.position, cam),
+                world_coords: cursor_pos_in_world(
+                    &windows,
+                    cursor_moved.position,
+                    display_size,
+                    cam_t,
+                    cam,
+                ),
+                ui_coords: cursor_pos_in_ui(&windows, cursor_moved.position, display_size, cam),
                 screen_coords: cursor_moved.position.extend(0.),
+                gamepad_aim: None,
             };
         }
     }
 }
+/// Aims with the right stick by putting the cursor on the tile next to the player
+/// in that direction, so hitting and placing blocks works without a mouse
+pub fn update_gamepad_aim(
+    actions: ActionInput,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    mut cursor_pos: ResMut<CursorPos>,
+) {
+    let stick = actions.get_aim_stick();
+    if stick != Vec2::ZERO {
+        cursor_pos.gamepad_aim = Some(stick.normalize());
+    }
+    let Some(direction) = cursor_pos.gamepad_aim else {
+        return;
+    };
+    let player_pos = player_query.single().translation().truncate();
+    let target_tile = world_pos_to_tile_pos(player_pos + direction.round() * TILE_SIZE.x);
+    cursor_pos.world_coords = tile_pos_to_world_pos(target_tile, true).extend(0.);
+}
+/// Aims along the right stick, which is more precise than the tile its cursor snaps to,
+/// or towards the mouse. Facing, melee swings and projectiles all follow the aim, so the
+/// player can move one way and attack another. Aim assist turns stick aim onto the
+/// nearest hostile in its cone.
+pub fn update_player_aim(
+    mut game: ResMut<Game>,
+    cursor_pos: Res<CursorPos>,
+    settings: Res<Settings>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    mobs: Query<(Entity, &GlobalTransform, &CombatAlignment), With<Mob>>,
+) {
+    let Ok(player_t) = player_query.get_single() else {
+        return;
+    };
+    let player_pos = player_t.translation().truncate();
+    let direction = cursor_pos
+        .gamepad_aim
+        .unwrap_or_else(|| (cursor_pos.world_coords.truncate() - player_pos).normalize_or_zero());
+    let aim = &mut game.player_state;
+    aim.aim_assist_target = None;
+    if direction == Vec2::ZERO {
+        return;
+    }
+    aim.aim = direction;
+    // the mouse is already precise
+    if !settings.aim_assist || cursor_pos.gamepad_aim.is_none() {
+        return;
+    }
+    let nearest_hostile = mobs
+        .iter()
+        .filter(|(_, _, alignment)| **alignment == CombatAlignment::Hostile)
+        .map(|(e, t, _)| (e, t.translation().truncate() - player_pos))
+        .filter(|(_, offset)| {
+            offset.length() <= AIM_ASSIST_RANGE
+                && offset.angle_between(direction).abs() <= AIM_ASSIST_CONE
+        })
+        .min_by(|(_, a), (_, b)| a.length().total_cmp(&b.length()));
+    if let Some((hostile, offset)) = nearest_hostile {
+        aim.aim = offset.normalize();
+        aim.aim_assist_target = Some(hostile);
+    }
+}
 // Converts the cursor position into a world position, taking into account any transforms applied
 // the camera.
 pub fn cursor_pos_in_world(
     windows: &Query<&Window, With<PrimaryWindow>>,
     cursor_pos: Vec2,
+    display_size: Vec2,
     cam_t: &Transform,
     cam: &Camera,
 ) -> Vec3 {
@@ -494,26 +508,27 @@ pub fn cursor_pos_in_world(
 
     let window_size = Vec2::new(window.width(), window.height());
 
-    // Convert screen position [0..resolution] to ndc [-1..1]
+    // Convert the position on the drawn game [0..display_size] to ndc [-1..1]
     // (ndc = normalized device coordinates)
     let ndc_to_world = cam_t.compute_matrix() * cam.projection_matrix().inverse();
-    let ndc = (cursor_pos / window_size) * 2.0 - Vec2::ONE;
+    let ndc = (cursor_pos - window_size / 2.) / (display_size / 2.);
     ndc_to_world.project_point3(ndc.extend(0.0))
 }
 pub fn cursor_pos_in_ui(
     windows: &Query<&Window, With<PrimaryWindow>>,
     cursor_pos: Vec2,
+    display_size: Vec2,
     cam: &Camera,
 ) -> Vec3 {
     let window = windows.single();
 
     let window_size = Vec2::new(window.width(), window.height());
 
-    // Convert screen position [0..resolution] to ndc [-1..1]
+    // Convert the position on the drawn game [0..display_size] to ndc [-1..1]
     // (ndc = normalized device coordinates)
     let t = Transform::from_translation(Vec3::new(0., 0., 0.));
     let ndc_to_world = t.compute_matrix() * cam.projection_matrix().inverse();
-    let ndc = (cursor_pos / window_size) * 2.0 - Vec2::ONE;
+    let ndc = (cursor_pos - window_size / 2.) / (display_size / 2.);
     ndc_to_world.project_point3(ndc.extend(0.0))
 }
 pub fn diagnostics(
@@ -530,7 +545,7 @@ pub fn diagnostics(
 }
 pub fn mouse_click_system(
     mut commands: Commands,
-    mouse_button_input: Res<Input<MouseButton>>,
+    actions: ActionInput,
     cursor_pos: Res<CursorPos>,
     mut game: GameParam,
     mut proto_param: ProtoParam,
@@ -545,6 +560,7 @@ pub fn mouse_click_system(
     mut ranged_attack_event: EventWriter<RangedAttackEvent>,
     mut item_action_param: ItemActionParam,
     obj_actions: Query<&ObjectAction>,
+    feedable_mobs: Query<(Entity, &GlobalTransform, &Breedable), (With<Mob>, Without<Baby>)>,
 ) {
     if ui_state.0 != UIState::Closed {
         return;
@@ -554,7 +570,7 @@ pub fn mouse_click_system(
     let player_pos = game.player().position;
     let (player_e, attack_timer_option) = player_query.single();
     // Hit Item, send attack event
-    if mouse_button_input.pressed(MouseButton::Left) {
+    if actions.pressed(InputAction::Attack) {
         if *DEBUG_MODE {
             println!("C: {cursor_tile_pos:?}",);
         }
@@ -564,13 +580,15 @@ pub fn mouse_click_system(
         let mut main_hand_option = None;
         // if it has AttackTimer, the action is on cooldown, so we abort.
         if let Some(tool) = &game.player().main_hand_slot {
-            main_hand_option = Some(tool.get_obj());
+            // broken tools and weapons attack like an empty hand until they are repaired
+            if !tool.item_stack.attributes.is_broken() {
+                main_hand_option = Some(tool.get_obj());
+            }
         }
-        let direction =
-            (cursor_pos.world_coords.truncate() - player_pos.truncate()).normalize_or_zero();
-        if let Ok(ranged_tool) = ranged_query.get_single() {
-            let mana_cost_option =
-                proto_param.get_component::<ManaCost, _>(main_hand_option.unwrap());
+        let direction = game.player().aim;
+        let ranged_tool_option = main_hand_option.and_then(|_| ranged_query.get_single().ok());
+        if let (Some(main_hand), Some(ranged_tool)) = (main_hand_option, ranged_tool_option) {
+            let mana_cost_option = proto_param.get_component::<ManaCost, _>(main_hand);
             ranged_attack_event.send(RangedAttackEvent {
                 projectile: ranged_tool.0.clone(),
                 direction,
@@ -588,7 +606,7 @@ pub fn mouse_click_system(
             .truncate()
             .distance(cursor_pos.world_coords.truncate())
             > game.player().reach_distance * 32.
-            || ranged_query.get_single().is_ok()
+            || ranged_tool_option.is_some()
         {
             return;
         }
@@ -606,11 +624,23 @@ pub fn mouse_click_system(
         }
     }
     // Attempt to place block in hand
-    if mouse_button_input.just_pressed(MouseButton::Right) {
+    if actions.just_pressed(InputAction::UseItem) {
         let hotbar_slot = inv_state.active_hotbar_slot;
         let held_item_option = inv.single().items.items[hotbar_slot].clone();
         if let Some(held_item) = held_item_option {
             let held_obj = *held_item.get_obj();
+            // feeding a mob its food takes priority over the item's own action
+            if let Some((mob_e, _, _)) = feedable_mobs.iter().find(|(_, t, breedable)| {
+                let mob_pos = t.translation().truncate();
+                breedable.food == held_obj
+                    && mob_pos.distance(cursor_pos.world_coords.truncate()) <= 12.
+                    && mob_pos.distance(player_pos.truncate()) <= game.player().reach_distance * 32.
+            }) {
+                item_action_param
+                    .feed_mob_event
+                    .send(FeedMobEvent { mob: mob_e });
+                return;
+            }
             if let Some(item_actions) = proto_param.get_component::<ItemActions, _>(held_obj) {
                 item_actions.run_action(held_obj, &mut item_action_param, &mut game, &proto_param);
             }
@@ -632,12 +662,12 @@ pub fn mouse_click_system(
 
 pub fn handle_open_essence_ui(
     mut commands: Commands,
-    key_input: ResMut<Input<KeyCode>>,
+    actions: ActionInput,
     player_query: Query<&GlobalTransform, With<Player>>,
     nearby_merchant_query: Query<(&GlobalTransform, &EssenceShopChoices)>,
     mut next_inv_state: ResMut<NextState<UIState>>,
 ) {
-    if key_input.just_pressed(KeyCode::F) {
+    if actions.just_pressed(InputAction::Interact) {
         let player_t = player_query.single().translation().truncate();
         for (transform, choices) in nearby_merchant_query.iter() {
             if player_t.distance(transform.translation().truncate()) < 32. {
@@ -647,46 +677,3 @@ pub fn handle_open_essence_ui(
         }
     }
 }
-
-pub fn move_camera_with_player(
-    player_query: Query<
-        (&Transform, &RawPosition, &MovementVector),
-        (
-            With<Player>,
-            // Changed<Transform>,
-            Without<MainCamera>,
-            Without<TextureCamera>,
-            Without<UICamera>,
-        ),
-    >,
-    mut game_camera: Query<
-        (&mut Transform, &mut RawPosition),
-        (Without<MainCamera>, Without<UICamera>, With<TextureCamera>),
-    >,
-    mut screen_camera: Query<
-        (&mut Transform, &GameUpscale),
-        (With<MainCamera>, Without<UICamera>, Without<TextureCamera>),
-    >,
-    time: Res<Time>,
-) {
-    let (mut game_camera_transform, mut raw_camera_pos) = game_camera.single_mut();
-    let (_player_pos, raw_player_pos, _player_movement_vec) = player_query.single();
-
-    let camera_lookahead_scale = 4.0;
-    let delta = raw_player_pos.0 - raw_camera_pos.0;
-    raw_camera_pos.0 = raw_camera_pos.0 + delta * camera_lookahead_scale * time.delta_seconds();
-
-    let decimals = 10i32.pow(3) as f32;
-
-    let camera_final_pos = Vec2::new(raw_camera_pos.x, raw_camera_pos.y);
-    let camera_final_pos = Vec2::new(
-        (camera_final_pos.x * decimals).round() / decimals,
-        (camera_final_pos.y * decimals).round() / decimals,
-    );
-
-    game_camera_transform.translation.x = camera_final_pos.x.trunc();
-    game_camera_transform.translation.y = camera_final_pos.y.trunc();
-    let (mut screen_camera_transform, game_upscale) = screen_camera.single_mut();
-    screen_camera_transform.translation.x = camera_final_pos.x.fract() * game_upscale.0;
-    screen_camera_transform.translation.y = camera_final_pos.y.fract() * game_upscale.0;
-}
@@ -1,36 +1,50 @@
+use std::collections::VecDeque;
+
 use bevy::{prelude::*, utils::HashMap};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    attributes::{attribute_helpers::reroll_item_bonus_attributes, AttributeModifier},
-    colors::YELLOW,
+    attributes::attribute_helpers::create_new_random_item_stack_with_attributes,
+    colors::{RED, YELLOW},
     container::Container,
     inventory::{Inventory, InventoryItemStack},
     item::WorldObject,
-    player::Player,
+    player::{levels::PlayerLevel, Player},
     proto::proto_param::ProtoParam,
     ui::{
-        crafting_ui::CraftingContainerType, damage_numbers::spawn_floating_text_with_shadow,
-        handle_hovering, mark_slot_dirty, FurnaceContainer, FurnaceState, InventorySlotState,
-        InventorySlotType,
+        crafting_ui::{CraftingContainer, CraftingContainerType},
+        damage_numbers::spawn_floating_text_with_shadow,
+        ChestContainer, UIState,
     },
-    GameState,
+    world::TILE_SIZE,
+    GameParam, GameState,
 };
 
+use super::machine::MachineRecipeList;
+
 pub struct CraftingPlugin;
 impl Plugin for CraftingPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(Recipes::default())
+            .insert_resource(CraftQueue::default())
             .add_event::<CraftedItemEvent>()
+            .add_event::<CraftRequestEvent>()
+            .add_event::<UnlockRecipeEvent>()
             .add_systems(
                 (
                     handle_crafting_update_when_inv_changes,
                     handle_crafted_item,
+                    handle_craft_requests,
+                    handle_craft_queue.after(handle_craft_requests),
                     handle_inv_changed_update_crafting_tracker,
-                    handle_furnace_slot_update.after(handle_hovering),
+                    handle_level_up_unlock_recipes,
+                    handle_unlock_recipe_events
+                        .after(handle_inv_changed_update_crafting_tracker)
+                        .after(handle_level_up_unlock_recipes),
                 )
                     .in_set(OnUpdate(GameState::Main)),
-            );
+            )
+            .add_system(clear_craft_queue.in_schedule(OnEnter(GameState::Main)));
     }
 }
 
@@ -38,8 +52,36 @@ impl Plugin for CraftingPlugin {
 pub struct Recipes {
     // map of recipie result and its recipe matrix
     pub crafting_list: RecipeList,
-    pub furnace_list: FurnaceRecipeList,
+    pub machine_list: MachineRecipeList,
     pub upgradeable_items: Vec<WorldObject>,
+    /// How each recipe is learned, recipes without an entry are learned
+    /// by picking up any of their ingredients
+    pub unlocks: HashMap<WorldObject, RecipeUnlock>,
+    /// Material used to repair each item at the Anvil, and how many a full repair takes
+    pub repair_materials: HashMap<WorldObject, RecipeItem>,
+}
+
+impl From<RecipeListProto> for Recipes {
+    fn from(desc: RecipeListProto) -> Self {
+        let (crafting_list, machine_list, upgradeable_items, unlocks, repair_materials) = desc;
+        Self {
+            crafting_list: crafting_list.into_iter().collect(),
+            machine_list,
+            upgradeable_items,
+            unlocks: unlocks.into_iter().collect(),
+            repair_materials: repair_materials.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum RecipeUnlock {
+    /// Learned the first time this item is picked up
+    KeyIngredient(WorldObject),
+    /// Learned by reading a Blueprint, which teaches a random unknown blueprint recipe
+    Blueprint,
+    /// Learned once the player reaches this level
+    Level(u8),
 }
 
 #[derive(Default, Clone, Debug, Deserialize, PartialEq, Eq)]
@@ -49,11 +91,12 @@ pub struct RecipeItem {
 }
 
 pub type RecipeList = HashMap<WorldObject, (Vec<RecipeItem>, CraftingContainerType, usize)>;
-pub type FurnaceRecipeList = HashMap<WorldObject, WorldObject>;
 pub type RecipeListProto = (
     Vec<(WorldObject, (Vec<RecipeItem>, CraftingContainerType, usize))>,
-    Vec<(WorldObject, WorldObject)>,
+    MachineRecipeList,
     Vec<WorldObject>,
+    Vec<(WorldObject, RecipeUnlock)>,
+    Vec<(WorldObject, RecipeItem)>,
 );
 
 #[derive(Resource, Default, Clone, Serialize, Deserialize)]
@@ -68,69 +111,314 @@ pub struct CraftedItemEvent {
     pub obj: WorldObject,
 }
 
+/// Queues a recipe to be crafted `count` times straight into the player's inventory.
+/// A `count` of `usize::MAX` crafts as many as the available ingredients allow.
+pub struct CraftRequestEvent {
+    pub obj: WorldObject,
+    pub count: usize,
+    /// Where to look for chests to pull ingredients from, the player if `None`
+    pub station_pos: Option<Vec2>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CraftJob {
+    pub obj: WorldObject,
+    pub remaining: usize,
+    pub station_pos: Option<Vec2>,
+    pub timer: Timer,
+}
+
+/// Crafts waiting to be made, one at a time from the front
+#[derive(Resource, Default, Debug)]
+pub struct CraftQueue {
+    pub jobs: VecDeque<CraftJob>,
+}
+
+/// Seconds each queued craft takes
+pub const CRAFT_TIME: f32 = 0.5;
+/// Chests within this distance of the crafting station (or the player,
+/// when crafting from the inventory) are used for ingredients
+pub const CHEST_PULL_RADIUS: f32 = 6. * TILE_SIZE.x;
+
+/// Chests within `CHEST_PULL_RADIUS` of `origin`, skipping the open chest
+/// since its resource copy is the live one
+pub fn get_nearby_chests<'a>(
+    origin: Vec2,
+    chests: impl Iterator<Item = (Entity, &'a GlobalTransform, &'a ChestContainer)>,
+    open_chest: Option<&ChestContainer>,
+) -> Vec<Entity> {
+    let open_chest = open_chest.map(|c| c.parent);
+    chests
+        .filter(|(e, t, _)| {
+            Some(*e) != open_chest
+                && t.translation().truncate().distance(origin) <= CHEST_PULL_RADIUS
+        })
+        .map(|(e, _, _)| e)
+        .collect()
+}
+
+pub fn get_ingredient_count(obj: WorldObject, items: &Container, chests: &[&Container]) -> usize {
+    items.get_item_count_in_container(obj)
+        + chests
+            .iter()
+            .map(|chest| chest.get_item_count_in_container(obj))
+            .sum::<usize>()
+}
+
+/// How many times a recipe can be crafted with the items in the container and chests.
+/// Results that don't fit in the inventory are dropped, so this isn't capped to a stack.
+pub fn get_max_crafts(
+    ingredients: &[RecipeItem],
+    items: &Container,
+    chests: &[&Container],
+) -> usize {
+    ingredients
+        .iter()
+        .map(|ingredient| {
+            get_ingredient_count(ingredient.item, items, chests) / ingredient.count.max(1)
+        })
+        .min()
+        .unwrap_or(0)
+}
+
+/// Takes the ingredients for `count` crafts, from the container first and then the chests
+pub fn remove_ingredients(
+    ingredients: &[RecipeItem],
+    count: usize,
+    items: &mut Container,
+    chests: &mut [Mut<ChestContainer>],
+) {
+    for ingredient in ingredients.iter() {
+        let mut remaining = ingredient.count * count;
+        let taken = remaining.min(items.get_item_count_in_container(ingredient.item));
+        if taken > 0 {
+            items
+                .remove_from_inventory(taken, ingredient.item)
+                .expect("checked the container has enough items");
+            remaining -= taken;
+        }
+        for chest in chests.iter_mut() {
+            if remaining == 0 {
+                break;
+            }
+            // only deref mutably when taking, so untouched chests are not marked changed
+            let taken = remaining.min(chest.items.get_item_count_in_container(ingredient.item));
+            if taken > 0 {
+                chest
+                    .items
+                    .remove_from_inventory(taken, ingredient.item)
+                    .expect("checked the chest has enough items");
+                remaining -= taken;
+            }
+        }
+    }
+}
+
+/// Where crafting currently pulls chest ingredients from: the open station, otherwise the player
+fn get_crafting_origin(
+    crafting_option: &Option<Res<CraftingContainer>>,
+    player_t: &Query<&GlobalTransform, With<Player>>,
+) -> Vec2 {
+    crafting_option
+        .as_ref()
+        .and_then(|c| c.station_pos)
+        .unwrap_or_else(|| player_t.single().translation().truncate())
+}
+
 // there will be N craftable items in a given Crafting UI.
 // each has a required list of items to craft it.
-// each time the inventory changes, we re-calculate if any of the craftable items can be crafted.
+// each time the inventory or a nearby chest changes, we re-calculate if any of the craftable items can be crafted.
 // if so, we update the UI to show the item as craftable.
 pub fn handle_crafting_update_when_inv_changes(
-    inv: Query<&Inventory, Changed<Inventory>>,
+    inv: Query<&Inventory>,
+    changed_inv: Query<(), Changed<Inventory>>,
+    changed_chests: Query<(), Changed<ChestContainer>>,
+    chests: Query<(Entity, &GlobalTransform, &ChestContainer)>,
+    chest_option: Option<Res<ChestContainer>>,
+    crafting_option: Option<Res<CraftingContainer>>,
+    ui_state: Res<State<UIState>>,
+    player_t: Query<&GlobalTransform, With<Player>>,
     recipes: Res<Recipes>,
     mut craft_tracker: ResMut<CraftingTracker>,
 ) {
-    if inv.get_single().is_err() {
+    let Ok(inv) = inv.get_single() else {
+        return;
+    };
+    let crafting_changed = crafting_option.as_ref().map_or(false, |c| c.is_changed());
+    if changed_inv.is_empty()
+        && changed_chests.is_empty()
+        && !crafting_changed
+        && !ui_state.is_changed()
+    {
         return;
     }
+    let origin = get_crafting_origin(&crafting_option, &player_t);
+    let nearby_chests: Vec<&Container> =
+        get_nearby_chests(origin, chests.iter(), chest_option.as_deref())
+            .into_iter()
+            .map(|e| &chests.get(e).unwrap().2.items)
+            .collect();
 
-    for (result, recipe) in recipes.crafting_list.clone() {
-        let mut can_craft = true;
-        let inv = inv.single();
-        for ingredient in recipe.0.clone() {
-            if inv.items.get_item_count_in_container(ingredient.item) < ingredient.count {
-                can_craft = false;
-                break;
-            }
-        }
+    for (result, recipe) in recipes.crafting_list.iter() {
+        let can_craft = recipe.0.iter().all(|ingredient| {
+            get_ingredient_count(ingredient.item, &inv.items, &nearby_chests) >= ingredient.count
+        });
         if can_craft {
-            craft_tracker.craftable.push(result);
+            if !craft_tracker.craftable.contains(result) {
+                craft_tracker.craftable.push(*result);
+            }
         } else {
-            craft_tracker.craftable.retain(|x| x != &result);
+            craft_tracker.craftable.retain(|x| x != result);
         }
     }
 }
 pub fn handle_crafted_item(
     mut inv: Query<&mut Inventory>,
     mut events: EventReader<CraftedItemEvent>,
+    mut chests: Query<(Entity, &GlobalTransform, &mut ChestContainer)>,
+    chest_option: Option<Res<ChestContainer>>,
+    crafting_option: Option<Res<CraftingContainer>>,
+    player_t: Query<&GlobalTransform, With<Player>>,
     recipes: Res<Recipes>,
 ) {
     for event in events.iter() {
         let mut inv = inv.single_mut();
-        let mut remaining_cost = recipes
+        let ingredients = &recipes
             .crafting_list
             .get(&event.obj)
             .expect("crafted item does not have recipe?")
-            .0
-            .clone();
-        while remaining_cost.len() > 0 {
-            for item in remaining_cost.clone().iter() {
-                let ingredient_slot = inv
-                    .items
-                    .get_slot_for_item_in_container(&item.item)
-                    .expect("player crafted item but does not have the required ingredients?");
-                let stack = inv.items.items[ingredient_slot].as_mut().unwrap();
-                if stack.item_stack.count >= item.count {
-                    inv.items.items[ingredient_slot] = stack.modify_count(-(item.count as i8));
-                    remaining_cost.retain(|x| x != item);
-                } else {
-                    let count = stack.item_stack.count;
-                    inv.items.items[ingredient_slot] = None;
-                    remaining_cost.retain(|x| x != item);
-                    remaining_cost.push(RecipeItem {
-                        item: item.item.clone(),
-                        count: (item.count - count) as usize,
-                    });
-                }
-            }
+            .0;
+        let origin = get_crafting_origin(&crafting_option, &player_t);
+        let nearby = get_nearby_chests(origin, chests.iter(), chest_option.as_deref());
+        let mut nearby_chests: Vec<Mut<ChestContainer>> = chests
+            .iter_mut()
+            .filter(|(e, _, _)| nearby.contains(e))
+            .map(|(_, _, chest)| chest)
+            .collect();
+        remove_ingredients(ingredients, 1, &mut inv.items, &mut nearby_chests);
+    }
+}
+
+pub fn handle_craft_requests(
+    mut events: EventReader<CraftRequestEvent>,
+    mut craft_queue: ResMut<CraftQueue>,
+    inv: Query<&Inventory>,
+    chests: Query<(Entity, &GlobalTransform, &ChestContainer)>,
+    chest_option: Option<Res<ChestContainer>>,
+    player_t: Query<&GlobalTransform, With<Player>>,
+    recipes: Res<Recipes>,
+) {
+    for event in events.iter() {
+        let Some((ingredients, _, _)) = recipes.crafting_list.get(&event.obj) else {
+            continue;
+        };
+        let origin = event
+            .station_pos
+            .unwrap_or_else(|| player_t.single().translation().truncate());
+        let nearby_chests: Vec<&Container> =
+            get_nearby_chests(origin, chests.iter(), chest_option.as_deref())
+                .into_iter()
+                .map(|e| &chests.get(e).unwrap().2.items)
+                .collect();
+        let count = event.count.min(get_max_crafts(
+            ingredients,
+            &inv.single().items,
+            &nearby_chests,
+        ));
+        if count == 0 {
+            continue;
+        }
+        if let Some(job) = craft_queue
+            .jobs
+            .iter_mut()
+            .find(|job| job.obj == event.obj && job.station_pos == event.station_pos)
+        {
+            job.remaining += count;
+            continue;
         }
+        craft_queue.jobs.push_back(CraftJob {
+            obj: event.obj,
+            remaining: count,
+            station_pos: event.station_pos,
+            timer: Timer::from_seconds(CRAFT_TIME, TimerMode::Once),
+        });
+    }
+}
+
+fn clear_craft_queue(mut craft_queue: ResMut<CraftQueue>) {
+    craft_queue.jobs.clear();
+}
+
+/// Works through the front of the craft queue, re-checking ingredients on each craft
+/// in case they were used or moved since the job was queued
+pub fn handle_craft_queue(
+    mut commands: Commands,
+    mut game: GameParam,
+    mut craft_queue: ResMut<CraftQueue>,
+    mut inv: Query<&mut Inventory>,
+    mut chests: Query<(Entity, &GlobalTransform, &mut ChestContainer)>,
+    chest_option: Option<Res<ChestContainer>>,
+    recipes: Res<Recipes>,
+    proto: ProtoParam,
+    player_t: Query<&GlobalTransform, With<Player>>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+) {
+    let Some(job) = craft_queue.jobs.front_mut() else {
+        return;
+    };
+    if !job.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    job.timer.reset();
+    let job = job.clone();
+    let player_pos = player_t.single().translation();
+    let (ingredients, _, yield_count) = recipes.crafting_list.get(&job.obj).unwrap();
+
+    let origin = job.station_pos.unwrap_or(player_pos.truncate());
+    let nearby = get_nearby_chests(origin, chests.iter(), chest_option.as_deref());
+    let mut nearby_chests: Vec<Mut<ChestContainer>> = chests
+        .iter_mut()
+        .filter(|(e, _, _)| nearby.contains(e))
+        .map(|(_, _, chest)| chest)
+        .collect();
+    let mut inv = inv.single_mut();
+    let chest_items: Vec<&Container> = nearby_chests.iter().map(|c| &c.items).collect();
+    if get_max_crafts(ingredients, &inv.items, &chest_items) == 0 {
+        craft_queue.jobs.pop_front();
+        spawn_floating_text_with_shadow(
+            &mut commands,
+            &asset_server,
+            player_pos + Vec3::new(0., 10., 0.),
+            RED,
+            "Missing Ingredients".to_string(),
+        );
+        return;
+    }
+    remove_ingredients(ingredients, 1, &mut inv.items, &mut nearby_chests);
+
+    let result = create_new_random_item_stack_with_attributes(
+        &proto
+            .get_item_data(job.obj)
+            .unwrap()
+            .copy_with_count(*yield_count),
+        &proto,
+    );
+    let has_room = inv.items.get_first_empty_slot().is_some()
+        || inv
+            .items
+            .get_slot_for_item_in_container_with_space(&job.obj, None)
+            .is_some();
+    if has_room {
+        result.add_to_inventory(&mut inv.items, &mut game.inv_slot_query);
+    } else {
+        result.spawn_as_drop(&mut commands, &mut game, player_pos.truncate());
+    }
+
+    let front = craft_queue.jobs.front_mut().unwrap();
+    front.remaining -= 1;
+    if front.remaining == 0 {
+        craft_queue.jobs.pop_front();
     }
 }
 
@@ -158,134 +446,42 @@ pub fn get_crafting_inventory_item_stacks(
     list
 }
 
-pub fn handle_furnace_slot_update(
-    furnace_option: Option<ResMut<FurnaceContainer>>,
-    mut furnace_objects: Query<&mut FurnaceContainer>,
-    proto: ProtoParam,
-    time: Res<Time>,
-    recipes: Res<Recipes>,
-    mut inv_slots: Query<&mut InventorySlotState>,
-) {
-    let mut process_furnace = |furnace: &mut FurnaceContainer| {
-        let is_upgrade_furnace = furnace.items.items.len() == 2;
-        let mut needs_fuel = false;
-        if let Some(fuel_state) = furnace.state.as_mut() {
-            fuel_state.current_fuel_left.tick(time.delta());
-        } else {
-            needs_fuel = true;
-        }
-        if furnace.items.items[1].is_none() || (furnace.items.items[0].is_none() && needs_fuel) {
-            furnace.timer.reset();
-            return;
-        }
-        let ingredient = furnace.items.items[1].as_ref().unwrap();
-        let curr_result_obj = if is_upgrade_furnace {
-            None
-        } else {
-            furnace.items.items[2].clone()
-        };
-
-        let expected_result = if is_upgrade_furnace {
-            &WorldObject::None
-        } else {
-            recipes
-                .furnace_list
-                .get(&ingredient.item_stack.obj_type)
-                .expect("incorrect furnace recipe?")
-        };
-        if let Some(curr_result) = curr_result_obj {
-            if curr_result.item_stack.obj_type != expected_result.clone() {
-                // the ingredient in slot 1 does not match the current output in slot 2
-                furnace.timer.reset();
-                return;
-            }
-        }
-
-        if needs_fuel {
-            let fuel = furnace.items.items[0].as_mut().unwrap();
-            let mut fuel_state = FurnaceState::from_fuel(*fuel.get_obj());
-            fuel_state.current_fuel_left.tick(time.delta());
-            furnace.state = Some(fuel_state);
-            let updated_fuel = fuel.modify_count(-1);
-            furnace.items.items[0] = updated_fuel;
-        }
-
-        furnace.timer.tick(time.delta());
-
-        if furnace.timer.just_finished() {
-            if !is_upgrade_furnace {
-                let updated_result =
-                    if let Some(mut existing_result) = furnace.items.items[2].clone() {
-                        existing_result.modify_count(1).unwrap()
-                    } else {
-                        InventoryItemStack::new(
-                            proto.get_item_data(*expected_result).unwrap().clone(),
-                            0,
-                        )
-                    };
-                furnace.items.items[2] = Some(updated_result);
-                let updated_resource = furnace.items.items[1].as_mut().unwrap().modify_count(-1);
-                furnace.items.items[1] = updated_resource;
-            } else {
-                match furnace
-                    .state
-                    .as_ref()
-                    .expect("no furnace state")
-                    .current_fuel_type
-                {
-                    WorldObject::UpgradeTome => {
-                        let mut modifiers: Vec<(String, i32)> = vec![];
-                        let furnace_item = furnace.items.items[1].as_ref().unwrap();
-                        if let Some(eqp_type) = furnace_item.get_obj().get_equip_type(&proto) {
-                            if eqp_type.is_weapon() || eqp_type.is_tool() {
-                                modifiers.push(("attack".to_owned(), 1));
-                            } else if eqp_type.is_equipment() && !eqp_type.is_accessory() {
-                                modifiers.push(("health".to_owned(), 2));
-                                modifiers.push(("armor".to_owned(), 1));
-                            }
-                        }
-                        for (modifier, delta) in modifiers {
-                            furnace.items.items[1]
-                                .as_ref()
-                                .unwrap()
-                                .clone()
-                                .modify_attributes(
-                                    AttributeModifier { modifier, delta },
-                                    &mut furnace.items,
-                                );
-                            furnace.items.items[1]
-                                .as_ref()
-                                .unwrap()
-                                .clone()
-                                .modify_level(1, &mut furnace.items);
-                        }
-                    }
-                    WorldObject::OrbOfTransformation => {
-                        let old_item = furnace.items.items[1].as_ref().unwrap();
-                        furnace.items.items[1] = Some(InventoryItemStack::new(
-                            reroll_item_bonus_attributes(&old_item.item_stack, &proto),
-                            old_item.slot,
-                        ));
-                    }
-                    _ => {}
-                }
-                mark_slot_dirty(1, InventorySlotType::Furnace, &mut inv_slots);
-            }
-
-            if let Some(state) = furnace.state.as_ref() {
-                if state.current_fuel_left.finished() {
-                    furnace.state = None;
-                }
-            }
-            furnace.timer.reset();
-        }
-    };
+/// Sent to learn a recipe, ignored if it is already known
+pub struct UnlockRecipeEvent {
+    pub obj: WorldObject,
+}
 
-    if let Some(mut furnace) = furnace_option {
-        process_furnace(&mut furnace);
+impl CraftingTracker {
+    pub fn is_recipe_known(&self, obj: &WorldObject) -> bool {
+        self.discovered_recipes.contains(obj)
     }
-    for mut furnace in furnace_objects.iter_mut() {
-        process_furnace(&mut furnace);
+    /// Blueprint recipes the player has not learned yet
+    pub fn get_locked_blueprint_recipes(&self, recipes: &Recipes) -> Vec<WorldObject> {
+        recipes
+            .unlocks
+            .iter()
+            .filter(|(result, unlock)| {
+                **unlock == RecipeUnlock::Blueprint && !self.is_recipe_known(result)
+            })
+            .map(|(result, _)| *result)
+            .collect()
+    }
+}
+
+fn update_inventory_crafting_items(
+    inv: &mut Inventory,
+    craft_tracker: &CraftingTracker,
+    recipes: &Recipes,
+    proto: &ProtoParam,
+) {
+    if let Some(inv_recipes) = craft_tracker
+        .crafting_type_map
+        .get(&CraftingContainerType::Inventory)
+    {
+        inv.crafting_items = Container {
+            items: get_crafting_inventory_item_stacks(inv_recipes.clone(), recipes, proto),
+            ..default()
+        };
     }
 }
 
@@ -294,9 +490,7 @@ pub fn handle_inv_changed_update_crafting_tracker(
     mut craft_tracker: ResMut<CraftingTracker>,
     recipes: Res<Recipes>,
     proto: ProtoParam,
-    player_t: Query<&GlobalTransform, With<Player>>,
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    mut unlock_events: EventWriter<UnlockRecipeEvent>,
 ) {
     if inv.get_single().is_err() {
         return;
@@ -311,38 +505,76 @@ pub fn handle_inv_changed_update_crafting_tracker(
             }
 
             for (result, recipe) in recipes.crafting_list.iter() {
-                if craft_tracker.discovered_recipes.contains(&result) {
+                if craft_tracker.is_recipe_known(result) {
                     continue;
                 }
-                for ingredient in recipe.0.iter() {
-                    if ingredient.item == new_obj {
-                        craft_tracker.discovered_recipes.push(result.clone());
-                        craft_tracker
-                            .crafting_type_map
-                            .entry(recipe.1.clone())
-                            .or_insert(vec![])
-                            .push(result.clone());
-                        spawn_floating_text_with_shadow(
-                            &mut commands,
-                            &asset_server,
-                            player_t.single().translation() + Vec3::new(0., 10., 0.),
-                            YELLOW,
-                            "New Recipe!".to_string(),
-                        );
-                        continue;
-                    }
+                let unlocked = match recipes.unlocks.get(result) {
+                    Some(RecipeUnlock::KeyIngredient(key)) => *key == new_obj,
+                    Some(_) => false,
+                    None => recipe.0.iter().any(|ingredient| ingredient.item == new_obj),
+                };
+                if unlocked {
+                    unlock_events.send(UnlockRecipeEvent { obj: *result });
                 }
             }
             craft_tracker.discovered_objects.push(new_obj);
         }
     }
-    if let Some(inv_recipes) = craft_tracker
-        .crafting_type_map
-        .get(&CraftingContainerType::Inventory)
-    {
-        inv.crafting_items = Container {
-            items: get_crafting_inventory_item_stacks(inv_recipes.clone(), &recipes, &proto),
-            ..default()
+    update_inventory_crafting_items(&mut inv, &craft_tracker, &recipes, &proto);
+}
+
+pub fn handle_level_up_unlock_recipes(
+    player_level: Query<&PlayerLevel, Changed<PlayerLevel>>,
+    craft_tracker: Res<CraftingTracker>,
+    recipes: Res<Recipes>,
+    mut unlock_events: EventWriter<UnlockRecipeEvent>,
+) {
+    let Ok(player_level) = player_level.get_single() else {
+        return;
+    };
+    for (result, unlock) in recipes.unlocks.iter() {
+        if let RecipeUnlock::Level(level) = unlock {
+            if player_level.level >= *level && !craft_tracker.is_recipe_known(result) {
+                unlock_events.send(UnlockRecipeEvent { obj: *result });
+            }
+        }
+    }
+}
+
+pub fn handle_unlock_recipe_events(
+    mut commands: Commands,
+    mut events: EventReader<UnlockRecipeEvent>,
+    mut craft_tracker: ResMut<CraftingTracker>,
+    mut inv: Query<&mut Inventory>,
+    recipes: Res<Recipes>,
+    proto: ProtoParam,
+    player_t: Query<&GlobalTransform, With<Player>>,
+    asset_server: Res<AssetServer>,
+) {
+    let mut num_unlocked = 0;
+    for event in events.iter() {
+        let Some(recipe) = recipes.crafting_list.get(&event.obj) else {
+            continue;
         };
+        if craft_tracker.is_recipe_known(&event.obj) {
+            continue;
+        }
+        craft_tracker.discovered_recipes.push(event.obj);
+        craft_tracker
+            .crafting_type_map
+            .entry(recipe.1.clone())
+            .or_insert(vec![])
+            .push(event.obj);
+        spawn_floating_text_with_shadow(
+            &mut commands,
+            &asset_server,
+            player_t.single().translation() + Vec3::new(0., 10. + 10. * num_unlocked as f32, 0.),
+            YELLOW,
+            format!("New Recipe: {}", event.obj),
+        );
+        num_unlocked += 1;
+    }
+    if num_unlocked > 0 {
+        update_inventory_crafting_items(&mut inv.single_mut(), &craft_tracker, &recipes, &proto);
     }
 }
@@ -0,0 +1,301 @@
+use bevy::prelude::*;
+
+use crate::{
+    attributes::{AttributeChangeEvent, MaxHealth},
+    colors::{LIGHT_GREEN, RED},
+    combat::{handle_hits, HitEvent, InvincibilityTimer},
+    container::Container,
+    enemy::Mob,
+    inventory::Inventory,
+    player::Player,
+    proto::proto_param::ProtoParam,
+    ui::{
+        crafting_ui::{CraftingContainer, CraftingContainerType},
+        damage_numbers::spawn_floating_text_with_shadow,
+        mark_slot_dirty, ChestContainer, InventorySlotState, InventorySlotType, InventoryState,
+    },
+    GameState,
+};
+
+use super::{
+    get_ingredient_count, get_nearby_chests,
+    projectile::{RangedAttack, RangedAttackEvent},
+    remove_ingredients, EquipmentType, MainHand, RecipeItem, Recipes, RequiredEquipmentType,
+};
+
+pub struct DurabilityPlugin;
+impl Plugin for DurabilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RepairItemEvent>().add_systems(
+            (
+                handle_main_hand_wear_on_hit.before(handle_hits),
+                handle_armor_wear_on_hit.before(handle_hits),
+                handle_ranged_weapon_wear,
+                handle_repair_item_events,
+            )
+                .in_set(OnUpdate(GameState::Main)),
+        );
+    }
+}
+
+/// Sent when the player right clicks a worn item while an Anvil is open
+pub struct RepairItemEvent {
+    pub slot: usize,
+    pub slot_type: InventorySlotType,
+}
+
+/// Durability a hit costs the held item. Hard objects that need a pickaxe wear tools
+/// down faster, while plants and other objects that break in one hit cost nothing.
+fn get_hit_durability_loss(
+    max_health: Option<&MaxHealth>,
+    required_tool: Option<&RequiredEquipmentType>,
+) -> i32 {
+    match (required_tool, max_health) {
+        (Some(RequiredEquipmentType(EquipmentType::Pickaxe)), _) => 2,
+        (_, Some(max_health)) if max_health.0 <= 1 => 0,
+        _ => 1,
+    }
+}
+
+/// How many repair materials it takes to fully repair an item, based on how worn it is
+pub fn get_repair_cost(material: &RecipeItem, durability: i32, max_durability: i32) -> usize {
+    let missing = (max_durability - durability.max(0)).max(0) as f32;
+    ((material.count as f32 * missing / max_durability.max(1) as f32).ceil() as usize).max(1)
+}
+
+/// Wears down the item in a slot. Once it runs out of durability, items that can be
+/// repaired stay in the slot as broken, and everything else is destroyed.
+/// Returns the floating text to show if the item broke.
+fn wear_item(items: &mut Container, slot: usize, amount: i32, recipes: &Recipes) -> Option<String> {
+    let stack = items.items.get_mut(slot)?.as_mut()?;
+    let attributes = &mut stack.item_stack.attributes;
+    if amount <= 0 || !attributes.has_durability() || attributes.is_broken() {
+        return None;
+    }
+    attributes.durability = (attributes.durability - amount).max(0);
+    if attributes.durability > 0 {
+        return None;
+    }
+    let name = stack.item_stack.metadata.name.clone();
+    if recipes
+        .repair_materials
+        .contains_key(&stack.item_stack.obj_type)
+    {
+        Some(format!("{name} is broken"))
+    } else {
+        items.items[slot] = None;
+        Some(format!("{name} broke!"))
+    }
+}
+
+/// Tools wear down when they damage objects, and weapons when they hit mobs
+pub fn handle_main_hand_wear_on_hit(
+    mut commands: Commands,
+    mut hits: EventReader<HitEvent>,
+    mut inv: Query<&mut Inventory>,
+    mut inv_slots: Query<&mut InventorySlotState>,
+    inv_state: Res<InventoryState>,
+    targets: Query<(
+        Option<&MaxHealth>,
+        Option<&RequiredEquipmentType>,
+        Option<&Mob>,
+    )>,
+    player: Query<(Entity, &GlobalTransform), With<Player>>,
+    recipes: Res<Recipes>,
+    proto: ProtoParam,
+    asset_server: Res<AssetServer>,
+) {
+    let (player_e, player_t) = player.single();
+    let slot = inv_state.active_hotbar_slot;
+    for hit in hits.iter() {
+        let Some(weapon) = hit.hit_with_melee else {
+            continue;
+        };
+        if hit.hit_entity == player_e {
+            continue;
+        }
+        let Ok((max_health, required_tool, mob_option)) = targets.get(hit.hit_entity) else {
+            continue;
+        };
+        let mut inv = inv.single_mut();
+        if inv.items.items[slot]
+            .as_ref()
+            .map(|stack| stack.item_stack.obj_type)
+            != Some(weapon)
+        {
+            continue;
+        }
+        let loss = if mob_option.is_some() {
+            1
+        } else {
+            // hitting with the wrong tool does no damage, so it costs no durability either
+            if let Some(required_tool) = required_tool {
+                if proto.get_component::<EquipmentType, _>(weapon) != Some(&required_tool.0) {
+                    continue;
+                }
+            }
+            get_hit_durability_loss(max_health, required_tool)
+        };
+        if let Some(text) = wear_item(&mut inv.items, slot, loss, &recipes) {
+            spawn_floating_text_with_shadow(
+                &mut commands,
+                &asset_server,
+                player_t.translation() + Vec3::new(0., 10., 0.),
+                RED,
+                text,
+            );
+        }
+        mark_slot_dirty(slot, InventorySlotType::Hotbar, &mut inv_slots);
+    }
+}
+
+/// Ranged weapons wear down once per shot, follow up projectiles from upgrades are free
+pub fn handle_ranged_weapon_wear(
+    mut commands: Commands,
+    mut ranged_attacks: EventReader<RangedAttackEvent>,
+    mut inv: Query<&mut Inventory>,
+    mut inv_slots: Query<&mut InventorySlotState>,
+    inv_state: Res<InventoryState>,
+    ranged_weapon: Query<&RangedAttack, With<MainHand>>,
+    player_t: Query<&GlobalTransform, With<Player>>,
+    recipes: Res<Recipes>,
+    asset_server: Res<AssetServer>,
+) {
+    for attack in ranged_attacks.iter() {
+        if attack.from_enemy.is_some() || attack.is_followup_proj || ranged_weapon.is_empty() {
+            continue;
+        }
+        let slot = inv_state.active_hotbar_slot;
+        if let Some(text) = wear_item(&mut inv.single_mut().items, slot, 1, &recipes) {
+            spawn_floating_text_with_shadow(
+                &mut commands,
+                &asset_server,
+                player_t.single().translation() + Vec3::new(0., 10., 0.),
+                RED,
+                text,
+            );
+        }
+        mark_slot_dirty(slot, InventorySlotType::Hotbar, &mut inv_slots);
+    }
+}
+
+/// Every piece of armor the player wears loses durability when they take a hit
+pub fn handle_armor_wear_on_hit(
+    mut commands: Commands,
+    mut hits: EventReader<HitEvent>,
+    mut inv: Query<&mut Inventory>,
+    mut inv_slots: Query<&mut InventorySlotState>,
+    player: Query<(Entity, &GlobalTransform, Option<&InvincibilityTimer>), With<Player>>,
+    mut att_event: EventWriter<AttributeChangeEvent>,
+    recipes: Res<Recipes>,
+    asset_server: Res<AssetServer>,
+) {
+    let (player_e, player_t, i_frame_option) = player.single();
+    for hit in hits.iter() {
+        if hit.hit_entity != player_e || hit.damage <= 0 || i_frame_option.is_some() {
+            continue;
+        }
+        let mut inv = inv.single_mut();
+        for slot in 0..inv.equipment_items.items.len() {
+            if inv.equipment_items.items[slot].is_none() {
+                continue;
+            }
+            if let Some(text) = wear_item(&mut inv.equipment_items, slot, 1, &recipes) {
+                spawn_floating_text_with_shadow(
+                    &mut commands,
+                    &asset_server,
+                    player_t.translation() + Vec3::new(0., 10., 0.),
+                    RED,
+                    text,
+                );
+                // broken armor stops giving stats
+                att_event.send(AttributeChangeEvent);
+            }
+            mark_slot_dirty(slot, InventorySlotType::Equipment, &mut inv_slots);
+        }
+    }
+}
+
+/// Repairs an item at the Anvil, taking its repair material from the inventory
+/// and any chests near the Anvil
+pub fn handle_repair_item_events(
+    mut commands: Commands,
+    mut events: EventReader<RepairItemEvent>,
+    mut inv: Query<&mut Inventory>,
+    mut inv_slots: Query<&mut InventorySlotState>,
+    mut chests: Query<(Entity, &GlobalTransform, &mut ChestContainer)>,
+    chest_option: Option<Res<ChestContainer>>,
+    crafting_option: Option<Res<CraftingContainer>>,
+    player_t: Query<&GlobalTransform, With<Player>>,
+    mut att_event: EventWriter<AttributeChangeEvent>,
+    recipes: Res<Recipes>,
+    asset_server: Res<AssetServer>,
+) {
+    for repair in events.iter() {
+        let Some(anvil) = crafting_option
+            .as_ref()
+            .filter(|c| c.station == CraftingContainerType::Anvil)
+        else {
+            continue;
+        };
+        let player_pos = player_t.single().translation();
+        let mut inv = inv.single_mut();
+        let Some(stack) = inv.get_items_from_slot_type(repair.slot_type).items[repair.slot]
+            .as_ref()
+            .map(|stack| stack.item_stack.clone())
+        else {
+            continue;
+        };
+        let Some(material) = recipes.repair_materials.get(&stack.obj_type) else {
+            continue;
+        };
+        if !stack.attributes.is_damaged() {
+            continue;
+        }
+        let cost = RecipeItem {
+            item: material.item,
+            count: get_repair_cost(
+                material,
+                stack.attributes.durability,
+                stack.attributes.max_durability,
+            ),
+        };
+
+        let nearby = get_nearby_chests(
+            anvil.station_pos.unwrap_or(player_pos.truncate()),
+            chests.iter(),
+            chest_option.as_deref(),
+        );
+        let mut nearby_chests: Vec<Mut<ChestContainer>> = chests
+            .iter_mut()
+            .filter(|(e, _, _)| nearby.contains(e))
+            .map(|(_, _, chest)| chest)
+            .collect();
+        let chest_items: Vec<&Container> = nearby_chests.iter().map(|c| &c.items).collect();
+        if get_ingredient_count(cost.item, &inv.items, &chest_items) < cost.count {
+            spawn_floating_text_with_shadow(
+                &mut commands,
+                &asset_server,
+                player_pos + Vec3::new(0., 10., 0.),
+                RED,
+                format!("Needs {} {}", cost.count, cost.item),
+            );
+            continue;
+        }
+        remove_ingredients(&[cost], 1, &mut inv.items, &mut nearby_chests);
+
+        let repaired = inv.get_mut_items_from_slot_type(repair.slot_type).items[repair.slot]
+            .as_mut()
+            .expect("repair materials are never the repaired item");
+        repaired.item_stack.attributes.durability = repaired.item_stack.attributes.max_durability;
+        mark_slot_dirty(repair.slot, repair.slot_type, &mut inv_slots);
+        att_event.send(AttributeChangeEvent);
+        spawn_floating_text_with_shadow(
+            &mut commands,
+            &asset_server,
+            player_pos + Vec3::new(0., 10., 0.),
+            LIGHT_GREEN,
+            format!("Repaired {}", stack.metadata.name),
+        );
+    }
+}
@@ -5,6 +5,12 @@ use crate::{
         hunger::Hunger,
         modifiers::{ModifyHealthEvent, ModifyManaEvent},
     },
+    enemy::{
+        companion::{Companion, SummonCompanionEvent, TemporaryAlly},
+        passive::FeedMobEvent,
+        spawn_helpers::can_spawn_mob_here,
+        Mob,
+    },
     inputs::CursorPos,
     inventory::Inventory,
     juice::UseItemEvent,
@@ -20,8 +26,9 @@ use crate::{
 };
 use bevy::{ecs::system::SystemParam, prelude::*};
 use bevy_proto::prelude::{ReflectSchematic, Schematic};
+use rand::seq::SliceRandom;
 
-use super::{CraftingTracker, PlaceItemEvent, Recipes, WorldObject};
+use super::{CraftingTracker, PlaceItemEvent, Recipes, UnlockRecipeEvent, WorldObject};
 
 #[derive(Component, Reflect, FromReflect, Clone, Schematic, Default, PartialEq)]
 #[reflect(Component, Schematic)]
@@ -36,6 +43,8 @@ pub enum ItemAction {
     Essence,
     DungeonKey,
     GrantSkillPoint(u8),
+    LearnRecipe,
+    SummonCompanion(Mob),
 }
 
 #[derive(Component, Reflect, FromReflect, Schematic, Default)]
@@ -63,6 +72,7 @@ pub struct ItemActionParam<'w, 's> {
     pub modify_mana_event: EventWriter<'w, ModifyManaEvent>,
     pub place_item_event: EventWriter<'w, PlaceItemEvent>,
     pub action_success_event: EventWriter<'w, ActionSuccessEvent>,
+    pub feed_mob_event: EventWriter<'w, FeedMobEvent>,
     pub cursor_pos: Res<'w, CursorPos>,
     pub hunger_query: Query<'w, 's, &'static mut Hunger>,
     pub chest_query: Query<'w, 's, &'static ChestContainer>,
@@ -71,6 +81,9 @@ pub struct ItemActionParam<'w, 's> {
     pub recipes: Res<'w, Recipes>,
     pub night_tracker: Res<'w, NightTracker>,
     pub skill_points: Query<'w, 's, &'static mut SkillPoints>,
+    pub unlock_recipe_event: EventWriter<'w, UnlockRecipeEvent>,
+    pub summon_companion_event: EventWriter<'w, SummonCompanionEvent>,
+    pub companions: Query<'w, 's, (), (With<Companion>, Without<TemporaryAlly>)>,
 
     #[system_param(ignore)]
     marker: PhantomData<&'s ()>,
@@ -151,6 +164,38 @@ impl ItemActions {
 
                     item_action_param.use_item_event.send(UseItemEvent(obj));
                 }
+                ItemAction::LearnRecipe => {
+                    let locked = item_action_param
+                        .crafting_tracker
+                        .get_locked_blueprint_recipes(&item_action_param.recipes);
+                    // keep the blueprint if there is nothing left to learn
+                    let Some(recipe) = locked.choose(&mut rand::thread_rng()) else {
+                        return;
+                    };
+                    item_action_param
+                        .unlock_recipe_event
+                        .send(UnlockRecipeEvent { obj: *recipe });
+                    item_action_param.use_item_event.send(UseItemEvent(obj));
+                }
+                ItemAction::SummonCompanion(mob) => {
+                    // only one companion at a time, so keep the item until it's free
+                    if !item_action_param.companions.is_empty() {
+                        return;
+                    }
+                    let pos = item_action_param.cursor_pos.world_coords.truncate();
+                    if game.player().position.truncate().distance(pos)
+                        > game.player().reach_distance * 32.
+                        || !can_spawn_mob_here(pos, game, proto_param)
+                    {
+                        return;
+                    }
+                    item_action_param
+                        .summon_companion_event
+                        .send(SummonCompanionEvent {
+                            mob: mob.clone(),
+                            pos,
+                        });
+                }
                 _ => {}
             }
         }
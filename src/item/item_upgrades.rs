@@ -1,12 +1,12 @@
 use std::time::Duration;
 
-use crate::attributes::{CurrentHealth, MaxHealth};
+use crate::attributes::{CurrentHealth, ItemAttributes, MaxHealth};
 use crate::combat::{EnemyDeathEvent, MarkedForDeath};
 use crate::custom_commands::CommandsExt;
 use crate::enemy::Mob;
 use crate::{
+    action_map::{ActionInput, InputAction},
     combat::{AttackTimer, HitEvent},
-    inputs::CursorPos,
     player::Player,
     proto::proto_param::ProtoParam,
     GameParam,
@@ -66,18 +66,20 @@ pub struct Poisoned {
 }
 
 pub fn handle_delayed_ranged_attack(
-    wep_query: Query<&RangedAttack, With<MainHand>>,
+    wep_query: Query<(&RangedAttack, &ItemAttributes), With<MainHand>>,
     mut ranged_attack_event: EventWriter<RangedAttackEvent>,
     game: GameParam,
-    mouse_button_input: Res<Input<MouseButton>>,
-    cursor_pos: Res<CursorPos>,
+    actions: ActionInput,
     time: Res<Time>,
     mut att_cooldown_query: Query<(&mut ClawUpgradeMultiThrow, Option<&AttackTimer>), With<Player>>,
     mut count: Local<u8>,
 ) {
-    let Ok(ranged_attack) = wep_query.get_single() else {
+    let Ok((ranged_attack, wep_attributes)) = wep_query.get_single() else {
         return;
     };
+    if wep_attributes.is_broken() {
+        return;
+    }
     if ranged_attack.0 != Projectile::ThrowingStar {
         return;
     }
@@ -89,14 +91,13 @@ pub fn handle_delayed_ranged_attack(
         *count = 0;
         return;
     }
-    if mouse_button_input.pressed(MouseButton::Left) || delayed_ranged_attack.0.percent() != 0. {
+    if actions.pressed(InputAction::Attack) || delayed_ranged_attack.0.percent() != 0. {
         delayed_ranged_attack.0.tick(time.delta());
         if delayed_ranged_attack.0.just_finished() {
             *count += 1;
             ranged_attack_event.send(RangedAttackEvent {
                 projectile: ranged_attack.0.clone(),
-                direction: (cursor_pos.world_coords.truncate() - game.player().position.truncate())
-                    .normalize_or_zero(),
+                direction: game.player().aim,
                 from_enemy: None,
                 is_followup_proj: true,
                 mana_cost: None,
@@ -111,17 +112,19 @@ pub fn handle_delayed_ranged_attack(
     }
 }
 pub fn handle_spread_arrows_attack(
-    wep_query: Query<&RangedAttack, With<MainHand>>,
+    wep_query: Query<(&RangedAttack, &ItemAttributes), With<MainHand>>,
     mut ranged_attack_event: EventWriter<RangedAttackEvent>,
     game: GameParam,
-    mouse_button_input: Res<Input<MouseButton>>,
-    cursor_pos: Res<CursorPos>,
+    actions: ActionInput,
     att_cooldown_query: Query<(&BowUpgradeSpread, Option<&AttackTimer>), With<Player>>,
     mut count: Local<u8>,
 ) {
-    let Ok(ranged_attack) = wep_query.get_single() else {
+    let Ok((ranged_attack, wep_attributes)) = wep_query.get_single() else {
         return;
     };
+    if wep_attributes.is_broken() {
+        return;
+    }
     if ranged_attack.0 != Projectile::Arrow {
         return;
     }
@@ -131,10 +134,9 @@ pub fn handle_spread_arrows_attack(
     if cooldown_option.is_none() {
         *count = 0;
     }
-    if mouse_button_input.pressed(MouseButton::Left) && *count < spread_attack.0 {
+    if actions.pressed(InputAction::Attack) && *count < spread_attack.0 {
         *count += 1;
-        let raw_dir = (cursor_pos.world_coords.truncate() - game.player().position.truncate())
-            .normalize_or_zero();
+        let raw_dir = game.player().aim;
         ranged_attack_event.send(RangedAttackEvent {
             projectile: ranged_attack.0.clone(),
             direction: (raw_dir
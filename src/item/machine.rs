@@ -0,0 +1,378 @@
+use std::{ops::Range, time::Duration};
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_proto::prelude::{ReflectSchematic, Schematic};
+use serde::Deserialize;
+
+use crate::{
+    attributes::{attribute_helpers::reroll_item_bonus_attributes, gems::Gems, AttributeModifier},
+    container::Container,
+    inventory::{InventoryItemStack, ItemStack, MAX_STACK_SIZE},
+    item::WorldObject,
+    proto::proto_param::ProtoParam,
+    ui::{
+        handle_hovering, mark_slot_dirty, FurnaceContainer, FurnaceState, InventorySlotState,
+        InventorySlotType,
+    },
+    GameState,
+};
+
+use super::{Loot, LootTable, LootTablePlugin, RecipeItem, Recipes};
+
+pub struct MachinePlugin;
+impl Plugin for MachinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            handle_machine_processing
+                .after(handle_hovering)
+                .in_set(OnUpdate(GameState::Main)),
+        );
+    }
+}
+
+/// Slot layout of a processing station like the Furnace, declared on its prototype.
+/// Slots are laid out as the fuel slot (if the machine has any fuels), then inputs, then outputs.
+#[derive(Component, Schematic, Reflect, FromReflect, Default, Clone, Debug)]
+#[reflect(Component, Schematic)]
+pub struct Machine {
+    pub input_slots: usize,
+    pub output_slots: usize,
+    /// Seconds of processing each fuel item burns for
+    pub fuels: HashMap<WorldObject, f32>,
+    /// If set, the fuel is applied to the item in the input slot
+    /// instead of running recipes, taking this many seconds
+    pub upgrade_time: Option<f32>,
+}
+
+impl Machine {
+    pub fn fuel_slot(&self) -> Option<usize> {
+        if self.fuels.is_empty() {
+            None
+        } else {
+            Some(0)
+        }
+    }
+    pub fn input_range(&self) -> Range<usize> {
+        let start = if self.fuel_slot().is_some() { 1 } else { 0 };
+        start..start + self.input_slots
+    }
+    pub fn output_range(&self) -> Range<usize> {
+        let start = self.input_range().end;
+        start..start + self.output_slots
+    }
+    pub fn num_slots(&self) -> usize {
+        self.output_range().end
+    }
+    /// Which items each slot accepts when dropped in by the player
+    pub fn get_slot_map(&self, station: WorldObject, recipes: &Recipes) -> Vec<Vec<WorldObject>> {
+        let station_recipes = recipes
+            .machine_list
+            .iter()
+            .filter(|recipe| recipe.station == station);
+        let inputs: Vec<WorldObject> = if self.upgrade_time.is_some() {
+            recipes.upgradeable_items.clone()
+        } else {
+            station_recipes
+                .clone()
+                .flat_map(|recipe| recipe.inputs.iter().map(|input| input.item))
+                .collect()
+        };
+        let outputs: Vec<WorldObject> = station_recipes
+            .flat_map(|recipe| {
+                std::iter::once(recipe.output.item)
+                    .chain(recipe.byproducts.iter().map(|byproduct| byproduct.item))
+            })
+            .collect();
+
+        let mut slot_map = vec![];
+        if self.fuel_slot().is_some() {
+            slot_map.push(self.fuels.keys().copied().collect());
+        }
+        slot_map.extend(vec![inputs; self.input_slots]);
+        slot_map.extend(vec![outputs; self.output_slots]);
+        slot_map
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct MachineRecipe {
+    pub station: WorldObject,
+    pub inputs: Vec<RecipeItem>,
+    pub output: RecipeItem,
+    /// Extra items rolled each time the recipe finishes
+    #[serde(default)]
+    pub byproducts: Vec<Loot>,
+    pub time: f32,
+}
+
+impl MachineRecipe {
+    pub fn has_inputs(&self, inputs: &[Option<InventoryItemStack>]) -> bool {
+        self.inputs.iter().all(|ingredient| {
+            inputs
+                .iter()
+                .flatten()
+                .filter(|stack| stack.item_stack.obj_type == ingredient.item)
+                .map(|stack| stack.item_stack.count)
+                .sum::<usize>()
+                >= ingredient.count
+        })
+    }
+}
+
+pub type MachineRecipeList = Vec<MachineRecipe>;
+
+fn has_room_for_output(items: &Container, outputs: Range<usize>, output: &RecipeItem) -> bool {
+    outputs.into_iter().any(|slot| match &items.items[slot] {
+        Some(stack) => {
+            stack.item_stack.obj_type == output.item
+                && stack.item_stack.count + output.count <= MAX_STACK_SIZE
+        }
+        None => true,
+    })
+}
+
+/// Stacks onto a matching output slot first, otherwise the first empty one.
+/// Returns false if there was no room.
+fn add_to_outputs(items: &mut Container, outputs: Range<usize>, item: ItemStack) -> bool {
+    let existing_slot = outputs.clone().find(|slot| {
+        items.items[*slot].as_ref().map_or(false, |stack| {
+            stack.item_stack.obj_type == item.obj_type
+                && stack.item_stack.count + item.count <= MAX_STACK_SIZE
+        })
+    });
+    if let Some(slot) = existing_slot {
+        let stack = items.items[slot].as_mut().unwrap();
+        stack.item_stack.count += item.count;
+        return true;
+    }
+    if let Some(slot) = outputs
+        .into_iter()
+        .find(|slot| items.items[*slot].is_none())
+    {
+        items.items[slot] = Some(InventoryItemStack::new(item, slot));
+        return true;
+    }
+    false
+}
+
+fn consume_inputs(items: &mut Container, inputs: Range<usize>, ingredients: &[RecipeItem]) {
+    for ingredient in ingredients.iter() {
+        let mut remaining = ingredient.count;
+        for slot in inputs.clone() {
+            if remaining == 0 {
+                break;
+            }
+            let Some(stack) = items.items[slot].as_mut() else {
+                continue;
+            };
+            if stack.item_stack.obj_type != ingredient.item {
+                continue;
+            }
+            let taken = remaining.min(stack.item_stack.count);
+            remaining -= taken;
+            items.items[slot] = stack.modify_count(-(taken as i8));
+        }
+    }
+}
+
+/// Gems need a free socket, and the Chisel needs a gem to take out and room to put it,
+/// so neither is burned for nothing
+fn can_apply_upgrade(
+    furnace: &FurnaceContainer,
+    slot: usize,
+    fuel: WorldObject,
+    gems: &Gems,
+) -> bool {
+    let Some(item) = furnace.items.items[slot].as_ref() else {
+        return false;
+    };
+    match fuel {
+        WorldObject::Chisel => item.item_stack.metadata.gems.last().map_or(false, |gem| {
+            has_room_for_output(
+                &furnace.items,
+                furnace.machine.output_range(),
+                &RecipeItem {
+                    item: *gem,
+                    count: 1,
+                },
+            )
+        }),
+        gem if gems.is_gem(gem) => gems.can_socket(&item.item_stack, gem),
+        _ => true,
+    }
+}
+
+fn apply_upgrade(furnace: &mut FurnaceContainer, slot: usize, proto: &ProtoParam, gems: &Gems) {
+    match furnace
+        .state
+        .as_ref()
+        .expect("no furnace state")
+        .current_fuel_type
+    {
+        WorldObject::UpgradeTome => {
+            let mut modifiers: Vec<(String, i32)> = vec![];
+            let furnace_item = furnace.items.items[slot].as_ref().unwrap();
+            if let Some(eqp_type) = furnace_item.get_obj().get_equip_type(proto) {
+                if eqp_type.is_weapon() || eqp_type.is_tool() {
+                    modifiers.push(("attack".to_owned(), 1));
+                } else if eqp_type.is_equipment() && !eqp_type.is_accessory() {
+                    modifiers.push(("health".to_owned(), 2));
+                    modifiers.push(("armor".to_owned(), 1));
+                }
+            }
+            for (modifier, delta) in modifiers {
+                furnace.items.items[slot]
+                    .as_ref()
+                    .unwrap()
+                    .clone()
+                    .modify_attributes(AttributeModifier { modifier, delta }, &mut furnace.items);
+                furnace.items.items[slot]
+                    .as_ref()
+                    .unwrap()
+                    .clone()
+                    .modify_level(1, &mut furnace.items);
+            }
+        }
+        WorldObject::OrbOfTransformation => {
+            let old_item = furnace.items.items[slot].as_ref().unwrap();
+            furnace.items.items[slot] = Some(InventoryItemStack::new(
+                reroll_item_bonus_attributes(&old_item.item_stack, proto, gems),
+                old_item.slot,
+            ));
+        }
+        WorldObject::Chisel => {
+            let old_item = furnace.items.items[slot].as_ref().unwrap();
+            if let Some((new_stack, gem)) = gems.unsocket_gem(&old_item.item_stack) {
+                let gem_stack = proto.get_item_data(gem).unwrap().copy_with_count(1);
+                let outputs = furnace.machine.output_range();
+                if add_to_outputs(&mut furnace.items, outputs, gem_stack) {
+                    furnace.items.items[slot] = Some(InventoryItemStack::new(new_stack, slot));
+                }
+            }
+        }
+        gem if gems.is_gem(gem) => {
+            let old_item = furnace.items.items[slot].as_ref().unwrap();
+            if let Some(new_stack) = gems.socket_gem(&old_item.item_stack, gem) {
+                furnace.items.items[slot] = Some(InventoryItemStack::new(new_stack, slot));
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn handle_machine_processing(
+    furnace_option: Option<ResMut<FurnaceContainer>>,
+    mut furnace_objects: Query<&mut FurnaceContainer>,
+    proto: ProtoParam,
+    time: Res<Time>,
+    recipes: Res<Recipes>,
+    gems: Res<Gems>,
+    mut inv_slots: Query<&mut InventorySlotState>,
+) {
+    let mut process_machine = |furnace: &mut FurnaceContainer| {
+        let machine = furnace.machine.clone();
+        let inputs = machine.input_range();
+        let outputs = machine.output_range();
+
+        let mut needs_fuel = false;
+        if let Some(fuel_state) = furnace.state.as_mut() {
+            fuel_state.current_fuel_left.tick(time.delta());
+        } else {
+            needs_fuel = machine.fuel_slot().is_some();
+        }
+        let out_of_fuel = needs_fuel
+            && machine
+                .fuel_slot()
+                .map_or(false, |slot| furnace.items.items[slot].is_none());
+        if furnace.items.items[inputs.clone()]
+            .iter()
+            .all(|i| i.is_none())
+            || out_of_fuel
+        {
+            furnace.timer.reset();
+            return;
+        }
+
+        let recipe = if machine.upgrade_time.is_some() {
+            None
+        } else {
+            let Some(recipe) = recipes.machine_list.iter().find(|recipe| {
+                recipe.station == furnace.station
+                    && recipe.has_inputs(&furnace.items.items[inputs.clone()])
+                    && has_room_for_output(&furnace.items, outputs.clone(), &recipe.output)
+            }) else {
+                // the inputs do not make anything, or the outputs are full
+                furnace.timer.reset();
+                return;
+            };
+            Some(recipe)
+        };
+        let process_time = recipe.map_or(machine.upgrade_time.unwrap_or(0.), |r| r.time);
+        if furnace.timer.duration() != Duration::from_secs_f32(process_time) {
+            furnace
+                .timer
+                .set_duration(Duration::from_secs_f32(process_time));
+            furnace.timer.reset();
+        }
+
+        if needs_fuel {
+            let fuel_slot = machine.fuel_slot().unwrap();
+            let fuel_obj = *furnace.items.items[fuel_slot].as_ref().unwrap().get_obj();
+            if machine.upgrade_time.is_some()
+                && !can_apply_upgrade(furnace, inputs.start, fuel_obj, &gems)
+            {
+                furnace.timer.reset();
+                return;
+            }
+            let fuel = furnace.items.items[fuel_slot].as_mut().unwrap();
+            let burn_time = *machine.fuels.get(fuel.get_obj()).unwrap_or(&0.);
+            let mut fuel_state = FurnaceState::from_fuel(*fuel.get_obj(), burn_time);
+            fuel_state.current_fuel_left.tick(time.delta());
+            furnace.state = Some(fuel_state);
+            let updated_fuel = fuel.modify_count(-1);
+            furnace.items.items[fuel_slot] = updated_fuel;
+        }
+
+        furnace.timer.tick(time.delta());
+
+        if furnace.timer.just_finished() {
+            if let Some(recipe) = recipe {
+                consume_inputs(&mut furnace.items, inputs.clone(), &recipe.inputs);
+                let result = proto
+                    .get_item_data(recipe.output.item)
+                    .unwrap()
+                    .copy_with_count(recipe.output.count);
+                add_to_outputs(&mut furnace.items, outputs.clone(), result);
+                let byproducts = LootTable {
+                    drops: recipe.byproducts.clone(),
+                };
+                for byproduct in LootTablePlugin::get_drops(&byproducts, &proto, 0, None) {
+                    // byproducts are lost if there is no room left for them
+                    add_to_outputs(&mut furnace.items, outputs.clone(), byproduct);
+                }
+                for slot in outputs.clone() {
+                    mark_slot_dirty(slot, InventorySlotType::Furnace, &mut inv_slots);
+                }
+            } else {
+                apply_upgrade(furnace, inputs.start, &proto, &gems);
+                for slot in std::iter::once(inputs.start).chain(outputs.clone()) {
+                    mark_slot_dirty(slot, InventorySlotType::Furnace, &mut inv_slots);
+                }
+            }
+
+            if let Some(state) = furnace.state.as_ref() {
+                if state.current_fuel_left.finished() {
+                    furnace.state = None;
+                }
+            }
+            furnace.timer.reset();
+        }
+    };
+
+    if let Some(mut furnace) = furnace_option {
+        process_machine(&mut furnace);
+    }
+    for mut furnace in furnace_objects.iter_mut() {
+        process_machine(&mut furnace);
+    }
+}
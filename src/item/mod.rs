@@ -1,6 +1,6 @@
 use crate::animations::AttackAnimationTimer;
 use crate::assets::{SpriteSize, WorldObjectData};
-use crate::attributes::item_abilities::ItemAbility;
+use crate::attributes::{affixes::ItemAffix, item_abilities::ItemAbility};
 use crate::attributes::ItemAttributes;
 use crate::colors::{
     BLACK, BLUE, DARK_BROWN, DARK_GREEN, LIGHT_BROWN, LIGHT_GREEN, LIGHT_GREY, PINK, RED,
@@ -33,11 +33,13 @@ use combat_shrine::{handle_shrine_rewards, CombatShrineMobDeathEvent};
 use rand::Rng;
 
 mod crafting;
+pub mod durability;
 pub mod item_actions;
 
 pub mod combat_shrine;
 pub mod item_upgrades;
 mod loot_table;
+pub mod machine;
 pub mod melee;
 pub mod object_actions;
 pub mod projectile;
@@ -51,6 +53,8 @@ use serde::{Deserialize, Serialize};
 use strum_macros::{Display, EnumIter, IntoStaticStr};
 
 use self::crafting::CraftingPlugin;
+use self::durability::DurabilityPlugin;
+use self::machine::MachinePlugin;
 use self::item_actions::handle_item_action_success;
 use self::item_upgrades::{
     handle_burning_ticks, handle_delayed_ranged_attack, handle_on_hit_upgrades,
@@ -181,6 +185,11 @@ pub struct ItemDisplayMetaData {
     pub desc: Vec<String>,
     pub level: Option<u8>,
     pub item_ability: Option<ItemAbility>,
+    #[serde(default)]
+    pub affixes: Vec<ItemAffix>,
+    /// Gems socketed at the upgrade station, in the order they went in
+    #[serde(default)]
+    pub gems: Vec<WorldObject>,
 }
 #[derive(Component)]
 pub struct Size(pub Vec2);
@@ -334,13 +343,22 @@ pub enum WorldObject {
     WoodDoor,
     WoodDoorOpen,
     WoodDoorBlock,
+    Pen,
+    PenBlock,
     MagicGem,
+    Ruby,
+    Sapphire,
+    Emerald,
+    Topaz,
+    Chisel,
     MagicTusk,
     Bed,
     BedBlock,
     Essence,
     Key,
     MiracleSeed,
+    Blueprint,
+    GolemCore,
 
     // Sapplings
     RedSapplingBlock,
@@ -552,6 +570,8 @@ impl WorldObject {
                 level: None,
                 desc: vec!["A cool piece of Equipment".to_string()],
                 item_ability: None,
+                affixes: vec![],
+                gems: vec![],
             })
             .insert(Equipment(Limb::Hands))
             .insert(YSort(0.))
@@ -624,6 +644,7 @@ impl WorldObject {
             WorldObject::Cattail => UI_GRASS_GREEN,
             WorldObject::WoodWall => LIGHT_BROWN,
             WorldObject::WoodDoor => LIGHT_BROWN,
+            WorldObject::Pen => LIGHT_BROWN,
 
             _ => BLACK,
         }
@@ -653,6 +674,8 @@ impl Plugin for ItemsPlugin {
             .add_plugin(CraftingPlugin)
             .add_plugin(RangedAttackPlugin)
             .add_plugin(LootTablePlugin)
+            .add_plugin(MachinePlugin)
+            .add_plugin(DurabilityPlugin)
             .add_system(
                 handle_break_object
                     .before(CustomFlush)
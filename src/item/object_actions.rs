@@ -108,6 +108,8 @@ impl ObjectAction {
                 };
                 let crafting_container_res = CraftingContainer {
                     items: Container { items },
+                    station_pos: Some(tile_pos_to_world_pos(obj_pos, false)),
+                    station: crafting_type.clone(),
                 };
                 commands.insert_resource(crafting_container_res.clone());
             }
@@ -146,20 +146,24 @@ fn handle_ranged_attack_event(
                 continue;
             }
         }
-        let t = if let Some(enemy) = proj_event.from_enemy {
-            enemy_transforms
+        let (t, direction) = if let Some(enemy) = proj_event.from_enemy {
+            let t = enemy_transforms
                 .get(enemy)
                 .unwrap()
                 .translation()
-                .truncate()
+                .truncate();
+            (t, proj_event.direction)
+        } else if proj_event.direction == Vec2::ZERO {
+            // nothing picked a direction, so it's launched the way the player is aiming
+            (game.player().position.truncate(), game.player().aim)
         } else {
-            game.player().position.truncate()
+            (game.player().position.truncate(), proj_event.direction)
         };
         let p = proto_commands.spawn_projectile_from_proto(
             proj_event.projectile.clone(),
             &proto,
             t,
-            proj_event.direction,
+            direction,
         );
         if let Some(p) = p {
             if let Some(e) = proj_event.from_enemy {
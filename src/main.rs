@@ -1,6 +1,7 @@
 #![allow(non_snake_case)]
 use std::{ env, marker::PhantomData, ops::{ Deref, DerefMut } };
 
+use action_map::ActionMapPlugin;
 use ai::AIPlugin;
 use attributes::{
     Attack,
@@ -51,16 +52,18 @@ use bevy::{
         view::RenderLayers,
     },
     sprite::{ Material2d, Material2dPlugin, MaterialMesh2dBundle },
-    window::{ PresentMode, WindowResolution },
+    window::{ WindowResizeConstraints, WindowResolution },
 };
 
 mod juice;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_rapier2d::prelude::*;
+mod action_map;
 mod ai;
 mod animations;
 mod assets;
 mod attributes;
+mod camera;
 mod client;
 mod colors;
 mod combat;
@@ -71,15 +74,20 @@ mod inventory;
 mod item;
 mod night;
 mod player;
+mod playtest;
 mod proto;
 mod sappling;
 mod schematic;
+mod settings;
+#[cfg(test)]
+mod tests;
 mod ui;
 mod world;
 use animations::AnimationsPlugin;
 use assets::{ GameAssetsPlugin, Graphics, SpriteSize };
 use bevy_asset_loader::prelude::{ AssetCollection, LoadingState, LoadingStateAppExt };
 use bevy_ecs_tilemap::TilemapPlugin;
+use camera::CameraPlugin;
 use client::ClientPlugin;
 use combat::*;
 use enemy::EnemyPlugin;
@@ -87,9 +95,11 @@ use inputs::InputsPlugin;
 use inventory::ItemStack;
 use item::{ Equipment, ItemsPlugin, WorldObject, WorldObjectResource };
 use player::{ Player, PlayerPlugin, PlayerState };
+use playtest::{ PlaytestConfig, PlaytestPlugin };
 use proto::{ proto_param::ProtoParam, ProtoPlugin };
 
 use schematic::SchematicPlugin;
+use settings::{ Settings, SettingsPlugin };
 use ui::{
     display_main_menu,
     handle_menu_button_click_events,
@@ -113,15 +123,14 @@ use world::{
 use crate::assets::SpriteAnchor;
 use lazy_static::lazy_static;
 
-const ZOOM_SCALE: f32 = 1.0;
-const PLAYER_MOVE_SPEED: f32 = 90.0 * ZOOM_SCALE;
-const PLAYER_DASH_SPEED: f32 = 250.0 * ZOOM_SCALE;
+const PLAYER_MOVE_SPEED: f32 = 90.0;
+const PLAYER_DASH_SPEED: f32 = 250.0;
 pub const TIME_STEP: f32 = 1.0 / 60.0;
 pub const HEIGHT: f32 = 1600.0;
 pub const ASPECT_RATIO: f32 = 16.0 / 9.0;
 pub const WIDTH: f32 = HEIGHT * ASPECT_RATIO;
-pub const GAME_HEIGHT: f32 = 180.0 * ZOOM_SCALE;
-pub const GAME_WIDTH: f32 = 320.0 * ZOOM_SCALE;
+pub const GAME_HEIGHT: f32 = 180.0;
+pub const GAME_WIDTH: f32 = 320.0;
 lazy_static! {
     pub static ref DEBUG_MODE: bool = env::var("DEBUG_MODE").is_ok();
 }
@@ -134,37 +143,60 @@ lazy_static! {
 lazy_static! {
     pub static ref COLLIDERS: bool = env::var("COLLIDERS").is_ok();
 }
+lazy_static! {
+    pub static ref PLAYTEST: Option<PlaytestConfig> = PlaytestConfig::from_env();
+}
 
 fn main() {
+    let mut settings = Settings::load();
+    if let Some(playtest) = PLAYTEST.as_ref() {
+        playtest.apply_to_settings(&mut settings);
+    }
     let mut app = App::new();
     let app = app
         .add_state::<GameState>()
+        .add_state::<PauseState>()
+        .configure_set(OnUpdate(GameState::Main).run_if(in_state(PauseState::Running)))
         .edit_schedule(CoreSchedule::FixedUpdate, |s| {
-            s.configure_set(CoreGameSet::Main.run_if(in_state(GameState::Main)));
+            s.configure_set(
+                CoreGameSet::Main.run_if(in_state(GameState::Main)).run_if(
+                    in_state(PauseState::Running)
+                )
+            );
         })
         .add_plugins(
             DefaultPlugins.set(AssetPlugin {
-                // Enable hot-reloading of assets:
-                watch_for_changes: false,
+                // Enable hot-reloading of assets, used to rebalance prototypes like the spawn table:
+                watch_for_changes: *DEBUG_MODE,
                 ..default()
             })
                 .set(ImagePlugin::default_nearest())
                 // .disable::<LogPlugin>()
                 .set(WindowPlugin {
                     primary_window: Some(Window {
-                        resolution: WindowResolution::new(WIDTH, HEIGHT).with_scale_factor_override(
-                            1.0
-                        ),
+                        resolution: WindowResolution::new(
+                            settings.resolution.0 as f32,
+                            settings.resolution.1 as f32
+                        ).with_scale_factor_override(1.0),
                         title: "Hiru's Island".to_string(),
-                        present_mode: PresentMode::Fifo,
-                        resizable: false,
+                        present_mode: settings.get_present_mode(),
+                        mode: settings.get_window_mode(),
+                        resizable: true,
+                        resize_constraints: WindowResizeConstraints {
+                            min_width: GAME_WIDTH,
+                            min_height: GAME_HEIGHT,
+                            ..default()
+                        },
                         transparent: true,
+                        visible: !PLAYTEST.as_ref().map_or(false, |p| p.headless),
                         ..Default::default()
                     }),
                     ..default()
                 })
         )
         .insert_resource(Msaa::Off)
+        .insert_resource(settings)
+        .insert_resource(GameViewport::default())
         .insert_resource(FixedTime::new_from_secs(TIME_STEP))
         .add_plugin(AsepritePlugin)
         .add_plugin(FrameTimeDiagnosticsPlugin::default())
@@ -174,9 +206,12 @@ fn main() {
         .add_plugin(TilemapPlugin)
         .add_plugin(GameAssetsPlugin)
         .add_plugin(AudioPlugin)
+        .add_plugin(SettingsPlugin)
         .add_plugin(ItemsPlugin)
         .add_plugin(AnimationsPlugin)
+        .add_plugin(ActionMapPlugin)
         .add_plugin(InputsPlugin)
+        .add_plugin(CameraPlugin)
         .add_plugin(UIPlugin)
         .add_plugin(NightPlugin)
         .add_plugin(SapplingPlugin)
@@ -204,6 +239,9 @@ fn main() {
     if *COLLIDERS {
         app.add_plugin(RapierDebugRenderPlugin::default());
     }
+    if let Some(config) = PLAYTEST.clone() {
+        app.add_plugin(PlaytestPlugin { config });
+    }
 
     app.run();
 }
@@ -236,6 +274,14 @@ pub enum GameState {
     Main,
     GameOver,
 }
+/// Kept separate from `GameState`, so resuming does not re-run the `OnEnter(GameState::Main)`
+/// setup. While paused, the `OnUpdate(GameState::Main)` and `CoreGameSet::Main` sets don't run.
+#[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
 struct CustomFlush;
 
@@ -469,6 +515,27 @@ pub struct RawPosition(Vec2);
 #[derive(Component)]
 pub struct GameUpscale(pub f32);
 
+/// The images the game and UI cameras render to, resized along with the window
+#[derive(Resource)]
+pub struct RenderTargetImages {
+    pub game: Handle<Image>,
+    pub ui: Handle<Image>,
+}
+
+/// How much of the world and UI is on screen, in game pixels. This is
+/// `GAME_WIDTH`x`GAME_HEIGHT`, unless the view is expanded to fill a wider or taller window.
+#[derive(Resource)]
+pub struct GameViewport {
+    pub size: Vec2,
+}
+impl Default for GameViewport {
+    fn default() -> Self {
+        Self {
+            size: Vec2::new(GAME_WIDTH, GAME_HEIGHT),
+        }
+    }
+}
+
 impl Deref for RawPosition {
     type Target = Vec2;
     fn deref(&self) -> &Self::Target {
@@ -547,6 +614,10 @@ fn setup(
 
     let game_image_handle = images.add(game_image);
     let ui_image_handle = images.add(ui_image);
+    commands.insert_resource(RenderTargetImages {
+        game: game_image_handle.clone(),
+        ui: ui_image_handle.clone(),
+    });
 
     // This specifies the layer used for the first pass, which will be attached to the first pass camera and cube.
     let first_pass_layer = RenderLayers::layer(1);
@@ -593,15 +664,9 @@ fn setup(
     let _game_texture_image = commands
         .spawn((
             MaterialMesh2dBundle {
-                mesh: meshes
-                    .add(
-                        (shape::Quad {
-                            size: Vec2::new(game_size.x, game_size.y),
-                            ..Default::default()
-                        }).into()
-                    )
-                    .into(),
-                transform: Transform::from_scale(Vec3::new(1.0, 1.0, 1.0)),
+                // unit sized, and scaled to the part of the window the game covers
+                mesh: meshes.add(shape::Quad::new(Vec2::ONE).into()).into(),
+                transform: Transform::from_scale(game_size.extend(1.0)),
                 material: game_render_material_handle,
                 ..default()
             },
@@ -612,23 +677,16 @@ fn setup(
     let _ui_texture_image = commands
         .spawn((
             MaterialMesh2dBundle {
-                mesh: meshes
-                    .add(
-                        (shape::Quad {
-                            size: Vec2::new(game_size.x, game_size.y),
-                            ..Default::default()
-                        }).into()
-                    )
-                    .into(),
+                mesh: meshes.add(shape::Quad::new(Vec2::ONE).into()).into(),
                 transform: Transform {
                     translation: Vec3::new(0.0, 0.0, 1.0),
-                    scale: Vec3::new(1.0, 1.0, 1.0),
+                    scale: game_size.extend(1.0),
                     ..default()
                 },
                 material: ui_render_material_handle,
                 ..default()
             },
-            // TextureTarget,
+            TextureTarget,
             second_pass_layer,
         ))
         .id();
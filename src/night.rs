@@ -5,8 +5,9 @@ use serde::{Deserialize, Serialize};
 use crate::{
     audio::{BGMPicker, UpdateBGMTrackEvent},
     colors::{overwrite_alpha, NIGHT},
+    ui::ScreenOverlay,
     world::dimension::dim_spawned,
-    GameState, GAME_HEIGHT, GAME_WIDTH,
+    GameState,
 };
 
 #[derive(Component)]
@@ -56,7 +57,6 @@ pub fn spawn_night(mut commands: Commands, night_tracker: Res<NightTracker>) {
         .spawn(SpriteBundle {
             sprite: Sprite {
                 color: overwrite_alpha(NIGHT, night_tracker.get_alpha()),
-                custom_size: Some(Vec2::new(GAME_WIDTH, GAME_HEIGHT)),
                 ..default()
             },
             transform: Transform {
@@ -67,6 +67,7 @@ pub fn spawn_night(mut commands: Commands, night_tracker: Res<NightTracker>) {
             ..default()
         })
         .insert(RenderLayers::from_layers(&[3]))
+        .insert(ScreenOverlay::default())
         .insert(Night(Timer::from_seconds(17., TimerMode::Repeating)))
         .insert(Name::new("night"));
 }
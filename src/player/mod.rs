@@ -22,10 +22,11 @@ use crate::{
         Attack, AttackCooldown, CritChance, CritDamage, HealthRegen, InvincibilityCooldown,
         ItemAttributes, Mana, ManaRegen, MaxHealth, PlayerAttributeBundle,
     },
-    client::CurrentRunSaveData,
+    camera::move_camera_with_player,
+    client::{get_data_path, CurrentRunSaveData, SAVE_PATH},
     container::Container,
     custom_commands::CommandsExt,
-    inputs::{move_camera_with_player, FacingDirection, MovementVector},
+    inputs::{FacingDirection, MovementVector},
     inventory::{Inventory, INVENTORY_SIZE},
     item::{ActiveMainHandState, WorldObject},
     juice::RunDustTimer,
@@ -51,6 +52,11 @@ pub struct Player;
 #[derive(Debug, Clone)]
 pub struct PlayerState {
     pub direction: FacingDirection,
+    /// Normalized direction the player is aiming with the mouse or right stick, see
+    /// `update_player_aim`. Kept when there's nothing to aim with.
+    pub aim: Vec2,
+    /// The hostile aim assist snapped `aim` to
+    pub aim_assist_target: Option<Entity>,
     pub is_moving: bool,
     pub is_dashing: bool,
     pub is_attacking: bool,
@@ -65,6 +71,8 @@ impl Default for PlayerState {
     fn default() -> Self {
         Self {
             direction: FacingDirection::Left,
+            aim: Vec2::new(-1., 0.),
+            aim_assist_target: None,
             is_moving: true,
             is_dashing: false,
             is_attacking: false,
@@ -238,7 +246,7 @@ fn spawn_player(
 
     let mut hunger = Hunger::new(100);
     // Try to load inv from save
-    if let Ok(save_file) = File::open("save_state.json") {
+    if let Ok(save_file) = File::open(get_data_path(SAVE_PATH)) {
         let reader = BufReader::new(save_file);
 
         // Read the JSON contents of the file as an instance of `User`.
@@ -266,7 +274,7 @@ fn spawn_player(
 }
 
 fn give_player_starting_items(mut proto_commands: ProtoCommands, proto: ProtoParam) {
-    if let Ok(_) = File::open("save_state.json") {
+    if let Ok(_) = File::open(get_data_path(SAVE_PATH)) {
         return;
     }
     proto_commands.spawn_item_from_proto(WorldObject::WoodSword, &proto, Vec2::ZERO, 1, Some(1));
@@ -0,0 +1,667 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    env, fs,
+    str::FromStr,
+};
+
+use bevy::{app::AppExit, input::InputSystem, prelude::*};
+use bevy_rapier2d::prelude::{RapierConfiguration, TimestepMode};
+use rand::Rng;
+use serde::Serialize;
+
+use crate::{
+    action_map::{ActionMap, InputAction, InputBinding, HOTBAR_ACTIONS, MOVE_ACTIONS},
+    assets::SpriteAnchor,
+    attributes::{hunger::Hunger, CurrentHealth, MaxHealth},
+    client::{get_data_path, GameOverEvent, NextRunSeed, SAVE_PATH},
+    enemy::{passive::Tamed, CombatAlignment, Mob},
+    inputs::CursorPos,
+    inventory::{Inventory, ItemStack},
+    item::{
+        item_actions::{ItemAction, ItemActions},
+        CraftRequestEvent, CraftingTracker, EquipmentType, RequiredEquipmentType, WorldObject,
+    },
+    juice::UseItemEvent,
+    night::NightTracker,
+    player::{levels::PlayerLevel, Player},
+    proto::proto_param::ProtoParam,
+    sappling::Sappling,
+    settings::Settings,
+    ui::{InventoryState, MenuButton, MenuButtonClickEvent, PauseMenuState, UIState},
+    world::TILE_SIZE,
+    GameState, GAME_HEIGHT, GAME_WIDTH, TIME_STEP,
+};
+
+/// Playtests keep their saves, run history and results here, away from the player's
+pub const PLAYTEST_DIR: &str = "playtest";
+const RESULTS_PATH: &str = "playtest_results.json";
+
+/// Mobs this close are fought, further at night when they come looking for the player
+const FIGHT_RANGE: f32 = 5. * TILE_SIZE.x;
+const NIGHT_FIGHT_RANGE: f32 = 8. * TILE_SIZE.x;
+/// How far the bot looks for something to break, at night it stays near where it started
+const GATHER_RANGE: f32 = 14. * TILE_SIZE.x;
+const NIGHT_HOME_RANGE: f32 = 6. * TILE_SIZE.x;
+/// Close enough to swing at a target, well inside the player's reach
+const ATTACK_RANGE: f32 = 1.2 * TILE_SIZE.x;
+/// Damage taken with no mob this close is put down to something else, like starving
+const DAMAGE_SOURCE_RANGE: f32 = 10. * TILE_SIZE.x;
+/// Hunger and health, in percent, below which the bot eats
+const EAT_BELOW: i32 = 50;
+const HEAL_BELOW: i32 = 40;
+/// Gear the bot crafts as soon as it has the ingredients
+const CRAFT_GOALS: [WorldObject; 2] = [WorldObject::WoodAxe, WorldObject::WoodSword];
+
+/// Set from environment variables, `PLAYTEST` being the number of runs to play
+#[derive(Resource, Clone, Debug)]
+pub struct PlaytestConfig {
+    pub runs: u32,
+    /// Each run uses the next seed up from this one, `PLAYTEST_SEED`
+    pub first_seed: u64,
+    /// How many times faster than real time the game runs, `PLAYTEST_SPEED`
+    pub speed: f32,
+    /// Runs still going on this day end as survived, `PLAYTEST_DAYS`
+    pub max_days: u8,
+    /// Hides the window and turns off vsync and sound, `HEADLESS`
+    pub headless: bool,
+}
+
+impl PlaytestConfig {
+    pub fn from_env() -> Option<Self> {
+        let runs = parse_env_var("PLAYTEST")?;
+        Some(Self {
+            runs,
+            first_seed: parse_env_var("PLAYTEST_SEED").unwrap_or(0),
+            speed: parse_env_var::<f32>("PLAYTEST_SPEED").unwrap_or(1.).max(1.),
+            max_days: parse_env_var("PLAYTEST_DAYS").unwrap_or(10),
+            headless: env::var("HEADLESS").is_ok(),
+        })
+    }
+    /// Only changes the loaded settings, the player's settings file is left as it was
+    pub fn apply_to_settings(&self, settings: &mut Settings) {
+        if self.headless {
+            settings.resolution = (GAME_WIDTH as u32, GAME_HEIGHT as u32);
+            settings.fullscreen = false;
+            settings.vsync = false;
+            settings.master_volume = 0.;
+        }
+    }
+}
+
+fn parse_env_var<T: FromStr>(name: &str) -> Option<T> {
+    env::var(name).ok()?.parse().ok()
+}
+
+/// What a playtest run measured, written out when it ends
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunMetrics {
+    pub seed: u64,
+    /// `None` if the run lasted until `max_days`
+    pub day_of_death: Option<u8>,
+    pub level_reached: u8,
+    pub seconds_survived: f32,
+    /// Keyed by the `Mob` nearest the player when they were hurt
+    pub damage_taken: BTreeMap<String, i32>,
+    pub food_eaten: u32,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct PlaytestState {
+    pub next_run: u32,
+    pub current: Option<RunMetrics>,
+    /// Game time the current run started at, sped up along with everything else
+    pub run_started: f32,
+    pub results: Vec<RunMetrics>,
+}
+
+impl PlaytestState {
+    fn finish_run(&mut self, mut metrics: RunMetrics, level: u8, now: f32) {
+        metrics.level_reached = level;
+        metrics.seconds_survived = now - self.run_started;
+        println!(
+            "PLAYTEST: run {} on seed {} ended on day {:?}, level {}",
+            self.results.len() + 1,
+            metrics.seed,
+            metrics.day_of_death,
+            level
+        );
+        self.results.push(metrics);
+        // written after every run, so a sweep stopped partway still has its results
+        let results = serde_json::to_string_pretty(&self.results).unwrap();
+        if let Err(e) = fs::write(get_data_path(RESULTS_PATH), results) {
+            println!("Failed to write playtest results: {e}");
+        }
+    }
+}
+
+/// The actions the bot wants this frame, pressed on its behalf by `apply_bot_input`
+#[derive(Resource, Default, Debug)]
+pub struct BotInput {
+    /// Held down until the bot lets go of them
+    pub held: HashSet<InputAction>,
+    /// Pressed for a single frame
+    pub taps: Vec<InputAction>,
+    /// Where the bot points the cursor, in world coordinates
+    pub aim_at: Option<Vec2>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BotGoal {
+    Eat,
+    Fight,
+    Gather,
+    Plant,
+    Wander,
+}
+
+/// The bot's simple policy: eat when hungry or hurt, fight nearby mobs,
+/// craft an axe and sword, break trees and other objects, and replant saplings.
+/// At night it stays near where the run started.
+#[derive(Resource, Debug)]
+pub struct PlaytestBot {
+    pub goal: BotGoal,
+    home: Option<Vec2>,
+    /// Spaces out one-off actions like eating and planting
+    action_cooldown: Timer,
+    craft_cooldown: Timer,
+    wander_dir: Vec2,
+    wander_timer: Timer,
+    last_pos: Vec2,
+    stuck_timer: Timer,
+    /// Walking off in `wander_dir` after getting stuck, whatever the goal
+    detour: Option<Timer>,
+}
+
+impl Default for PlaytestBot {
+    fn default() -> Self {
+        Self {
+            goal: BotGoal::Wander,
+            home: None,
+            action_cooldown: Timer::from_seconds(1., TimerMode::Once),
+            craft_cooldown: Timer::from_seconds(5., TimerMode::Once),
+            wander_dir: Vec2::X,
+            wander_timer: Timer::from_seconds(3., TimerMode::Once),
+            last_pos: Vec2::ZERO,
+            stuck_timer: Timer::from_seconds(1.5, TimerMode::Once),
+            detour: None,
+        }
+    }
+}
+
+impl PlaytestBot {
+    fn move_towards(&self, input: &mut BotInput, from: Vec2, to: Vec2) {
+        let dir = (to - from).normalize_or_zero();
+        move_in_direction(input, dir);
+    }
+    fn pick_wander_dir(&mut self) {
+        let angle = rand::thread_rng().gen_range(0. ..std::f32::consts::TAU);
+        self.wander_dir = Vec2::from_angle(angle);
+        self.wander_timer.reset();
+    }
+}
+
+fn move_in_direction(input: &mut BotInput, dir: Vec2) {
+    // diagonals are held as two keys, small components are dropped like a player would
+    for (action, pressed) in
+        MOVE_ACTIONS
+            .into_iter()
+            .zip([dir.y > 0.3, dir.y < -0.3, dir.x < -0.3, dir.x > 0.3])
+    {
+        if pressed {
+            input.held.insert(action);
+        }
+    }
+}
+
+pub struct PlaytestPlugin {
+    pub config: PlaytestConfig,
+}
+
+impl Plugin for PlaytestPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.config.clone())
+            .init_resource::<PlaytestState>()
+            .init_resource::<PlaytestBot>()
+            .init_resource::<BotInput>()
+            .add_startup_system(setup_playtest)
+            .add_system(start_next_playtest_run.in_set(OnUpdate(GameState::MainMenu)))
+            .add_system(reset_bot.in_schedule(OnEnter(GameState::Main)))
+            .add_systems(
+                (
+                    run_bot_policy,
+                    track_damage_taken,
+                    track_food_eaten,
+                    end_run_on_game_over,
+                    end_run_at_max_days,
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            )
+            .add_system(
+                apply_bot_input
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem)
+                    .run_if(in_state(GameState::Main)),
+            );
+    }
+}
+
+/// Speeds up time, and lets physics take larger steps to keep up with it
+fn setup_playtest(
+    config: Res<PlaytestConfig>,
+    mut time: ResMut<Time>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    if let Err(e) = fs::create_dir_all(PLAYTEST_DIR) {
+        println!("Failed to create the playtest folder: {e}");
+    }
+    time.set_relative_speed(config.speed);
+    rapier_config.timestep_mode = TimestepMode::Variable {
+        max_dt: TIME_STEP * config.speed,
+        time_scale: 1.,
+        substeps: config.speed.ceil() as usize,
+    };
+}
+
+/// Presses Start for each run in turn, and quits once they're all played
+fn start_next_playtest_run(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<PlaytestConfig>,
+    mut playtest: ResMut<PlaytestState>,
+    mut menu_events: EventWriter<MenuButtonClickEvent>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if playtest.current.is_some() {
+        return;
+    }
+    if playtest.next_run >= config.runs {
+        println!("PLAYTEST: finished {} runs", playtest.results.len());
+        exit.send(AppExit);
+        return;
+    }
+    let seed = config.first_seed + playtest.next_run as u64;
+    playtest.next_run += 1;
+    playtest.current = Some(RunMetrics { seed, ..default() });
+    playtest.run_started = time.elapsed_seconds();
+    // every run starts fresh, never from the last one's save
+    let _ = fs::remove_file(get_data_path(SAVE_PATH));
+    commands.insert_resource(NextRunSeed(seed));
+    menu_events.send(MenuButtonClickEvent {
+        button: MenuButton::Start,
+    });
+}
+
+fn reset_bot(mut bot: ResMut<PlaytestBot>, mut bot_input: ResMut<BotInput>) {
+    *bot = PlaytestBot::default();
+    *bot_input = BotInput::default();
+}
+
+/// Presses and releases each action's first keyboard or mouse binding, so the bot's
+/// input goes through the same bindings and systems as a player's
+fn apply_bot_input(
+    mut bot_input: ResMut<BotInput>,
+    action_map: Res<ActionMap>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut mouse: ResMut<Input<MouseButton>>,
+    mut cursor_pos: ResMut<CursorPos>,
+    mut pressed_last_frame: Local<HashSet<InputAction>>,
+) {
+    let taps: Vec<InputAction> = bot_input.taps.drain(..).collect();
+    let pressed: HashSet<InputAction> = bot_input.held.iter().copied().chain(taps).collect();
+    for (action, press) in pressed_last_frame
+        .difference(&pressed)
+        .map(|a| (*a, false))
+        .chain(pressed.iter().map(|a| (*a, true)))
+    {
+        match action_map
+            .get_bindings(action)
+            .iter()
+            .find(|b| !b.is_gamepad())
+        {
+            Some(InputBinding::Key(key)) if press => keys.press(*key),
+            Some(InputBinding::Key(key)) => keys.release(*key),
+            Some(InputBinding::Mouse(button)) if press => mouse.press(*button),
+            Some(InputBinding::Mouse(button)) => mouse.release(*button),
+            _ => {}
+        }
+    }
+    *pressed_last_frame = pressed;
+
+    // like a mouse pointing at the target, which the player's aim follows
+    if let Some(target) = bot_input.aim_at {
+        cursor_pos.world_coords = target.extend(0.);
+        cursor_pos.gamepad_aim = None;
+    }
+}
+
+/// The first hotbar slot holding an item that passes `check`
+fn find_hotbar_slot(inv: &Inventory, check: impl Fn(WorldObject) -> bool) -> Option<usize> {
+    inv.items
+        .items
+        .iter()
+        .take(HOTBAR_ACTIONS.len())
+        .position(|item| item.as_ref().map_or(false, |i| check(*i.get_obj())))
+}
+
+/// Taps the slot's hotbar key, true once it is the active slot
+fn select_hotbar_slot(input: &mut BotInput, inv_state: &InventoryState, slot: usize) -> bool {
+    if inv_state.active_hotbar_slot == slot {
+        return true;
+    }
+    input.taps.push(HOTBAR_ACTIONS[slot]);
+    false
+}
+
+fn has_equipment_type(obj: WorldObject, eqp_type: &EquipmentType, proto: &ProtoParam) -> bool {
+    obj.get_equip_type(proto).as_ref() == Some(eqp_type)
+}
+
+fn run_bot_policy(
+    time: Res<Time>,
+    mut bot: ResMut<PlaytestBot>,
+    mut bot_input: ResMut<BotInput>,
+    player: Query<
+        (
+            &GlobalTransform,
+            &CurrentHealth,
+            &MaxHealth,
+            &Hunger,
+            &Inventory,
+        ),
+        With<Player>,
+    >,
+    mobs: Query<(&GlobalTransform, &CombatAlignment), (With<Mob>, Without<Tamed>)>,
+    objects: Query<
+        (
+            &GlobalTransform,
+            &WorldObject,
+            Option<&RequiredEquipmentType>,
+        ),
+        (With<MaxHealth>, Without<ItemStack>, Without<Mob>),
+    >,
+    proto: ProtoParam,
+    inv_state: Res<InventoryState>,
+    ui_state: Res<State<UIState>>,
+    night_tracker: Res<NightTracker>,
+    crafting_tracker: Res<CraftingTracker>,
+    mut craft_events: EventWriter<CraftRequestEvent>,
+) {
+    let Ok((player_t, health, max_health, hunger, inv)) = player.get_single() else {
+        return;
+    };
+    let pos = player_t.translation().truncate();
+    let home = *bot.home.get_or_insert(pos);
+    bot_input.held.clear();
+    bot_input.aim_at = None;
+    bot.action_cooldown.tick(time.delta());
+    bot.craft_cooldown.tick(time.delta());
+    bot.wander_timer.tick(time.delta());
+    bot.stuck_timer.tick(time.delta());
+
+    // anything opened by accident, like a chest walked into, is closed again
+    if ui_state.0 != UIState::Closed {
+        if bot.action_cooldown.finished() {
+            bot_input.taps.push(InputAction::CloseMenu);
+            bot.action_cooldown.reset();
+        }
+        return;
+    }
+
+    // eat, or drink a potion when hurt
+    let is_hurt = health.0 * 100 < max_health.0 * HEAL_BELOW;
+    let is_hungry = (hunger.current as i32) * 100 < (hunger.max as i32) * EAT_BELOW;
+    if (is_hurt || is_hungry) && bot.action_cooldown.finished() {
+        let food_slot = find_hotbar_slot(inv, |obj| {
+            proto
+                .get_component::<ItemActions, _>(obj)
+                .map_or(false, |a| {
+                    a.actions.iter().any(|action| match action {
+                        ItemAction::Eat(_) => true,
+                        ItemAction::ModifyHealth(delta) => is_hurt && *delta > 0,
+                        _ => false,
+                    })
+                })
+        });
+        if let Some(slot) = food_slot {
+            bot.goal = BotGoal::Eat;
+            if select_hotbar_slot(&mut bot_input, &inv_state, slot) {
+                bot_input.taps.push(InputAction::UseItem);
+                bot.action_cooldown.reset();
+            }
+            return;
+        }
+    }
+
+    // craft the next piece of gear on the list, straight into the inventory
+    if bot.craft_cooldown.finished() {
+        bot.craft_cooldown.reset();
+        if let Some(obj) = CRAFT_GOALS.into_iter().find(|obj| {
+            crafting_tracker.craftable.contains(obj)
+                && find_hotbar_slot(inv, |o| o == *obj).is_none()
+        }) {
+            craft_events.send(CraftRequestEvent {
+                obj,
+                count: 1,
+                station_pos: None,
+            });
+        }
+    }
+
+    // fight the closest hostile mob in range
+    let fight_range = if night_tracker.is_night() {
+        NIGHT_FIGHT_RANGE
+    } else {
+        FIGHT_RANGE
+    };
+    let closest_mob = mobs
+        .iter()
+        .filter(|(_, alignment)| matches!(alignment, CombatAlignment::Hostile))
+        .map(|(t, _)| t.translation().truncate())
+        .filter(|mob_pos| mob_pos.distance(pos) <= fight_range)
+        .min_by(|a, b| a.distance(pos).total_cmp(&b.distance(pos)));
+    if let Some(mob_pos) = closest_mob {
+        bot.goal = BotGoal::Fight;
+        let weapon_slot = find_hotbar_slot(inv, |obj| {
+            has_equipment_type(obj, &EquipmentType::Weapon, &proto)
+        });
+        if weapon_slot.map_or(true, |slot| {
+            select_hotbar_slot(&mut bot_input, &inv_state, slot)
+        }) {
+            bot_input.aim_at = Some(mob_pos);
+            if mob_pos.distance(pos) <= ATTACK_RANGE {
+                bot_input.held.insert(InputAction::Attack);
+            }
+        }
+        if mob_pos.distance(pos) > ATTACK_RANGE * 0.75 {
+            bot.move_towards(&mut bot_input, pos, mob_pos);
+        }
+        return;
+    }
+
+    // replant saplings while it's light out
+    if !night_tracker.is_night() && bot.action_cooldown.finished() {
+        let sappling_slot = find_hotbar_slot(inv, |obj| {
+            proto
+                .get_component::<ItemActions, _>(obj)
+                .map_or(false, |a| {
+                    a.actions.iter().any(|action| match action {
+                        ItemAction::PlacesInto(placed) => {
+                            proto.get_component::<Sappling, _>(*placed).is_some()
+                        }
+                        _ => false,
+                    })
+                })
+        });
+        if let Some(slot) = sappling_slot {
+            bot.goal = BotGoal::Plant;
+            if select_hotbar_slot(&mut bot_input, &inv_state, slot) {
+                bot_input.aim_at = Some(pos + bot.wander_dir * TILE_SIZE.x);
+                bot_input.taps.push(InputAction::UseItem);
+                bot.action_cooldown.reset();
+            }
+            return;
+        }
+    }
+
+    // break the closest object the held tools can break, trees once there's an axe
+    let tool_types: Vec<EquipmentType> = [EquipmentType::Axe, EquipmentType::Pickaxe]
+        .into_iter()
+        .filter(|t| find_hotbar_slot(inv, |obj| has_equipment_type(obj, t, &proto)).is_some())
+        .collect();
+    let search_origin = if night_tracker.is_night() { home } else { pos };
+    let search_range = if night_tracker.is_night() {
+        NIGHT_HOME_RANGE
+    } else {
+        GATHER_RANGE
+    };
+    let target = objects
+        .iter()
+        .filter(|(_, _, required)| required.map_or(true, |r| tool_types.contains(&r.0)))
+        .map(|(t, obj, required)| {
+            let anchor = proto
+                .get_component::<SpriteAnchor, _>(*obj)
+                .map_or(Vec2::ZERO, |a| a.0);
+            (t.translation().truncate() - anchor, required)
+        })
+        .filter(|(target_pos, _)| target_pos.distance(search_origin) <= search_range)
+        .min_by(|(a, _), (b, _)| a.distance(pos).total_cmp(&b.distance(pos)));
+    if let Some((target_pos, required)) = target {
+        bot.goal = BotGoal::Gather;
+        let tool_slot = required
+            .and_then(|r| find_hotbar_slot(inv, |obj| has_equipment_type(obj, &r.0, &proto)));
+        if tool_slot.map_or(true, |slot| {
+            select_hotbar_slot(&mut bot_input, &inv_state, slot)
+        }) {
+            bot_input.aim_at = Some(target_pos);
+            if target_pos.distance(pos) <= ATTACK_RANGE {
+                bot_input.held.insert(InputAction::Attack);
+            }
+        }
+        if target_pos.distance(pos) > ATTACK_RANGE * 0.75 {
+            bot.move_towards(&mut bot_input, pos, target_pos);
+        }
+    } else {
+        bot.goal = BotGoal::Wander;
+        if night_tracker.is_night() && home.distance(pos) > NIGHT_HOME_RANGE {
+            bot.move_towards(&mut bot_input, pos, home);
+        } else {
+            if bot.wander_timer.finished() {
+                bot.pick_wander_dir();
+            }
+            move_in_direction(&mut bot_input, bot.wander_dir);
+        }
+    }
+
+    // walks off in a new direction for a bit when something is in the way
+    let is_moving = bot_input.held.iter().any(|a| MOVE_ACTIONS.contains(a));
+    if !is_moving || pos.distance(bot.last_pos) > 2. {
+        bot.last_pos = pos;
+        bot.stuck_timer.reset();
+    } else if bot.stuck_timer.finished() {
+        bot.pick_wander_dir();
+        bot.detour = Some(Timer::from_seconds(1., TimerMode::Once));
+        bot.stuck_timer.reset();
+    }
+    let delta = time.delta();
+    match bot
+        .detour
+        .as_mut()
+        .map(|detour| detour.tick(delta).finished())
+    {
+        Some(true) => bot.detour = None,
+        Some(false) => {
+            for action in MOVE_ACTIONS {
+                bot_input.held.remove(&action);
+            }
+            bot_input.held.remove(&InputAction::Attack);
+            move_in_direction(&mut bot_input, bot.wander_dir);
+        }
+        None => {}
+    }
+}
+
+/// Puts lost health down to the closest mob, since hits don't say where they came from
+fn track_damage_taken(
+    mut playtest: ResMut<PlaytestState>,
+    player: Query<(&GlobalTransform, &CurrentHealth), (With<Player>, Changed<CurrentHealth>)>,
+    mobs: Query<(&GlobalTransform, &Mob), Without<Tamed>>,
+    mut last_health: Local<Option<i32>>,
+) {
+    let Ok((player_t, health)) = player.get_single() else {
+        return;
+    };
+    let damage = last_health.unwrap_or(health.0) - health.0;
+    *last_health = Some(health.0);
+    let Some(metrics) = playtest.current.as_mut() else {
+        return;
+    };
+    if damage <= 0 {
+        return;
+    }
+    let pos = player_t.translation().truncate();
+    let source = mobs
+        .iter()
+        .map(|(t, mob)| (t.translation().truncate().distance(pos), mob))
+        .filter(|(dist, _)| *dist <= DAMAGE_SOURCE_RANGE)
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map_or("Other".to_string(), |(_, mob)| mob.to_string());
+    *metrics.damage_taken.entry(source).or_default() += damage;
+}
+
+fn track_food_eaten(
+    mut playtest: ResMut<PlaytestState>,
+    mut use_item_events: EventReader<UseItemEvent>,
+    proto: ProtoParam,
+) {
+    for UseItemEvent(obj) in use_item_events.iter() {
+        let is_food = proto
+            .get_component::<ItemActions, _>(*obj)
+            .map_or(false, |a| {
+                a.actions
+                    .iter()
+                    .any(|action| matches!(action, ItemAction::Eat(_)))
+            });
+        if let (true, Some(metrics)) = (is_food, playtest.current.as_mut()) {
+            metrics.food_eaten += 1;
+        }
+    }
+}
+
+fn end_run_on_game_over(
+    time: Res<Time>,
+    mut playtest: ResMut<PlaytestState>,
+    game_over: EventReader<GameOverEvent>,
+    night_tracker: Res<NightTracker>,
+    level: Query<&PlayerLevel>,
+) {
+    if game_over.is_empty() {
+        return;
+    }
+    let Some(mut metrics) = playtest.current.take() else {
+        return;
+    };
+    metrics.day_of_death = Some(night_tracker.days);
+    let level = level.get_single().map_or(1, |l| l.level);
+    playtest.finish_run(metrics, level, time.elapsed_seconds());
+}
+
+/// Ends runs that make it to `max_days` through the pause menu's quit, without saving
+fn end_run_at_max_days(
+    time: Res<Time>,
+    config: Res<PlaytestConfig>,
+    mut playtest: ResMut<PlaytestState>,
+    mut pause_menu_state: ResMut<PauseMenuState>,
+    night_tracker: Res<NightTracker>,
+    level: Query<&PlayerLevel>,
+) {
+    if night_tracker.days < config.max_days || pause_menu_state.quitting {
+        return;
+    }
+    let Some(mut metrics) = playtest.current.take() else {
+        return;
+    };
+    let level = level.get_single().map_or(1, |l| l.level);
+    playtest.finish_run(metrics, level, time.elapsed_seconds());
+    pause_menu_state.quitting = true;
+    pause_menu_state.skip_save = true;
+}
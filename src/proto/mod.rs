@@ -18,27 +18,32 @@ use bevy_rapier2d::prelude::{Collider, KinematicCharacterController, QueryFilter
 use strum::IntoEnumIterator;
 
 pub mod proto_param;
+pub mod validation;
 use crate::{
-    ai::IdleState,
+    ai::{IdleState, MobBehaviour, MobStateProto, MobStateType, MobTransition, MobTrigger},
     animations::{
         enemy_sprites::{
             CharacterAnimationSpriteSheetData, EnemyAnimationState, LeftFacingSideProfile,
         },
         AnimationFrameTracker, AnimationPosTracker, AnimationTimer, DoneAnimation,
     },
-    assets::{SpriteAnchor, SpriteSize},
+    assets::{GameAssetsPlugin, SpriteAnchor, SpriteSize},
     attributes::{
         Attack, ItemAttributes, ItemRarity, MaxHealth, RawItemBaseAttributes,
         RawItemBonusAttributes,
     },
     enemy::{
-        CombatAlignment, EnemyMaterial, FollowSpeed, LeapAttack, Mob, MobLevel, ProjectileAttack,
+        passive::{AnimalPen, Breedable, Herd, ResourceProducer},
+        raid::RaidTable,
+        spawner::{MobSpawnRule, MobSpawnTable, SpawnTable, SpawnTimeWindow},
+        CombatAlignment, EnemyMaterial, FollowSpeed, Mob, MobLevel,
     },
     inputs::FacingDirection,
     inventory::ItemStack,
     item::{
         item_actions::{ConsumableItem, ItemAction, ItemActions, ManaCost},
         item_upgrades::ClawUpgradeMultiThrow,
+        machine::Machine,
         melee::MeleeAttack,
         object_actions::ObjectAction,
         projectile::{ArcProjectileData, Projectile, ProjectileState, RangedAttack},
@@ -114,8 +119,25 @@ impl Plugin for ProtoPlugin {
             .register_type::<ManaCost>()
             .register_type::<FacingDirection>()
             .register_type::<CraftingContainerType>()
-            .register_type::<LeapAttack>()
-            .register_type::<ProjectileAttack>()
+            .register_type::<MobBehaviour>()
+            .register_type::<MobTransition>()
+            .register_type::<Vec<MobTransition>>()
+            .register_type::<Option<Vec<MobTransition>>>()
+            .register_type::<MobStateProto>()
+            .register_type::<Option<MobStateProto>>()
+            .register_type::<MobStateType>()
+            .register_type::<MobTrigger>()
+            .register_type::<Herd>()
+            .register_type::<Breedable>()
+            .register_type::<ResourceProducer>()
+            .register_type::<AnimalPen>()
+            .register_type::<SpawnTable>()
+            .register_type::<MobSpawnTable>()
+            .register_type::<MobSpawnRule>()
+            .register_type::<Vec<MobSpawnRule>>()
+            .register_type::<RaidTable>()
+            .register_type::<SpawnTimeWindow>()
+            .register_type::<Option<u32>>()
             .register_type::<EssenceOption>()
             .register_type::<Vec<EssenceOption>>()
             .register_type::<EssenceShopChoices>()
@@ -123,6 +145,9 @@ impl Plugin for ProtoPlugin {
             .register_type::<AnimationPosTracker>()
             .register_type::<HashMap<WorldObject, Vec<WorldObject>>>()
             .register_type::<HashMap<WorldObject, f64>>()
+            .register_type::<HashMap<WorldObject, f32>>()
+            .register_type::<Option<f32>>()
+            .register_type::<Machine>()
             .register_type::<HashMap<SchematicType, f64>>()
             .register_type::<Vec<WorldObject>>()
             .register_type::<Vec<u8>>()
@@ -141,6 +166,11 @@ impl Plugin for ProtoPlugin {
                     .after(Self::load_prototypes)
                     .in_set(OnUpdate(GameState::Loading)),
             )
+            .add_system(
+                validation::validate_game_data
+                    .after(GameAssetsPlugin::load_graphics)
+                    .in_schedule(OnExit(GameState::Loading)),
+            )
             .add_system(
                 Self::spawn_proto_resources
                     .in_schedule(OnExit(GameState::Loading))
@@ -150,7 +180,7 @@ impl Plugin for ProtoPlugin {
 }
 
 impl ProtoPlugin {
-    fn load_prototypes(mut prototypes: PrototypesMut) {
+    pub fn load_prototypes(mut prototypes: PrototypesMut) {
         println!("Loading prototypes...");
         //TODO: automate this
         prototypes.load("proto/item_drop.prototype.ron");
@@ -163,6 +193,7 @@ impl ProtoPlugin {
         prototypes.load("proto/mediumgreentree.prototype.ron");
         prototypes.load("proto/WorldGenerationParams.prototype.ron");
         prototypes.load("proto/DungeonWorldGenerationParams.prototype.ron");
+        prototypes.load("proto/SpawnTable.prototype.ron");
         prototypes.load("proto/stonewall.prototype.ron");
         prototypes.load("proto/stonewallblock.prototype.ron");
         prototypes.load("proto/projectile.prototype.ron");
@@ -247,6 +278,11 @@ impl ProtoPlugin {
         prototypes.load("proto/bedblock.prototype.ron");
         prototypes.load("proto/magictusk.prototype.ron");
         prototypes.load("proto/magicgem.prototype.ron");
+        prototypes.load("proto/ruby.prototype.ron");
+        prototypes.load("proto/sapphire.prototype.ron");
+        prototypes.load("proto/emerald.prototype.ron");
+        prototypes.load("proto/topaz.prototype.ron");
+        prototypes.load("proto/chisel.prototype.ron");
         prototypes.load("proto/leather.prototype.ron");
         prototypes.load("proto/rawmeat.prototype.ron");
         prototypes.load("proto/cookedmeat.prototype.ron");
@@ -298,11 +334,17 @@ impl ProtoPlugin {
         prototypes.load("proto/wooddoor.prototype.ron");
         prototypes.load("proto/wooddooropen.prototype.ron");
         prototypes.load("proto/wooddoorblock.prototype.ron");
+        prototypes.load("proto/pen.prototype.ron");
+        prototypes.load("proto/penblock.prototype.ron");
         prototypes.load("proto/essence.prototype.ron");
         prototypes.load("proto/key.prototype.ron");
         prototypes.load("proto/fairy.prototype.ron");
         prototypes.load("proto/miracleseed.prototype.ron");
+        prototypes.load("proto/blueprint.prototype.ron");
+        prototypes.load("proto/golemcore.prototype.ron");
+        prototypes.load("proto/golem.prototype.ron");
         prototypes.load("proto/combatshrine.prototype.ron");
+        prototypes.load("proto/combatshrinedone.prototype.ron");
 
         // Sapplings
         prototypes.load("proto/redsapplingblock.prototype.ron");
@@ -320,33 +362,32 @@ impl ProtoPlugin {
     }
     fn spawn_proto_resources(mut commands: ProtoCommands) {
         commands.apply("WorldGenerationParams");
+        commands.apply("SpawnTable");
+    }
+    /// The first prototype the game needs that has not finished loading, if any
+    pub fn get_unready_prototype(prototypes: &Prototypes) -> Option<String> {
+        let objs = WorldObject::iter()
+            .filter(|obj| {
+                !matches!(
+                    obj,
+                    WorldObject::None
+                        | WorldObject::WaterTile
+                        | WorldObject::GrassTile
+                        | WorldObject::StoneTile
+                )
+            })
+            .map(|obj| <WorldObject as Into<&str>>::into(obj).to_owned());
+        let mobs = Mob::iter()
+            .filter(|mob| mob != &Mob::None)
+            .map(|mob| <Mob as Into<&str>>::into(mob).to_owned());
+        objs.chain(mobs)
+            .chain(std::iter::once("SpawnTable".to_owned()))
+            .find(|p| !prototypes.is_ready(p))
     }
     fn check_proto_ready(prototypes: Prototypes) {
-        for obj in WorldObject::iter() {
-            if obj == WorldObject::None
-                || obj == WorldObject::WaterTile
-                || obj == WorldObject::GrassTile
-                || obj == WorldObject::StoneTile
-            {
-                continue;
-            }
-            let p = <WorldObject as Into<&str>>::into(obj.clone()).to_owned();
-
-            if !prototypes.is_ready(&p) {
-                println!("proto {p:?} not ready");
-                return;
-            }
-        }
-        for mob in Mob::iter() {
-            if mob == Mob::None {
-                continue;
-            }
-            let p = <Mob as Into<&str>>::into(mob.clone()).to_owned();
-
-            if !prototypes.is_ready(&p) {
-                println!("proto {p:?} not ready");
-                return;
-            }
+        if let Some(p) = Self::get_unready_prototype(&prototypes) {
+            println!("proto {p:?} not ready");
+            return;
         }
         println!("READY, ENTERING GAME STATE");
     }
@@ -4,6 +4,7 @@ use core::fmt::Display;
 
 use crate::{
     assets::Graphics,
+    attributes::affixes::AffixPools,
     inventory::ItemStack,
     item::{
         melee::MeleeAttack,
@@ -23,6 +24,7 @@ pub struct ProtoParam<'w, 's> {
     pub meshes: ResMut<'w, Assets<Mesh>>,
     pub graphics: Res<'w, Graphics>,
     pub asset_server: Res<'w, AssetServer>,
+    pub affix_pools: Res<'w, AffixPools>,
 }
 impl<'w, 's> ProtoParam<'w, 's> {
     pub fn get_prototype(&self, id: &str) -> Option<&Prototype> {
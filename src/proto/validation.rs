@@ -0,0 +1,541 @@
+use std::{collections::HashSet, fs};
+
+use bevy::prelude::*;
+use bevy_proto::prelude::Schematic;
+use strum::IntoEnumIterator;
+
+use crate::{
+    assets::Graphics,
+    attributes::{
+        affixes::{AffixPools, AffixStat},
+        gems::Gems,
+        item_abilities::{ItemAbility, ItemAbilityDefs},
+        set_bonuses::EquipmentSets,
+        ItemAttributes, RawItemBonusAttributes,
+    },
+    enemy::{spawner::SpawnTable, Mob},
+    item::{
+        item_actions::{ItemAction, ItemActions},
+        machine::Machine,
+        object_actions::ObjectAction,
+        EquipmentType, Loot, LootTable, RecipeUnlock, Recipes, WorldObject,
+    },
+    proto::proto_param::ProtoParam,
+    schematic::loot_chests::{get_loot_chest_table, LootChestType},
+    ui::crafting_ui::CraftingContainerType,
+    world::WorldGeneration,
+};
+
+/// Collects every broken reference in the game data so they can be reported together,
+/// instead of panicking on the first `get_item_data(...).unwrap()` at runtime.
+#[derive(Default)]
+struct ValidationReport {
+    problems: Vec<String>,
+}
+
+impl ValidationReport {
+    fn add(&mut self, source: &str, problem: String) {
+        self.problems.push(format!("[{source}] {problem}"));
+    }
+    /// Items need an ItemStack in their prototype to be dropped or crafted, and a sprite to be shown
+    fn check_item(
+        &mut self,
+        proto: &ProtoParam,
+        graphics: &Graphics,
+        obj: WorldObject,
+        source: &str,
+    ) {
+        if proto.get_prototype(obj.into()).is_none() {
+            self.add(source, format!("{obj} has no prototype"));
+            return;
+        }
+        if proto.get_item_data(obj).is_none() {
+            self.add(
+                source,
+                format!("{obj} is used as an item but has no ItemStack"),
+            );
+        }
+        if !has_sprite(graphics, obj) {
+            self.add(source, format!("{obj} has no sprite in sprites_desc.ron"));
+        }
+    }
+    /// Prototypes are loaded by exact path, so a file named in a different case fails on Linux
+    fn check_proto_file(&mut self, proto_files: Option<&HashSet<String>>, source: &str) {
+        let Some(proto_files) = proto_files else {
+            return;
+        };
+        if !proto_files.contains(source.trim_start_matches("proto/")) {
+            self.add(
+                source,
+                "no file with this exact name in assets/proto".to_string(),
+            );
+        }
+    }
+    /// Placed objects only need a prototype to be spawned from
+    fn check_object(&mut self, proto: &ProtoParam, obj: WorldObject, source: &str) {
+        if proto.get_prototype(obj.into()).is_none() {
+            self.add(source, format!("{obj} has no prototype"));
+        }
+    }
+    fn check_loot(&mut self, proto: &ProtoParam, graphics: &Graphics, loot: &[Loot], source: &str) {
+        for drop in loot.iter() {
+            self.check_item(proto, graphics, drop.item, source);
+            if drop.min > drop.max {
+                self.add(
+                    source,
+                    format!("{} drops min {} > max {}", drop.item, drop.min, drop.max),
+                );
+            }
+        }
+    }
+}
+
+fn has_sprite(graphics: &Graphics, obj: WorldObject) -> bool {
+    graphics
+        .spritesheet_map
+        .as_ref()
+        .map_or(false, |map| map.contains_key(&obj))
+        || graphics
+            .icons
+            .as_ref()
+            .map_or(false, |map| map.contains_key(&obj))
+}
+
+fn is_tile(obj: WorldObject) -> bool {
+    obj == WorldObject::None
+        || obj == WorldObject::WaterTile
+        || obj == WorldObject::GrassTile
+        || obj == WorldObject::StoneTile
+}
+
+/// Reads a schematic straight from a prototype file, for resource prototypes
+/// whose names do not follow the lowercase item naming
+fn get_schematic_from_file<T: Schematic + Clone>(proto: &ProtoParam, path: &str) -> Option<T> {
+    let handle = proto.prototypes.get(path)?;
+    proto
+        .prototype_assets
+        .get(handle)?
+        .schematics()
+        .get::<T>()?
+        .input()
+        .downcast_ref::<T>()
+        .cloned()
+}
+
+/// The file names in assets/proto, to catch names that only differ in case,
+/// which load on case-insensitive filesystems but not on Linux
+fn get_proto_file_names() -> Option<HashSet<String>> {
+    let dir = fs::read_dir("assets/proto").ok()?;
+    Some(
+        dir.filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect(),
+    )
+}
+
+fn validate_prototypes(report: &mut ValidationReport, proto: &ProtoParam, graphics: &Graphics) {
+    let proto_files = get_proto_file_names();
+    for obj in WorldObject::iter().filter(|obj| !is_tile(*obj)) {
+        let source = format!("proto/{}.prototype.ron", obj.to_string().to_lowercase());
+        report.check_proto_file(proto_files.as_ref(), &source);
+        if proto.get_prototype(obj.into()).is_none() {
+            report.add(&source, format!("missing prototype for WorldObject::{obj}"));
+            continue;
+        }
+        if let Some(proto_obj) = proto.get_component::<WorldObject, _>(obj) {
+            if *proto_obj != obj {
+                report.add(
+                    &source,
+                    format!("WorldObject is {proto_obj}, expected {obj}"),
+                );
+            }
+        }
+        if let Some(stack) = proto.get_item_data(obj) {
+            if stack.obj_type != obj {
+                report.add(
+                    &source,
+                    format!("ItemStack obj_type is {}, expected {obj}", stack.obj_type),
+                );
+            }
+            if !has_sprite(graphics, obj) {
+                report.add(&source, format!("{obj} has no sprite in sprites_desc.ron"));
+            }
+        }
+        if let Some(actions) = proto.get_component::<ItemActions, _>(obj) {
+            for action in actions.actions.iter() {
+                if let ItemAction::PlacesInto(placed) = action {
+                    report.check_object(proto, *placed, &source);
+                }
+            }
+        }
+        if let Some(loot) = proto.get_component::<LootTable, _>(obj) {
+            report.check_loot(proto, graphics, &loot.drops, &source);
+        }
+        if let Some(machine) = proto.get_component::<Machine, _>(obj) {
+            for fuel in machine.fuels.keys() {
+                report.check_item(proto, graphics, *fuel, &source);
+            }
+        }
+    }
+    for mob in Mob::iter().filter(|mob| mob != &Mob::None) {
+        let source = format!("proto/{}.prototype.ron", mob.to_string().to_lowercase());
+        report.check_proto_file(proto_files.as_ref(), &source);
+        if proto.get_prototype(mob.clone().into()).is_none() {
+            report.add(&source, format!("missing prototype for Mob::{mob}"));
+            continue;
+        }
+        if let Some(loot) = proto.get_component::<LootTable, _>(mob.clone()) {
+            report.check_loot(proto, graphics, &loot.drops, &source);
+        }
+    }
+}
+
+fn validate_recipes(
+    report: &mut ValidationReport,
+    proto: &ProtoParam,
+    graphics: &Graphics,
+    recipes: &Recipes,
+) {
+    let source = "recipes/recipes.ron";
+    let stations: Vec<CraftingContainerType> = WorldObject::iter()
+        .filter(|obj| !is_tile(*obj) && proto.get_prototype((*obj).into()).is_some())
+        .filter_map(|obj| match proto.get_component::<ObjectAction, _>(obj) {
+            Some(ObjectAction::Crafting(station)) => Some(station.clone()),
+            _ => None,
+        })
+        .collect();
+    for (result, (ingredients, station, yield_count)) in recipes.crafting_list.iter() {
+        report.check_item(proto, graphics, *result, source);
+        for ingredient in ingredients.iter() {
+            report.check_item(proto, graphics, ingredient.item, source);
+        }
+        if *yield_count == 0 {
+            report.add(source, format!("recipe for {result} yields nothing"));
+        }
+        if *station != CraftingContainerType::Inventory && !stations.contains(station) {
+            report.add(
+                source,
+                format!("recipe for {result} needs a {station:?} but no object opens one"),
+            );
+        }
+    }
+    for recipe in recipes.machine_list.iter() {
+        if proto.get_component::<Machine, _>(recipe.station).is_none() {
+            report.add(
+                source,
+                format!(
+                    "machine recipe for {} uses {} which has no Machine",
+                    recipe.output.item, recipe.station
+                ),
+            );
+        }
+        for input in recipe.inputs.iter() {
+            report.check_item(proto, graphics, input.item, source);
+        }
+        report.check_item(proto, graphics, recipe.output.item, source);
+        report.check_loot(proto, graphics, &recipe.byproducts, source);
+    }
+    for item in recipes.upgradeable_items.iter() {
+        report.check_item(proto, graphics, *item, source);
+    }
+    for (result, unlock) in recipes.unlocks.iter() {
+        if !recipes.crafting_list.contains_key(result) {
+            report.add(
+                source,
+                format!("unlock rule for {result} which has no recipe"),
+            );
+        }
+        if let RecipeUnlock::KeyIngredient(key) = unlock {
+            report.check_item(proto, graphics, *key, source);
+        }
+    }
+    for (item, material) in recipes.repair_materials.iter() {
+        report.check_item(proto, graphics, *item, source);
+        report.check_item(proto, graphics, material.item, source);
+        if proto
+            .get_item_data(*item)
+            .map_or(false, |stack| !stack.attributes.has_durability())
+        {
+            report.add(
+                source,
+                format!("repair material for {item} which has no durability"),
+            );
+        }
+    }
+}
+
+fn validate_spawns_and_world_gen(
+    report: &mut ValidationReport,
+    proto: &ProtoParam,
+    graphics: &Graphics,
+) {
+    for chest_type in [
+        LootChestType::Common,
+        LootChestType::Uncommon,
+        LootChestType::Rare,
+        LootChestType::Food,
+    ] {
+        let source = format!("{chest_type:?} loot chest");
+        report.check_loot(
+            proto,
+            graphics,
+            &get_loot_chest_table(&chest_type).drops,
+            &source,
+        );
+    }
+
+    let source = "proto/SpawnTable.prototype.ron";
+    match get_schematic_from_file::<SpawnTable>(proto, source) {
+        Some(spawn_table) => {
+            report.check_loot(proto, graphics, &spawn_table.raid.rewards, source);
+            for target in spawn_table.raid.target_objects.iter() {
+                report.check_object(proto, *target, source);
+            }
+        }
+        None => report.add(source, "could not read the SpawnTable".to_string()),
+    }
+
+    for source in [
+        "proto/WorldGenerationParams.prototype.ron",
+        "proto/DungeonWorldGenerationParams.prototype.ron",
+    ] {
+        let Some(world_gen) = get_schematic_from_file::<WorldGeneration>(proto, source) else {
+            report.add(source, "could not read the WorldGeneration".to_string());
+            continue;
+        };
+        for obj in world_gen.object_generation_frequencies.keys() {
+            report.check_object(proto, *obj, source);
+        }
+        for (obj, tiles) in world_gen.obj_allowed_tiles_map.iter() {
+            report.check_object(proto, *obj, source);
+            for tile in tiles.iter().filter(|tile| !is_tile(**tile)) {
+                report.add(
+                    source,
+                    format!("{obj} is allowed on {tile}, which is not a tile"),
+                );
+            }
+        }
+    }
+}
+
+fn validate_affixes(
+    report: &mut ValidationReport,
+    affix_pools: &AffixPools,
+    ability_defs: &ItemAbilityDefs,
+) {
+    let source = "items/affixes.ron";
+    for family in affix_pools
+        .prefixes
+        .iter()
+        .chain(affix_pools.suffixes.iter())
+    {
+        if let AffixStat::Attribute(attribute) = &family.stat {
+            if !RawItemBonusAttributes::add_to_item_attributes(
+                attribute,
+                0,
+                &mut ItemAttributes::default(),
+            ) {
+                report.add(
+                    source,
+                    format!("affix for unknown bonus attribute {attribute}"),
+                );
+            }
+        }
+        if let AffixStat::Ability(ability) = &family.stat {
+            if !ability_defs.0.contains_key(ability) {
+                report.add(
+                    source,
+                    format!("affix for ability {ability} which is not in abilities.ron"),
+                );
+            }
+        }
+        if family.tiers.is_empty() {
+            report.add(source, format!("{:?} affix has no tiers", family.stat));
+        }
+        if family.item_types.is_empty() {
+            report.add(source, format!("{:?} affix has no item types", family.stat));
+        }
+        for tier in family.tiers.iter() {
+            if tier.value.is_empty() {
+                report.add(source, format!("{} has an empty value range", tier.name));
+            }
+        }
+        for tiers in family.tiers.windows(2) {
+            if tiers[0].min_level > tiers[1].min_level {
+                report.add(
+                    source,
+                    format!(
+                        "{} needs a higher level than {}",
+                        tiers[0].name, tiers[1].name
+                    ),
+                );
+            }
+        }
+    }
+}
+
+fn validate_item_abilities(
+    report: &mut ValidationReport,
+    proto: &ProtoParam,
+    graphics: &Graphics,
+    ability_defs: &ItemAbilityDefs,
+) {
+    let source = "items/abilities.ron";
+    for ability in ItemAbility::iter() {
+        if !ability_defs.0.contains_key(ability.get_name()) {
+            report.add(source, format!("missing ability {}", ability.get_name()));
+        }
+    }
+    for (name, def) in ability_defs.0.iter() {
+        if ItemAbility::from_name(name, 0).is_none() {
+            report.add(source, format!("unknown ability {name}"));
+        }
+        if def.proc_chance == 0 || def.proc_chance > 100 {
+            report.add(source, format!("{name} has a proc chance outside 1-100"));
+        }
+        if let Some(mob) = &def.summon {
+            if proto.get_prototype(&mob.to_string()).is_none() {
+                report.add(
+                    source,
+                    format!("{name} summons {mob} which has no prototype"),
+                );
+            }
+        }
+        if !has_sprite(graphics, def.icon) {
+            report.add(
+                source,
+                format!("{name} has icon {} with no sprite", def.icon),
+            );
+        }
+    }
+}
+
+fn validate_equipment_sets(
+    report: &mut ValidationReport,
+    proto: &ProtoParam,
+    graphics: &Graphics,
+    equipment_sets: &EquipmentSets,
+) {
+    let source = "items/sets.ron";
+    for set in equipment_sets.0.iter() {
+        for piece in set.pieces.iter() {
+            report.check_item(proto, graphics, *piece, source);
+            if proto.get_component::<EquipmentType, _>(*piece).is_none() {
+                report.add(
+                    source,
+                    format!("{} set piece {piece} is not equipment", set.name),
+                );
+            }
+        }
+        for bonus in set.bonuses.iter() {
+            if bonus.pieces == 0 || bonus.pieces > set.pieces.len() {
+                report.add(
+                    source,
+                    format!(
+                        "{} set has a {} piece bonus but {} pieces",
+                        set.name,
+                        bonus.pieces,
+                        set.pieces.len()
+                    ),
+                );
+            }
+            for (attribute, _) in bonus.attributes.iter() {
+                if !RawItemBonusAttributes::add_to_item_attributes(
+                    attribute,
+                    0,
+                    &mut ItemAttributes::default(),
+                ) {
+                    report.add(
+                        source,
+                        format!("{} set bonus for unknown attribute {attribute}", set.name),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn validate_gems(
+    report: &mut ValidationReport,
+    proto: &ProtoParam,
+    graphics: &Graphics,
+    gems: &Gems,
+    ability_defs: &ItemAbilityDefs,
+) {
+    let source = "items/gems.ron";
+    let station_fuels = proto
+        .get_component::<Machine, _>(WorldObject::UpgradeStation)
+        .map(|machine| machine.fuels.clone())
+        .unwrap_or_default();
+    for (gem_obj, gem) in gems.0.iter() {
+        report.check_item(proto, graphics, *gem_obj, source);
+        if !station_fuels.contains_key(gem_obj) {
+            report.add(
+                source,
+                format!("{gem_obj} is not a fuel of the UpgradeStation, so it can't be socketed"),
+            );
+        }
+        for (attribute, _) in gem.attributes.iter() {
+            if !RawItemBonusAttributes::add_to_item_attributes(
+                attribute,
+                0,
+                &mut ItemAttributes::default(),
+            ) {
+                report.add(
+                    source,
+                    format!("{gem_obj} adds unknown attribute {attribute}"),
+                );
+            }
+        }
+        if let Some(ability) = &gem.ability {
+            if !ability_defs.0.contains_key(ability.get_name()) {
+                report.add(
+                    source,
+                    format!(
+                        "{gem_obj} adds {} which is not in abilities.ron",
+                        ability.get_name()
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// Runs once all data is loaded, and logs every missing prototype, sprite or
+/// dangling item reference in one report before the main menu shows.
+/// Debug builds stop here if anything is broken, so it can't be missed.
+pub fn validate_game_data(
+    proto: ProtoParam,
+    graphics: Res<Graphics>,
+    recipes: Res<Recipes>,
+    ability_defs: Res<ItemAbilityDefs>,
+    equipment_sets: Res<EquipmentSets>,
+    gems: Res<Gems>,
+) {
+    let mut report = ValidationReport::default();
+    validate_prototypes(&mut report, &proto, &graphics);
+    validate_recipes(&mut report, &proto, &graphics, &recipes);
+    validate_spawns_and_world_gen(&mut report, &proto, &graphics);
+    validate_affixes(&mut report, &proto.affix_pools, &ability_defs);
+    validate_item_abilities(&mut report, &proto, &graphics, &ability_defs);
+    validate_equipment_sets(&mut report, &proto, &graphics, &equipment_sets);
+    validate_gems(&mut report, &proto, &graphics, &gems, &ability_defs);
+
+    report.problems.sort();
+    report.problems.dedup();
+    if report.problems.is_empty() {
+        info!("Game data validation passed");
+        return;
+    }
+    for problem in report.problems.iter() {
+        error!("{problem}");
+    }
+    let summary = format!(
+        "Game data validation found {} problem(s)",
+        report.problems.len()
+    );
+    if cfg!(debug_assertions) {
+        panic!("{summary}, see the errors above");
+    }
+    error!("{summary}");
+}
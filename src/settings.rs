@@ -0,0 +1,217 @@
+use std::fs;
+
+use bevy::{
+    prelude::*,
+    render::render_resource::Extent3d,
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::BGMPicker, GameUpscale, GameViewport, RenderTargetImages, TextureTarget, GAME_HEIGHT,
+    GAME_WIDTH, HEIGHT, WIDTH,
+};
+
+/// Where the player's settings are saved, next to their controls
+const SETTINGS_PATH: &str = "settings.ron";
+
+/// Window sizes the settings screen cycles through, all 16:9 like the game
+pub const RESOLUTIONS: [(u32, u32); 5] = [
+    (1280, 720),
+    (1600, 900),
+    (1920, 1080),
+    (2560, 1440),
+    (WIDTH as u32, HEIGHT as u32),
+];
+
+/// The most an expanded view shows, past this the window is letterboxed.
+/// Kept within the range objects stay visible around the camera.
+const MAX_VIEW_SIZE: Vec2 = Vec2::new(432., 240.);
+
+/// Video, audio and gameplay options, loaded from and saved to settings.ron
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub resolution: (u32, u32),
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// Only scale the game by whole multiples of its pixel size, letterboxing the rest
+    pub integer_scaling: bool,
+    /// Show more of the world on windows wider or taller than 16:9, instead of letterboxing
+    pub expand_view: bool,
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub screen_shake: bool,
+    pub damage_numbers: bool,
+    /// Snap right stick aim to the nearest hostile in front of the player
+    pub aim_assist: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            resolution: (WIDTH as u32, HEIGHT as u32),
+            fullscreen: false,
+            vsync: true,
+            integer_scaling: false,
+            expand_view: false,
+            master_volume: 1.,
+            music_volume: 1.,
+            sfx_volume: 1.,
+            screen_shake: true,
+            damage_numbers: true,
+            aim_assist: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Falls back to the defaults if there is no saved file
+    pub fn load() -> Self {
+        let Ok(settings_desc) = fs::read_to_string(SETTINGS_PATH) else {
+            return Self::default();
+        };
+        ron::de::from_str(&settings_desc).unwrap_or_else(|e| {
+            println!("Failed to load settings, using the defaults: {e}");
+            Self::default()
+        })
+    }
+    pub fn save(&self) {
+        let settings_desc = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .expect("Could not serialize settings");
+        if let Err(e) = fs::write(SETTINGS_PATH, settings_desc) {
+            println!("Failed to save settings: {e}");
+        }
+    }
+    pub fn get_music_volume(&self) -> f32 {
+        self.master_volume * self.music_volume
+    }
+    pub fn get_sfx_volume(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+    pub fn get_present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+    pub fn get_window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+    /// How many screen pixels each game pixel takes up in a window of this size
+    pub fn get_game_upscale(&self, window_size: Vec2) -> f32 {
+        let upscale = f32::min(window_size.x / GAME_WIDTH, window_size.y / GAME_HEIGHT);
+        if self.integer_scaling {
+            upscale.floor().max(1.)
+        } else {
+            upscale
+        }
+    }
+    /// How many game pixels fit in the window at this upscale, rounded down to an even
+    /// size so the camera stays centered on a pixel
+    pub fn get_view_size(&self, window_size: Vec2, upscale: f32) -> Vec2 {
+        let base_size = Vec2::new(GAME_WIDTH, GAME_HEIGHT);
+        if !self.expand_view {
+            return base_size;
+        }
+        ((window_size / upscale / 2.).floor() * 2.)
+            .min(MAX_VIEW_SIZE)
+            .max(base_size)
+    }
+}
+
+/// Only resizes the window when the resolution setting itself changes,
+/// so changing other settings keeps the size the window was dragged to
+pub fn apply_window_settings(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut applied_resolution: Local<Option<(u32, u32)>>,
+) {
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+    if *applied_resolution != Some(settings.resolution) {
+        let (width, height) = settings.resolution;
+        window.resolution.set(width as f32, height as f32);
+        *applied_resolution = Some(settings.resolution);
+    }
+    window.mode = settings.get_window_mode();
+    window.present_mode = settings.get_present_mode();
+}
+
+/// Sizes the game and UI render textures to what's on screen, and stretches them over
+/// the window. Keeps `GameUpscale` in sync so the camera's sub-pixel smoothing still lines up.
+pub fn scale_game_to_window(
+    settings: Res<Settings>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    render_images: Res<RenderTargetImages>,
+    mut images: ResMut<Assets<Image>>,
+    mut viewport: ResMut<GameViewport>,
+    mut render_targets: Query<&mut Transform, With<TextureTarget>>,
+    mut upscales: Query<&mut GameUpscale>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+    let upscale = settings.get_game_upscale(window_size);
+    let view_size = settings.get_view_size(window_size, upscale);
+    if viewport.size != view_size {
+        viewport.size = view_size;
+        // the cameras' projections follow their target image's size
+        let image_size = Extent3d {
+            width: view_size.x as u32,
+            height: view_size.y as u32,
+            ..default()
+        };
+        for handle in [&render_images.game, &render_images.ui] {
+            if let Some(image) = images.get_mut(handle) {
+                image.resize(image_size);
+            }
+        }
+    }
+    let scale = (view_size * upscale).extend(1.);
+    for mut t in render_targets.iter_mut() {
+        if t.scale != scale {
+            t.scale = scale;
+        }
+    }
+    for mut game_upscale in upscales.iter_mut() {
+        if game_upscale.0 != upscale {
+            game_upscale.0 = upscale;
+        }
+    }
+}
+
+pub fn apply_music_volume(
+    settings: Res<Settings>,
+    bgm_tracker: Res<BGMPicker>,
+    audio_sinks: Res<Assets<AudioSink>>,
+) {
+    if let Some(sink) = bgm_tracker
+        .current_handle
+        .as_ref()
+        .and_then(|handle| audio_sinks.get(handle))
+    {
+        sink.set_volume(settings.get_music_volume());
+    }
+}
+
+pub fn damage_numbers_enabled(settings: Res<Settings>) -> bool {
+    settings.damage_numbers
+}
+
+pub struct SettingsPlugin;
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(apply_window_settings.run_if(resource_changed::<Settings>()))
+            .add_system(apply_music_volume.run_if(resource_changed::<Settings>()))
+            .add_system(scale_game_to_window.run_if(resource_exists::<RenderTargetImages>()));
+    }
+}
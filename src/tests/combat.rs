@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+
+use crate::{
+    attributes::CurrentHealth,
+    combat::{handle_hits, handle_invincibility_frames, HitEvent, InvincibilityTimer},
+    item::WorldObject,
+};
+
+use super::harness::{hit, TestApp};
+
+#[test]
+fn hits_are_ignored_during_invincibility_frames() {
+    let mut app = TestApp::new();
+    app.add_systems((handle_hits, handle_invincibility_frames.after(handle_hits)));
+    let player = app.spawn_player();
+
+    app.send(hit(player, 10));
+    app.step();
+    assert_eq!(app.get::<CurrentHealth>(player).0, 90);
+    assert!(app.has::<InvincibilityTimer>(player));
+
+    app.send(hit(player, 10));
+    app.step();
+    assert_eq!(app.get::<CurrentHealth>(player).0, 90);
+
+    // the player's InvincibilityCooldown is 1 second
+    app.step_for(1.1);
+    assert!(!app.has::<InvincibilityTimer>(player));
+    app.send(hit(player, 10));
+    app.step();
+    assert_eq!(app.get::<CurrentHealth>(player).0, 80);
+}
+
+#[test]
+fn objects_only_take_hits_from_their_required_tool() {
+    let mut app = TestApp::new();
+    app.add_systems(handle_hits);
+    app.spawn_player();
+    let tree = app.spawn_object(WorldObject::SmallGreenTree, Vec2::new(64., 0.));
+    let full_health = app.get::<CurrentHealth>(tree).0;
+
+    for hit_with in [None, Some(WorldObject::WoodSword)] {
+        app.send(HitEvent {
+            hit_with_melee: hit_with,
+            ..hit(tree, 10)
+        });
+        app.step();
+        assert_eq!(app.get::<CurrentHealth>(tree).0, full_health);
+    }
+
+    app.send(HitEvent {
+        hit_with_melee: Some(WorldObject::WoodAxe),
+        ..hit(tree, 10)
+    });
+    app.step();
+    assert_eq!(app.get::<CurrentHealth>(tree).0, full_health - 10);
+}
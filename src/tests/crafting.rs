@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use crate::{
+    container::Container,
+    inventory::{Inventory, InventoryItemStack, ItemStack, INVENTORY_SIZE},
+    item::{handle_crafted_item, CraftedItemEvent, WorldObject, CHEST_PULL_RADIUS},
+    ui::ChestContainer,
+};
+
+use super::harness::TestApp;
+
+fn container_with(size: usize, stacks: &[(WorldObject, usize)]) -> Container {
+    let mut container = Container::with_size(size);
+    for (slot, (obj, count)) in stacks.iter().enumerate() {
+        let stack = ItemStack::crate_icon_stack(*obj).copy_with_count(*count);
+        container.items[slot] = Some(InventoryItemStack::new(stack, slot));
+    }
+    container
+}
+
+fn spawn_chest(app: &mut TestApp, pos: Vec2, stacks: &[(WorldObject, usize)]) -> Entity {
+    let chest = app
+        .app
+        .world
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(pos.extend(0.)),
+        ))
+        .id();
+    app.app.world.entity_mut(chest).insert(ChestContainer {
+        items: container_with(INVENTORY_SIZE, stacks),
+        parent: chest,
+    });
+    chest
+}
+
+/// A crafting app with the real recipes, and a player holding `stacks`
+fn setup(stacks: &[(WorldObject, usize)]) -> (TestApp, Entity) {
+    let mut app = TestApp::without_prototypes();
+    app.load_recipes();
+    app.add_systems(handle_crafted_item);
+    let player = app.spawn_player();
+    app.get_mut::<Inventory>(player).items = container_with(INVENTORY_SIZE, stacks);
+    (app, player)
+}
+
+#[test]
+fn crafting_takes_ingredients_from_the_inventory_before_nearby_chests() {
+    // a Crafting Table takes a Wood Plank, a Log and a Stick
+    let (mut app, player) = setup(&[(WorldObject::Log, 1)]);
+    let chest = spawn_chest(
+        &mut app,
+        Vec2::new(32., 0.),
+        &[
+            (WorldObject::WoodPlank, 2),
+            (WorldObject::Log, 1),
+            (WorldObject::Stick, 1),
+        ],
+    );
+    // transforms are propagated at the end of the first step
+    app.step();
+
+    app.send(CraftedItemEvent {
+        obj: WorldObject::CraftingTableBlock,
+    });
+    app.step();
+
+    let inv = &app.get::<Inventory>(player).items;
+    assert_eq!(inv.get_item_count_in_container(WorldObject::Log), 0);
+    let chest_items = &app.get::<ChestContainer>(chest).items;
+    assert_eq!(
+        chest_items.get_item_count_in_container(WorldObject::WoodPlank),
+        1
+    );
+    assert_eq!(chest_items.get_item_count_in_container(WorldObject::Log), 1);
+    assert_eq!(
+        chest_items.get_item_count_in_container(WorldObject::Stick),
+        0
+    );
+}
+
+#[test]
+fn crafting_ignores_chests_out_of_range() {
+    let (mut app, player) = setup(&[(WorldObject::Log, 2)]);
+    let chest = spawn_chest(
+        &mut app,
+        Vec2::new(CHEST_PULL_RADIUS + 16., 0.),
+        &[(WorldObject::Log, 2)],
+    );
+    app.step();
+
+    // a Wood Plank takes a single Log
+    app.send(CraftedItemEvent {
+        obj: WorldObject::WoodPlank,
+    });
+    app.step();
+
+    let inv = &app.get::<Inventory>(player).items;
+    assert_eq!(inv.get_item_count_in_container(WorldObject::Log), 1);
+    let chest_items = &app.get::<ChestContainer>(chest).items;
+    assert_eq!(chest_items.get_item_count_in_container(WorldObject::Log), 2);
+}
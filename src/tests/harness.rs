@@ -0,0 +1,250 @@
+use std::{
+    fmt::Display,
+    fs, thread,
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    ecs::{event::Event, system::SystemState},
+    prelude::*,
+    time::TimeUpdateStrategy,
+    utils::HashMap,
+};
+use bevy_proto::prelude::{ProtoCommands, Prototypes, PrototypesMut, Schematic};
+use ron::de::from_str;
+
+use crate::{
+    assets::Graphics,
+    attributes::{
+        affixes::AffixPools, gems::Gems, CurrentHealth, InvincibilityCooldown, LootRateBonus,
+        MaxHealth,
+    },
+    combat::{EnemyDeathEvent, HitEvent, ObjBreakEvent},
+    container::{Container, ContainerRegistry},
+    custom_commands::CommandsExt,
+    enemy::{EnemyMaterial, Mob, MobLevel},
+    inventory::{Inventory, INVENTORY_SIZE},
+    item::{
+        combat_shrine::CombatShrineMobDeathEvent, durability::RepairItemEvent, CraftRequestEvent,
+        CraftedItemEvent, CraftingTracker, Foliage, PlaceItemEvent, RecipeListProto, Recipes,
+        WorldObject, WorldObjectResource,
+    },
+    player::{levels::PlayerLevel, stats::SkillPoints, Player},
+    proto::ProtoPlugin,
+    ui::{minimap::UpdateMiniMapEvent, DropOnSlotEvent, UIState},
+    world::{
+        chunk::{Chunk, TileEntityCollection},
+        generation::WorldObjectCache,
+        WorldGeneration,
+    },
+    Game, GameState, TIME_STEP,
+};
+
+/// Prototypes are loaded from disk like in the game, this is how long they get
+const PROTOTYPE_LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A headless `App` with the game's resources, events and prototypes, but no rendering,
+/// audio or world generation. Tests add the systems they cover, send events, step the
+/// schedule and check the results.
+pub struct TestApp {
+    pub app: App,
+}
+
+impl TestApp {
+    /// With every prototype loaded, ready to spawn from
+    pub fn new() -> Self {
+        let mut test_app = Self::without_prototypes();
+        test_app.load_prototypes();
+        test_app
+    }
+    /// For rules that don't need prototypes, skipping the wait for them to load
+    pub fn without_prototypes() -> Self {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_asset::<Image>()
+            .add_asset::<Mesh>()
+            .add_asset::<TextureAtlas>()
+            .add_asset::<EnemyMaterial>()
+            .add_state::<GameState>()
+            .add_state::<UIState>()
+            // registered with the saveable types in the game
+            .register_type::<Foliage>()
+            .add_plugin(ProtoPlugin)
+            // every update is one fixed step, so timers run the same on any machine
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                TIME_STEP,
+            )))
+            .init_resource::<Graphics>()
+            .insert_resource(WorldObjectResource {
+                properties: HashMap::new(),
+            })
+            .init_resource::<Game>()
+            .init_resource::<WorldGeneration>()
+            .init_resource::<WorldObjectCache>()
+            .init_resource::<ContainerRegistry>()
+            .init_resource::<AffixPools>()
+            .init_resource::<Recipes>()
+            .init_resource::<CraftingTracker>()
+            .init_resource::<Gems>()
+            .add_event::<HitEvent>()
+            .add_event::<EnemyDeathEvent>()
+            .add_event::<ObjBreakEvent>()
+            .add_event::<CombatShrineMobDeathEvent>()
+            .add_event::<PlaceItemEvent>()
+            .add_event::<UpdateMiniMapEvent>()
+            .add_event::<DropOnSlotEvent>()
+            .add_event::<CraftedItemEvent>()
+            .add_event::<CraftRequestEvent>()
+            .add_event::<RepairItemEvent>();
+        // skips the loading screen, its systems need the real textures and data files
+        app.world.insert_resource(State(GameState::Main));
+        Self { app }
+    }
+
+    fn load_prototypes(&mut self) {
+        let mut prototypes = SystemState::<PrototypesMut>::new(&mut self.app.world);
+        ProtoPlugin::load_prototypes(prototypes.get_mut(&mut self.app.world));
+
+        let started = Instant::now();
+        let mut prototypes = SystemState::<Prototypes>::new(&mut self.app.world);
+        loop {
+            self.app.update();
+            let Some(unready) =
+                ProtoPlugin::get_unready_prototype(&prototypes.get_mut(&mut self.app.world))
+            else {
+                return;
+            };
+            assert!(
+                started.elapsed() < PROTOTYPE_LOAD_TIMEOUT,
+                "prototype {unready:?} did not load"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Replaces the empty `Recipes` with the real ones from `assets/recipes/recipes.ron`
+    pub fn load_recipes(&mut self) {
+        let desc = fs::read_to_string("./assets/recipes/recipes.ron").unwrap();
+        let recipes: RecipeListProto = from_str(&desc).expect("recipes.ron should parse");
+        self.app.world.insert_resource(Recipes::from(recipes));
+    }
+
+    /// Systems run on every step, without the game's states or run conditions
+    pub fn add_systems<M>(&mut self, systems: impl IntoSystemConfigs<M>) -> &mut Self {
+        self.app.add_systems(systems);
+        self
+    }
+    pub fn step(&mut self) {
+        self.app.update();
+    }
+    /// Steps for at least `secs` of game time
+    pub fn step_for(&mut self, secs: f32) {
+        for _ in 0..(secs / TIME_STEP).ceil() as usize {
+            self.step();
+        }
+    }
+    /// Read by the systems on the next step
+    pub fn send<E: Event>(&mut self, event: E) {
+        self.app.world.resource_mut::<Events<E>>().send(event);
+    }
+
+    pub fn get<C: Component>(&self, entity: Entity) -> &C {
+        self.app
+            .world
+            .get::<C>(entity)
+            .unwrap_or_else(|| panic!("{entity:?} has no {}", std::any::type_name::<C>()))
+    }
+    pub fn get_mut<C: Component>(&mut self, entity: Entity) -> Mut<C> {
+        self.app
+            .world
+            .get_mut::<C>(entity)
+            .unwrap_or_else(|| panic!("{entity:?} has no {}", std::any::type_name::<C>()))
+    }
+    pub fn has<C: Component>(&self, entity: Entity) -> bool {
+        self.app.world.get::<C>(entity).is_some()
+    }
+
+    /// A player with just the components the gameplay rules read, set as the `Game`'s player
+    pub fn spawn_player(&mut self) -> Entity {
+        let player = self
+            .app
+            .world
+            .spawn((
+                Player,
+                CurrentHealth(100),
+                MaxHealth(100),
+                InvincibilityCooldown(1.),
+                LootRateBonus(0),
+                PlayerLevel::new(1),
+                SkillPoints::default(),
+                Inventory {
+                    items: Container::with_size(INVENTORY_SIZE),
+                    equipment_items: Container::with_size(4),
+                    accessory_items: Container::with_size(4),
+                    crafting_items: Container::with_size(0),
+                },
+                TransformBundle::default(),
+            ))
+            .id();
+        self.app.world.resource_mut::<Game>().player = player;
+        player
+    }
+    /// Spawns a prototype with `ProtoCommands`, the same way the game does
+    pub fn spawn_from_proto<'a, T: Display + Schematic + Clone + Into<&'a str>>(
+        &mut self,
+        obj: T,
+        pos: Vec2,
+    ) -> Entity {
+        let mut state = SystemState::<(ProtoCommands, Prototypes)>::new(&mut self.app.world);
+        let (mut commands, prototypes) = state.get_mut(&mut self.app.world);
+        let entity = commands
+            .spawn_from_proto(obj.clone(), &prototypes, pos)
+            .unwrap_or_else(|| panic!("prototype {obj} is not loaded"));
+        state.apply(&mut self.app.world);
+        entity
+    }
+    /// An empty chunk for objects to be placed in, without any tiles
+    pub fn spawn_chunk(&mut self, chunk_pos: IVec2) -> Entity {
+        self.app
+            .world
+            .spawn((
+                Chunk { chunk_pos },
+                TileEntityCollection::default(),
+                SpatialBundle::default(),
+            ))
+            .id()
+    }
+    /// A mob at full health, at level 1 like on the first day
+    pub fn spawn_mob(&mut self, mob: Mob, pos: Vec2) -> Entity {
+        let entity = self.spawn_from_proto(mob, pos);
+        let max_health = self.get::<MaxHealth>(entity).0;
+        self.app
+            .world
+            .entity_mut(entity)
+            .insert((CurrentHealth(max_health), MobLevel(1)));
+        entity
+    }
+    /// An object like a tree or a furnace, at full health if it can be broken
+    pub fn spawn_object(&mut self, obj: WorldObject, pos: Vec2) -> Entity {
+        let entity = self.spawn_from_proto(obj, pos);
+        if let Some(max_health) = self.app.world.get::<MaxHealth>(entity) {
+            let health = CurrentHealth(max_health.0);
+            self.app.world.entity_mut(entity).insert(health);
+        }
+        entity
+    }
+}
+
+/// A melee hit with nothing in hand, like the one enemies deal the player
+pub fn hit(entity: Entity, damage: i32) -> HitEvent {
+    HitEvent {
+        hit_entity: entity,
+        damage,
+        dir: Vec2::X,
+        hit_with_melee: None,
+        hit_with_projectile: None,
+    }
+}
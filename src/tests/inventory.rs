@@ -0,0 +1,159 @@
+use bevy::{ecs::system::SystemState, prelude::*};
+
+use crate::{
+    attributes::ItemRarity,
+    container::Container,
+    inventory::{Inventory, InventoryItemStack, ItemStack, INVENTORY_SIZE, MAX_STACK_SIZE},
+    item::WorldObject,
+    ui::{handle_drop_on_slot_events, DropOnSlotEvent, InventorySlotState, InventorySlotType},
+};
+
+use super::harness::TestApp;
+
+fn logs(count: usize) -> ItemStack {
+    ItemStack::crate_icon_stack(WorldObject::Log).copy_with_count(count)
+}
+
+/// Adds each stack to `container` like picking it up would
+fn add_to_inventory(app: &mut TestApp, container: &mut Container, stacks: Vec<ItemStack>) {
+    let mut state = SystemState::<Query<&mut InventorySlotState>>::new(&mut app.app.world);
+    let mut inv_slots = state.get_mut(&mut app.app.world);
+    for stack in stacks {
+        stack.add_to_inventory(container, &mut inv_slots);
+    }
+}
+
+fn get_count(container: &Container, slot: usize) -> Option<usize> {
+    container.items[slot].as_ref().map(|i| i.item_stack.count)
+}
+
+#[test]
+fn added_items_merge_into_a_matching_stack() {
+    let mut app = TestApp::without_prototypes();
+    let mut container = Container::with_size(INVENTORY_SIZE);
+    container.items[3] = Some(InventoryItemStack::new(logs(5), 3));
+
+    add_to_inventory(&mut app, &mut container, vec![logs(10)]);
+
+    assert_eq!(get_count(&container, 3), Some(15));
+    assert_eq!(get_count(&container, 0), None);
+}
+
+#[test]
+fn added_items_past_the_stack_size_overflow_into_an_empty_slot() {
+    let mut app = TestApp::without_prototypes();
+    let mut container = Container::with_size(INVENTORY_SIZE);
+    container.items[0] = Some(InventoryItemStack::new(logs(MAX_STACK_SIZE), 0));
+    container.items[3] = Some(InventoryItemStack::new(logs(MAX_STACK_SIZE - 4), 3));
+
+    add_to_inventory(&mut app, &mut container, vec![logs(10)]);
+
+    // full stacks are skipped, the rest goes to the first empty slot
+    assert_eq!(get_count(&container, 0), Some(MAX_STACK_SIZE));
+    assert_eq!(get_count(&container, 3), Some(MAX_STACK_SIZE));
+    assert_eq!(get_count(&container, 1), Some(6));
+}
+
+#[test]
+fn added_items_only_merge_with_the_same_rarity() {
+    let mut app = TestApp::without_prototypes();
+    let mut container = Container::with_size(INVENTORY_SIZE);
+    container.items[0] = Some(InventoryItemStack::new(logs(5), 0));
+    let mut rare_logs = logs(5);
+    rare_logs.rarity = ItemRarity::Rare;
+
+    add_to_inventory(&mut app, &mut container, vec![rare_logs]);
+
+    assert_eq!(get_count(&container, 0), Some(5));
+    assert_eq!(get_count(&container, 1), Some(5));
+}
+
+#[test]
+fn added_items_mark_their_slot_for_redrawing() {
+    let mut app = TestApp::without_prototypes();
+    let slot = app
+        .app
+        .world
+        .spawn(InventorySlotState {
+            slot_index: 2,
+            item: None,
+            count: None,
+            obj_type: None,
+            r#type: InventorySlotType::Normal,
+            dirty: false,
+        })
+        .id();
+    let mut container = Container::with_size(INVENTORY_SIZE);
+    container.items[2] = Some(InventoryItemStack::new(logs(1), 2));
+
+    add_to_inventory(&mut app, &mut container, vec![logs(1)]);
+
+    assert!(app.get::<InventorySlotState>(slot).dirty);
+}
+
+/// Drops a dragged stack of `stack` on a slot, letting go of all of it
+fn drop_on_slot(
+    app: &mut TestApp,
+    stack: ItemStack,
+    slot_index: usize,
+    slot_type: InventorySlotType,
+) -> Entity {
+    let dragged = app.app.world.spawn(stack.clone()).id();
+    let parent_slot = app.app.world.spawn_empty().id();
+    app.send(DropOnSlotEvent {
+        dropped_entity: dragged,
+        dropped_item_stack: stack,
+        drop_target_slot_state: InventorySlotState {
+            slot_index,
+            item: None,
+            count: None,
+            obj_type: None,
+            r#type: slot_type,
+            dirty: false,
+        },
+        parent_interactable_entity: parent_slot,
+        stack_empty: true,
+    });
+    app.step();
+    dragged
+}
+
+#[test]
+fn dropping_on_an_empty_slot_moves_the_stack_there() {
+    let mut app = TestApp::without_prototypes();
+    app.add_systems(handle_drop_on_slot_events);
+    let player = app.spawn_player();
+
+    let dragged = drop_on_slot(&mut app, logs(5), 2, InventorySlotType::Normal);
+
+    assert_eq!(get_count(&app.get::<Inventory>(player).items, 2), Some(5));
+    assert!(app.app.world.get_entity(dragged).is_none());
+}
+
+#[test]
+fn dropping_on_a_matching_stack_merges_them() {
+    let mut app = TestApp::without_prototypes();
+    app.add_systems(handle_drop_on_slot_events);
+    let player = app.spawn_player();
+    app.get_mut::<Inventory>(player).items.items[2] = Some(InventoryItemStack::new(logs(5), 2));
+
+    drop_on_slot(&mut app, logs(10), 2, InventorySlotType::Normal);
+
+    assert_eq!(get_count(&app.get::<Inventory>(player).items, 2), Some(15));
+}
+
+#[test]
+fn items_that_are_not_equipment_cannot_be_dropped_on_equipment_slots() {
+    let mut app = TestApp::without_prototypes();
+    app.add_systems(handle_drop_on_slot_events);
+    let player = app.spawn_player();
+
+    let dragged = drop_on_slot(&mut app, logs(5), 0, InventorySlotType::Equipment);
+
+    assert_eq!(
+        get_count(&app.get::<Inventory>(player).equipment_items, 0),
+        None
+    );
+    // still held by the cursor
+    assert!(app.app.world.get_entity(dragged).is_some());
+}
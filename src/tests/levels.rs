@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+use crate::{
+    combat::{handle_enemy_death, handle_hits},
+    enemy::Mob,
+    player::{
+        levels::{handle_level_up, ExperienceReward, PlayerLevel},
+        stats::SkillPoints,
+    },
+};
+
+use super::harness::{hit, TestApp};
+
+#[test]
+fn xp_carries_over_into_the_next_level() {
+    let mut level = PlayerLevel::new(1);
+    assert_eq!(level.next_level_xp, 200);
+
+    level.add_xp(150);
+    assert_eq!((level.level, level.xp), (1, 150));
+
+    level.add_xp(100);
+    assert_eq!((level.level, level.xp), (2, 50));
+    // each level needs half the base requirement more than the last
+    assert_eq!(level.next_level_xp, 300);
+}
+
+#[test]
+fn killing_a_mob_levels_up_the_player_and_gives_a_skill_point() {
+    let mut app = TestApp::new();
+    app.add_systems((
+        handle_hits,
+        handle_enemy_death.after(handle_hits),
+        handle_level_up.after(handle_enemy_death),
+    ));
+    let player = app.spawn_player();
+    let slime = app.spawn_mob(Mob::Slime, Vec2::new(64., 0.));
+    let xp = app.get::<ExperienceReward>(slime).0;
+    let next_level_xp = app.get::<PlayerLevel>(player).next_level_xp;
+    assert!(xp >= next_level_xp, "a slime should be worth a level");
+
+    app.send(hit(slime, 100_000));
+    app.step();
+
+    let level = app.get::<PlayerLevel>(player);
+    assert_eq!((level.level, level.next_level), (2, 3));
+    assert_eq!(level.xp, xp - next_level_xp);
+    assert_eq!(app.get::<SkillPoints>(player).count, 1);
+}
@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+
+use crate::{
+    attributes::LootRateBonus,
+    combat::{handle_enemy_death, handle_hits},
+    enemy::Mob,
+    inventory::ItemStack,
+    item::LootTable,
+};
+
+use super::harness::{hit, TestApp};
+
+#[test]
+fn killed_mobs_drop_from_their_loot_table() {
+    let mut app = TestApp::new();
+    app.add_systems((handle_hits, handle_enemy_death.after(handle_hits)));
+    let player = app.spawn_player();
+    // large enough to push every drop's rate past 100%
+    app.get_mut::<LootRateBonus>(player).0 = 100_000;
+    let slime = app.spawn_mob(Mob::Slime, Vec2::new(64., 0.));
+    let loot_table = app.get::<LootTable>(slime).clone();
+
+    app.send(hit(slime, 100_000));
+    app.step();
+
+    let mut drops = app.app.world.query::<&ItemStack>();
+    let drops: Vec<ItemStack> = drops.iter(&app.app.world).cloned().collect();
+    assert_eq!(drops.len(), loot_table.drops.len());
+    for loot in loot_table.drops.iter() {
+        let drop = drops
+            .iter()
+            .find(|drop| drop.obj_type == loot.item)
+            .unwrap_or_else(|| panic!("{:?} was not dropped", loot.item));
+        assert!((loot.min..=loot.max).contains(&drop.count));
+    }
+}
+
+#[test]
+fn mobs_drop_nothing_while_alive() {
+    let mut app = TestApp::new();
+    app.add_systems((handle_hits, handle_enemy_death.after(handle_hits)));
+    let player = app.spawn_player();
+    app.get_mut::<LootRateBonus>(player).0 = 100_000;
+    let slime = app.spawn_mob(Mob::Slime, Vec2::new(64., 0.));
+
+    app.send(hit(slime, 1));
+    app.step();
+
+    let mut drops = app.app.world.query::<&ItemStack>();
+    assert_eq!(drops.iter(&app.app.world).count(), 0);
+}
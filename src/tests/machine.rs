@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+
+use crate::{
+    inventory::{InventoryItemStack, ItemStack},
+    item::{machine::handle_machine_processing, WorldObject},
+    ui::{add_container_to_new_furnace_objs, FurnaceContainer},
+};
+
+use super::harness::TestApp;
+
+/// A furnace with its container set up, smelting with the real recipes
+fn spawn_furnace(app: &mut TestApp) -> Entity {
+    app.load_recipes();
+    app.add_systems((
+        add_container_to_new_furnace_objs,
+        handle_machine_processing.after(add_container_to_new_furnace_objs),
+    ));
+    let furnace = app.spawn_object(WorldObject::Furnace, Vec2::new(64., 0.));
+    app.step();
+    assert!(app.has::<FurnaceContainer>(furnace));
+    furnace
+}
+
+fn put_in_slot(app: &mut TestApp, furnace: Entity, slot: usize, obj: WorldObject, count: usize) {
+    let stack = ItemStack::crate_icon_stack(obj).copy_with_count(count);
+    app.get_mut::<FurnaceContainer>(furnace).items.items[slot] =
+        Some(InventoryItemStack::new(stack, slot));
+}
+
+fn get_slot(app: &TestApp, furnace: Entity, slot: usize) -> Option<(WorldObject, usize)> {
+    app.get::<FurnaceContainer>(furnace).items.items[slot]
+        .as_ref()
+        .map(|i| (i.item_stack.obj_type, i.item_stack.count))
+}
+
+#[test]
+fn furnace_burns_fuel_to_smelt_each_input() {
+    let mut app = TestApp::new();
+    let furnace = spawn_furnace(&mut app);
+    let machine = app.get::<FurnaceContainer>(furnace).machine.clone();
+    let (fuel, input, output) = (
+        machine.fuel_slot().unwrap(),
+        machine.input_range().start,
+        machine.output_range().start,
+    );
+    put_in_slot(&mut app, furnace, fuel, WorldObject::Coal, 1);
+    put_in_slot(&mut app, furnace, input, WorldObject::MetalShard, 2);
+
+    app.step_for(3.1);
+    assert_eq!(get_slot(&app, furnace, fuel), None);
+    assert_eq!(
+        get_slot(&app, furnace, input),
+        Some((WorldObject::MetalShard, 1))
+    );
+    assert_eq!(
+        get_slot(&app, furnace, output),
+        Some((WorldObject::MetalBar, 1))
+    );
+
+    // one Coal burns for long enough to smelt the second shard too
+    app.step_for(3.1);
+    assert_eq!(get_slot(&app, furnace, input), None);
+    assert_eq!(
+        get_slot(&app, furnace, output),
+        Some((WorldObject::MetalBar, 2))
+    );
+}
+
+#[test]
+fn furnace_without_fuel_does_nothing() {
+    let mut app = TestApp::new();
+    let furnace = spawn_furnace(&mut app);
+    let machine = app.get::<FurnaceContainer>(furnace).machine.clone();
+    let input = machine.input_range().start;
+    put_in_slot(&mut app, furnace, input, WorldObject::MetalShard, 2);
+
+    app.step_for(4.);
+    assert_eq!(
+        get_slot(&app, furnace, input),
+        Some((WorldObject::MetalShard, 2))
+    );
+    assert_eq!(get_slot(&app, furnace, machine.output_range().start), None);
+}
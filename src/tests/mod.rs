@@ -0,0 +1,9 @@
+mod harness;
+
+mod combat;
+mod crafting;
+mod inventory;
+mod levels;
+mod loot;
+mod machine;
+mod placing;
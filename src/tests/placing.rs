@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::tiles::TilePos;
+
+use crate::{
+    item::{handle_placing_world_object, PlaceItemEvent, WorldObject},
+    world::{generation::WorldObjectCache, world_helpers::tile_pos_to_world_pos, TileMapPosition},
+};
+
+use super::harness::TestApp;
+
+fn place(obj: WorldObject, tile: TileMapPosition) -> PlaceItemEvent {
+    PlaceItemEvent {
+        obj,
+        pos: tile_pos_to_world_pos(tile, false),
+        placed_by_player: true,
+        override_existing_obj: false,
+    }
+}
+
+fn get_placed(app: &mut TestApp, obj: WorldObject) -> Vec<(Entity, Entity)> {
+    app.app
+        .world
+        .query::<(Entity, &WorldObject, &Parent)>()
+        .iter(&app.app.world)
+        .filter(|(_, placed, _)| **placed == obj)
+        .map(|(e, _, parent)| (e, parent.get()))
+        .collect()
+}
+
+#[test]
+fn placed_objects_spawn_in_their_chunk_and_are_cached() {
+    let mut app = TestApp::new();
+    app.add_systems(handle_placing_world_object);
+    let chunk = app.spawn_chunk(IVec2::ZERO);
+    let tile = TileMapPosition::new(IVec2::ZERO, TilePos { x: 3, y: 3 });
+
+    app.send(place(WorldObject::Chest, tile));
+    app.step();
+
+    let placed = get_placed(&mut app, WorldObject::Chest);
+    assert_eq!(placed.len(), 1);
+    assert_eq!(placed[0].1, chunk);
+    assert_eq!(
+        app.app
+            .world
+            .resource::<WorldObjectCache>()
+            .objects
+            .get(&tile),
+        Some(&WorldObject::Chest)
+    );
+}
+
+#[test]
+fn objects_are_not_placed_on_an_occupied_tile() {
+    let mut app = TestApp::new();
+    app.add_systems(handle_placing_world_object);
+    app.spawn_chunk(IVec2::ZERO);
+    let tile = TileMapPosition::new(IVec2::ZERO, TilePos { x: 3, y: 3 });
+
+    app.send(place(WorldObject::Chest, tile));
+    app.step();
+    app.send(place(WorldObject::Chest, tile));
+    app.step();
+
+    assert_eq!(get_placed(&mut app, WorldObject::Chest).len(), 1);
+}
@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+
+use crate::{assets::Graphics, player::Player, Game};
+
+use super::{UIElement, UIState};
+
+/// How far in front of the player the reticle sits when aim assist hasn't picked a target
+const RETICLE_DISTANCE: f32 = 28.;
+/// Above everything y-sorted, so it's never hidden behind a tree
+const RETICLE_Z: f32 = 950.;
+
+#[derive(Component)]
+pub struct AimReticle;
+
+/// Shows where attacks will go, on the hostile aim assist snapped to,
+/// or in front of the player along their aim
+pub fn update_aim_reticle(
+    mut commands: Commands,
+    graphics: Res<Graphics>,
+    game: Res<Game>,
+    ui_state: Res<State<UIState>>,
+    player: Query<&GlobalTransform, With<Player>>,
+    targets: Query<&GlobalTransform, Without<AimReticle>>,
+    mut reticle: Query<(&mut Transform, &mut Visibility), With<AimReticle>>,
+) {
+    let Ok((mut reticle_t, mut visibility)) = reticle.get_single_mut() else {
+        commands.spawn((
+            SpriteBundle {
+                texture: graphics.get_ui_element_texture(UIElement::AimReticle),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            AimReticle,
+            Name::new("AIM RETICLE"),
+        ));
+        return;
+    };
+    let Ok(player_t) = player.get_single() else {
+        return;
+    };
+    let aim = game.player_state.aim;
+    let target_pos = game
+        .player_state
+        .aim_assist_target
+        .and_then(|e| targets.get(e).ok())
+        .map(|t| t.translation().truncate());
+    let pos =
+        target_pos.unwrap_or_else(|| player_t.translation().truncate() + aim * RETICLE_DISTANCE);
+    reticle_t.translation = pos.round().extend(RETICLE_Z);
+    *visibility = if ui_state.0 == UIState::Closed && aim != Vec2::ZERO {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
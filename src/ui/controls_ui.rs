@@ -0,0 +1,282 @@
+use bevy::prelude::*;
+use strum::IntoEnumIterator;
+
+use crate::{
+    action_map::{ActionInput, ActionMap, InputAction, InputBinding},
+    colors::{BLACK, DARK_BROWN, LIGHT_BROWN, YELLOW_2},
+    inputs::CursorPos,
+};
+
+use super::{
+    ui_helpers::{get_menu_button_at_cursor, spawn_menu_button, spawn_menu_panel, spawn_menu_text},
+    CONTROLS_UI_SIZE,
+};
+
+const CONTROLS_ROWS_PER_COLUMN: usize = 15;
+const CONTROLS_ROW_HEIGHT: f32 = 8.;
+
+/// The controls screen can be opened from any menu, so it tracks itself
+/// instead of being a `UIState`
+#[derive(Resource, Default, Debug)]
+pub struct ControlsMenuState {
+    pub open: bool,
+    /// The action waiting for the player to press its new key
+    pub rebinding: Option<InputAction>,
+    pub message: String,
+    pub dirty: bool,
+}
+
+impl ControlsMenuState {
+    pub fn open(&mut self) {
+        self.open = true;
+        self.rebinding = None;
+        self.message = "Click an action to rebind it".to_string();
+        self.dirty = true;
+    }
+}
+
+#[derive(Component)]
+pub struct ControlsMenuUI;
+#[derive(Component)]
+pub struct ControlsRows;
+
+#[derive(Component, Clone, Debug)]
+pub enum ControlsButton {
+    Rebind(InputAction),
+    ResetDefaults,
+    Back,
+}
+
+pub fn is_controls_menu_closed(controls_state: Res<ControlsMenuState>) -> bool {
+    !controls_state.open
+}
+
+pub fn is_rebinding_controls(controls_state: Res<ControlsMenuState>) -> bool {
+    controls_state.rebinding.is_some()
+}
+
+fn get_bindings_text(action_map: &ActionMap, action: InputAction) -> String {
+    let bindings: Vec<String> = action_map
+        .get_bindings(action)
+        .iter()
+        .map(|b| b.get_name())
+        .collect();
+    if bindings.is_empty() {
+        "-".to_string()
+    } else {
+        bindings.join("/")
+    }
+}
+
+/// Spawns the screen when it opens, rebuilds the rows when a binding changes,
+/// and despawns it when it closes
+pub fn update_controls_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut controls_state: ResMut<ControlsMenuState>,
+    action_map: Res<ActionMap>,
+    menu: Query<Entity, With<ControlsMenuUI>>,
+    rows: Query<Entity, With<ControlsRows>>,
+) {
+    if !controls_state.open {
+        for e in menu.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+    let top = CONTROLS_UI_SIZE.y / 2.;
+    let left = -CONTROLS_UI_SIZE.x / 2.;
+    if menu.get_single().is_err() {
+        let title = spawn_menu_text(
+            &mut commands,
+            &asset_server,
+            "Controls".to_string(),
+            BLACK,
+            Vec3::new(-24., top - 9., 1.),
+        );
+        let reset_label = spawn_menu_text(
+            &mut commands,
+            &asset_server,
+            "Reset".to_string(),
+            BLACK,
+            Vec3::new(-14., 0., 1.),
+        );
+        let reset = spawn_menu_button(
+            &mut commands,
+            Vec2::new(34., 10.),
+            Vec3::new(left + 24., -top + 9., 1.),
+            LIGHT_BROWN,
+            ControlsButton::ResetDefaults,
+            vec![reset_label],
+        );
+        let back_label = spawn_menu_text(
+            &mut commands,
+            &asset_server,
+            "Back".to_string(),
+            BLACK,
+            Vec3::new(-14., 0., 1.),
+        );
+        let back = spawn_menu_button(
+            &mut commands,
+            Vec2::new(34., 10.),
+            Vec3::new(-left - 24., -top + 9., 1.),
+            LIGHT_BROWN,
+            ControlsButton::Back,
+            vec![back_label],
+        );
+        let rows = commands
+            .spawn((
+                SpatialBundle::default(),
+                ControlsRows,
+                Name::new("CONTROLS ROWS"),
+            ))
+            .id();
+        let panel = spawn_menu_panel(&mut commands, CONTROLS_UI_SIZE, 20.);
+        commands
+            .entity(panel)
+            .insert(ControlsMenuUI)
+            .insert(Name::new("CONTROLS UI"))
+            .push_children(&[title, reset, back, rows]);
+        // the rows are filled in next frame, once the screen exists
+        controls_state.dirty = true;
+        return;
+    }
+    if !controls_state.dirty && !action_map.is_changed() {
+        return;
+    }
+    let Ok(rows_e) = rows.get_single() else {
+        return;
+    };
+    controls_state.dirty = false;
+    commands.entity(rows_e).despawn_descendants();
+
+    let column_width = (CONTROLS_UI_SIZE.x - 8.) / 2.;
+    let mut children = vec![spawn_menu_text(
+        &mut commands,
+        &asset_server,
+        controls_state.message.clone(),
+        DARK_BROWN,
+        Vec3::new(left + 6., top - 21., 1.),
+    )];
+    for (i, action) in InputAction::iter().enumerate() {
+        let column = (i / CONTROLS_ROWS_PER_COLUMN) as f32;
+        let row = (i % CONTROLS_ROWS_PER_COLUMN) as f32;
+        let is_rebinding = controls_state.rebinding == Some(action);
+        let name = spawn_menu_text(
+            &mut commands,
+            &asset_server,
+            action.get_name().to_string(),
+            BLACK,
+            Vec3::new(-column_width / 2. + 4., 0., 1.),
+        );
+        let bindings = spawn_menu_text(
+            &mut commands,
+            &asset_server,
+            if is_rebinding {
+                "...".to_string()
+            } else {
+                get_bindings_text(&action_map, action)
+            },
+            BLACK,
+            Vec3::new(-column_width / 2. + 66., 0., 1.),
+        );
+        children.push(spawn_menu_button(
+            &mut commands,
+            Vec2::new(column_width - 4., CONTROLS_ROW_HEIGHT - 2.),
+            Vec3::new(
+                left + 4. + column_width * (column + 0.5),
+                top - 34. - row * CONTROLS_ROW_HEIGHT,
+                1.,
+            ),
+            if is_rebinding { YELLOW_2 } else { LIGHT_BROWN },
+            ControlsButton::Rebind(action),
+            vec![name, bindings],
+        ));
+    }
+    commands.entity(rows_e).push_children(&children);
+}
+
+pub fn handle_controls_menu_buttons(
+    cursor_pos: Res<CursorPos>,
+    mouse_input: Res<Input<MouseButton>>,
+    actions: ActionInput,
+    buttons: Query<(&Sprite, &GlobalTransform, &ControlsButton)>,
+    mut controls_state: ResMut<ControlsMenuState>,
+    mut commands: Commands,
+) {
+    if !controls_state.open || controls_state.rebinding.is_some() {
+        return;
+    }
+    if actions.just_pressed(InputAction::CloseMenu) {
+        controls_state.open = false;
+        return;
+    }
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(button) = get_menu_button_at_cursor(&cursor_pos, &buttons) else {
+        return;
+    };
+    match button {
+        ControlsButton::Rebind(action) => {
+            controls_state.rebinding = Some(*action);
+            controls_state.message =
+                format!("Press a button for {}, Esc cancels", action.get_name());
+        }
+        ControlsButton::ResetDefaults => {
+            let action_map = ActionMap::default();
+            action_map.save();
+            commands.insert_resource(action_map);
+            controls_state.message = "Controls reset to the defaults".to_string();
+        }
+        ControlsButton::Back => {
+            controls_state.open = false;
+        }
+    }
+    controls_state.dirty = true;
+}
+
+/// Runs before the rest of the game reads input, so the key being bound
+/// does not also do whatever it was bound to before
+pub fn handle_rebind_input(
+    mut controls_state: ResMut<ControlsMenuState>,
+    mut action_map: ResMut<ActionMap>,
+    mut key_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    mut gamepad_input: ResMut<Input<GamepadButton>>,
+) {
+    let Some(action) = controls_state.rebinding else {
+        return;
+    };
+    let binding = if key_input.just_pressed(KeyCode::Escape) {
+        None
+    } else if let Some(key) = key_input.get_just_pressed().next() {
+        Some(InputBinding::Key(*key))
+    } else if let Some(button) = mouse_input.get_just_pressed().next() {
+        Some(InputBinding::Mouse(*button))
+    } else if let Some(button) = gamepad_input.get_just_pressed().next() {
+        Some(InputBinding::Gamepad(button.button_type))
+    } else {
+        return;
+    };
+    controls_state.rebinding = None;
+    controls_state.dirty = true;
+    controls_state.message = match binding {
+        None => "Rebinding cancelled".to_string(),
+        Some(binding) => {
+            let conflict = action_map.rebind(action, binding);
+            action_map.save();
+            match conflict {
+                Some(other) => format!(
+                    "{} was used by {}, swapped",
+                    binding.get_name(),
+                    other.get_name()
+                ),
+                None => format!("{} bound to {}", action.get_name(), binding.get_name()),
+            }
+        }
+    };
+    key_input.reset_all();
+    mouse_input.reset_all();
+    gamepad_input.reset_all();
+}
@@ -0,0 +1,60 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+
+use crate::{colors::YELLOW_2, item::CraftQueue};
+
+use super::ScreenAnchor;
+
+#[derive(Component)]
+pub struct CraftQueueText;
+
+pub fn setup_craft_queue_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/Kitchen Sink.ttf"),
+                    font_size: 8.0,
+                    color: YELLOW_2,
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            transform: Transform {
+                translation: Vec3::new(0., 0., 1.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..default()
+        },
+        Name::new("CRAFT QUEUE TEXT"),
+        ScreenAnchor::new(Anchor::TopCenter, Vec2::new(0., -22.)),
+        CraftQueueText,
+        RenderLayers::from_layers(&[3]),
+    ));
+}
+
+pub fn update_craft_queue_hud(
+    craft_queue: Res<CraftQueue>,
+    mut query: Query<&mut Text, With<CraftQueueText>>,
+) {
+    if !craft_queue.is_changed() {
+        return;
+    }
+    for mut text in query.iter_mut() {
+        text.sections[0].value = if let Some(job) = craft_queue.jobs.front() {
+            let queued = craft_queue.jobs.len() - 1;
+            format!(
+                "Crafting {} x{}{}",
+                job.obj,
+                job.remaining,
+                if queued > 0 {
+                    format!(" (+{} queued)", queued)
+                } else {
+                    "".to_string()
+                }
+            )
+        } else {
+            "".to_string()
+        };
+    }
+}
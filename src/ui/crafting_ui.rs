@@ -8,6 +8,9 @@ use super::UIState;
 #[derive(Resource, Default, Debug, Clone)]
 pub struct CraftingContainer {
     pub items: Container,
+    /// Position of the crafting station, used to find nearby chests to pull ingredients from
+    pub station_pos: Option<Vec2>,
+    pub station: CraftingContainerType,
 }
 
 #[derive(
@@ -4,11 +4,12 @@ use rand::Rng;
 use crate::{
     assets::Graphics,
     attributes::{Attack, BonusDamage, CurrentHealth, MaxHealth},
+    camera::CameraController,
     colors::{BLACK, DMG_NUM_GREEN, DMG_NUM_PURPLE, DMG_NUM_RED, DMG_NUM_YELLOW},
     inventory::ItemStack,
     item::WorldObject,
-    world::TILE_SIZE,
-    Game, RawPosition, TextureCamera,
+    settings::Settings,
+    Game, GameViewport, RawPosition, TextureCamera,
 };
 
 use super::{spawn_item_stack_icon, UIElement, UI_SLOT_SIZE};
@@ -52,6 +53,7 @@ pub fn handle_add_damage_numbers_after_hit(
     asset_server: Res<AssetServer>,
     raw_dmg: Query<(&Attack, &BonusDamage)>,
     game: Res<Game>,
+    settings: Res<Settings>,
 ) {
     for (e, changed_health, mut prev_health) in changed_health.iter_mut() {
         let delta = changed_health.0 - prev_health.0;
@@ -66,6 +68,9 @@ pub fn handle_add_damage_numbers_after_hit(
             2.,
         );
         prev_health.0 = changed_health.0;
+        if !settings.damage_numbers {
+            continue;
+        }
         let is_player = e == game.player;
         let dmg = raw_dmg.get(game.player).unwrap().0 .0 + raw_dmg.get(game.player).unwrap().1 .0;
         let is_crit = !is_player && delta.abs() > dmg && dmg != 0;
@@ -177,8 +182,11 @@ pub fn handle_clamp_screen_locked_icons(
     mut query: Query<(Entity, &ScreenLockedIcon, &mut Transform, &mut Visibility)>,
     txfms: Query<&GlobalTransform>,
     game_camera: Query<&GlobalTransform, With<TextureCamera>>,
+    camera: Res<CameraController>,
+    viewport: Res<GameViewport>,
 ) {
-    let MAX_DIST: Vec2 = Vec2::new(9.5, 5.) * TILE_SIZE.x - Vec2::new(3., 1.);
+    // kept just inside the edges of the screen
+    let MAX_DIST: Vec2 = camera.get_view_size(&viewport) / 2. - Vec2::new(11., 11.);
 
     for (e, screen_locked_icon, mut icon_txfm, mut v) in query.iter_mut() {
         if let Ok(parent_txfm) = txfms.get(screen_locked_icon.parent) {
@@ -0,0 +1,576 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+use bevy_proto::prelude::ProtoCommands;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::{
+    attributes::{CurrentHealth, MaxHealth},
+    colors::{overwrite_alpha, BLACK, LIGHT_GREY},
+    combat::{GodMode, HitEvent},
+    custom_commands::CommandsExt,
+    enemy::{passive::Tamed, EliteMob, Mob, SpawnLevelOverride},
+    item::{
+        item_upgrades::{
+            ArrowSpeedUpgrade, BowUpgradeSpread, BurnOnHitUpgrade, ClawUpgradeMultiThrow,
+            FireStaffAOEUpgrade, LethalHitUpgrade, LightningStaffChainUpgrade, VenomOnHitUpgrade,
+        },
+        WorldObject,
+    },
+    night::{Night, NightTracker},
+    player::{MovePlayerEvent, Player},
+    proto::proto_param::ProtoParam,
+    world::{
+        dimension::{DimensionSpawnEvent, GenerationSeed},
+        dungeon::spawn_new_dungeon_dimension,
+        world_helpers::world_pos_to_tile_pos,
+        TILE_SIZE,
+    },
+    GameParam, DEBUG_MODE,
+};
+
+use super::{ScreenAnchor, ScreenOverlay};
+
+pub const CONSOLE_TOGGLE_KEY: KeyCode = KeyCode::Grave;
+const MAX_OUTPUT_LINES: usize = 12;
+
+/// Name and usage of every command, shown by `help` and used for tab completion
+const COMMANDS: [(&str, &str); 13] = [
+    ("give", "give <item> [count]"),
+    ("spawn", "spawn <mob> [level] [elite]"),
+    ("tp", "tp <x> <y>  (tiles)"),
+    ("time", "time <hour>"),
+    ("day", "day <n>"),
+    ("seed", "seed"),
+    ("heal", "heal"),
+    ("god", "god"),
+    ("kill", "kill all"),
+    ("upgrade", "upgrade <name|all>"),
+    ("dungeon", "dungeon"),
+    ("regen", "regen"),
+    ("help", "help"),
+];
+
+#[derive(Resource, Default, Debug)]
+pub struct DevConsoleState {
+    pub open: bool,
+    pub input: String,
+    pub output: Vec<String>,
+    pub history: Vec<String>,
+    /// Index into `history` while browsing it with the arrow keys
+    history_index: Option<usize>,
+}
+
+impl DevConsoleState {
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.output.push(line.into());
+        if self.output.len() > MAX_OUTPUT_LINES {
+            self.output.remove(0);
+        }
+    }
+    /// The output above a prompt with the current input
+    pub fn get_text(&self) -> String {
+        let mut lines = self.output.clone();
+        lines.push(format!("> {}_", self.input));
+        lines.join("\n")
+    }
+}
+
+#[derive(Component)]
+pub struct DevConsoleUI;
+
+#[derive(Component)]
+pub struct DevConsoleText;
+
+pub struct ConsoleCommandEvent(pub ConsoleCommand);
+
+#[derive(Clone, Copy, Debug, PartialEq, EnumIter)]
+pub enum DevUpgrade {
+    FireAOE,
+    ChainLightning,
+    BowSpread,
+    ArrowSpeed,
+    BurnOnHit,
+    VenomOnHit,
+    LethalHit,
+    ClawMultiThrow,
+}
+
+impl DevUpgrade {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            DevUpgrade::FireAOE => "fire_aoe",
+            DevUpgrade::ChainLightning => "chain_lightning",
+            DevUpgrade::BowSpread => "bow_spread",
+            DevUpgrade::ArrowSpeed => "arrow_speed",
+            DevUpgrade::BurnOnHit => "burn",
+            DevUpgrade::VenomOnHit => "venom",
+            DevUpgrade::LethalHit => "lethal",
+            DevUpgrade::ClawMultiThrow => "claw_throw",
+        }
+    }
+    pub fn insert(&self, commands: &mut Commands, player: Entity) {
+        let mut player = commands.entity(player);
+        match self {
+            DevUpgrade::FireAOE => player.insert(FireStaffAOEUpgrade),
+            DevUpgrade::ChainLightning => player.insert(LightningStaffChainUpgrade),
+            DevUpgrade::BowSpread => player.insert(BowUpgradeSpread(2)),
+            DevUpgrade::ArrowSpeed => player.insert(ArrowSpeedUpgrade(1.)),
+            DevUpgrade::BurnOnHit => player.insert(BurnOnHitUpgrade),
+            DevUpgrade::VenomOnHit => player.insert(VenomOnHitUpgrade),
+            DevUpgrade::LethalHit => player.insert(LethalHitUpgrade),
+            DevUpgrade::ClawMultiThrow => player.insert(ClawUpgradeMultiThrow(
+                Timer::from_seconds(0.1, TimerMode::Once),
+                2,
+            )),
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConsoleCommand {
+    Give {
+        obj: WorldObject,
+        count: usize,
+    },
+    Spawn {
+        mob: Mob,
+        level: Option<u8>,
+        elite: bool,
+    },
+    Teleport {
+        x: i32,
+        y: i32,
+    },
+    Time(f32),
+    Day(u8),
+    Seed,
+    Heal,
+    God,
+    KillAll,
+    /// `None` gives every upgrade
+    Upgrade(Option<DevUpgrade>),
+    Dungeon,
+    Regen,
+    Help,
+}
+
+impl ConsoleCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let Some(name) = args.first() else {
+            return Err("Empty command".to_string());
+        };
+        let arg = |i: usize| {
+            args.get(i)
+                .copied()
+                .ok_or_else(|| format!("Missing argument, usage: {}", get_usage(name)))
+        };
+        let command = match name.to_lowercase().as_str() {
+            "give" => ConsoleCommand::Give {
+                obj: find_by_name(arg(1)?).ok_or(format!("Unknown item {}", arg(1)?))?,
+                count: parse_optional(args.get(2), 1)?,
+            },
+            "spawn" => ConsoleCommand::Spawn {
+                mob: find_by_name(arg(1)?).ok_or(format!("Unknown mob {}", arg(1)?))?,
+                level: args.get(2).map(|l| parse_number(l)).transpose()?,
+                elite: args.get(3).map(|e| e.eq_ignore_ascii_case("elite")) == Some(true),
+            },
+            "tp" => ConsoleCommand::Teleport {
+                x: parse_number(arg(1)?)?,
+                y: parse_number(arg(2)?)?,
+            },
+            "time" => {
+                let hour: f32 = parse_number(arg(1)?)?;
+                if !(0. ..24.).contains(&hour) {
+                    return Err("Hour must be between 0 and 23".to_string());
+                }
+                ConsoleCommand::Time(hour.floor())
+            }
+            "day" => ConsoleCommand::Day(parse_number(arg(1)?)?),
+            "seed" => ConsoleCommand::Seed,
+            "heal" => ConsoleCommand::Heal,
+            "god" => ConsoleCommand::God,
+            "kill" => match arg(1)? {
+                "all" => ConsoleCommand::KillAll,
+                _ => return Err(format!("Usage: {}", get_usage(name))),
+            },
+            "upgrade" => match arg(1)? {
+                "all" => ConsoleCommand::Upgrade(None),
+                upgrade => ConsoleCommand::Upgrade(Some(
+                    DevUpgrade::iter()
+                        .find(|u| u.get_name() == upgrade)
+                        .ok_or(format!("Unknown upgrade {upgrade}"))?,
+                )),
+            },
+            "dungeon" => ConsoleCommand::Dungeon,
+            "regen" => ConsoleCommand::Regen,
+            "help" => ConsoleCommand::Help,
+            _ => return Err(format!("Unknown command {name}, try help")),
+        };
+        Ok(command)
+    }
+}
+
+fn get_usage(name: &str) -> &'static str {
+    COMMANDS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, usage)| *usage)
+        .unwrap_or("help")
+}
+
+fn parse_number<T: std::str::FromStr>(arg: &str) -> Result<T, String> {
+    arg.parse::<T>()
+        .map_err(|_| format!("Not a valid number: {arg}"))
+}
+
+fn parse_optional<T: std::str::FromStr>(arg: Option<&&str>, default: T) -> Result<T, String> {
+    arg.map(|a| parse_number(a)).unwrap_or(Ok(default))
+}
+
+/// Item and mob names are matched ignoring case
+fn find_by_name<T: IntoEnumIterator + Clone>(name: &str) -> Option<T>
+where
+    &'static str: From<T>,
+{
+    T::iter().find(|v| <&str>::from(v.clone()).eq_ignore_ascii_case(name))
+}
+
+fn names_of<T: IntoEnumIterator>() -> Vec<String>
+where
+    &'static str: From<T>,
+{
+    T::iter().map(|v| <&str>::from(v).to_string()).collect()
+}
+
+/// Completes the word being typed, the command name first and then its argument.
+/// Returns the completed input, and the candidates when there is more than one.
+pub fn complete_input(input: &str) -> (String, Vec<String>) {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let typing_new_word = input.is_empty() || input.ends_with(' ');
+    let (done, partial) = if typing_new_word {
+        (words.as_slice(), "")
+    } else {
+        (&words[..words.len() - 1], *words.last().unwrap())
+    };
+    let candidates: Vec<String> = match done {
+        [] => COMMANDS.iter().map(|(n, _)| n.to_string()).collect(),
+        [cmd] => match cmd.to_lowercase().as_str() {
+            "give" => names_of::<WorldObject>(),
+            "spawn" => names_of::<Mob>(),
+            "upgrade" => DevUpgrade::iter()
+                .map(|u| u.get_name().to_string())
+                .chain(["all".to_string()])
+                .collect(),
+            "kill" => vec!["all".to_string()],
+            _ => vec![],
+        },
+        _ => vec![],
+    };
+    let matches: Vec<String> = candidates
+        .into_iter()
+        .filter(|c| c.to_lowercase().starts_with(&partial.to_lowercase()))
+        .collect();
+    let mut completed = done.join(" ");
+    if !completed.is_empty() {
+        completed.push(' ');
+    }
+    match matches.as_slice() {
+        [] => (input.to_string(), vec![]),
+        [only] => (format!("{completed}{only} "), vec![]),
+        [first, rest @ ..] => {
+            let mut prefix_len = first.len();
+            for m in rest {
+                prefix_len = first
+                    .chars()
+                    .zip(m.chars())
+                    .take_while(|(a, b)| a.eq_ignore_ascii_case(b))
+                    .count()
+                    .min(prefix_len);
+            }
+            let prefix = if prefix_len > partial.len() {
+                &first[..prefix_len]
+            } else {
+                partial
+            };
+            (format!("{completed}{prefix}"), matches.clone())
+        }
+    }
+}
+
+/// Opens and closes the console, only available in debug mode
+pub fn toggle_dev_console(
+    mut key_input: ResMut<Input<KeyCode>>,
+    mut console_state: ResMut<DevConsoleState>,
+) {
+    if !*DEBUG_MODE {
+        return;
+    }
+    let closing = console_state.open && key_input.just_pressed(KeyCode::Escape);
+    if key_input.just_pressed(CONSOLE_TOGGLE_KEY) || closing {
+        console_state.open = !console_state.open;
+        console_state.input.clear();
+        console_state.history_index = None;
+        key_input.reset_all();
+    }
+}
+
+pub fn is_dev_console_open(console_state: Res<DevConsoleState>) -> bool {
+    console_state.open
+}
+
+/// Typed characters go to the console, and keys, mouse and gamepad buttons are consumed
+/// so nothing else reacts to them
+pub fn handle_dev_console_text_input(
+    mut char_events: EventReader<ReceivedCharacter>,
+    mut key_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    mut gamepad_input: ResMut<Input<GamepadButton>>,
+    mut console_state: ResMut<DevConsoleState>,
+    mut command_events: EventWriter<ConsoleCommandEvent>,
+) {
+    for ev in char_events.iter() {
+        if !ev.char.is_control() && ev.char != '`' && ev.char != '~' {
+            console_state.input.push(ev.char);
+        }
+    }
+    if key_input.just_pressed(KeyCode::Back) {
+        console_state.input.pop();
+    }
+    if key_input.just_pressed(KeyCode::Tab) {
+        let (completed, candidates) = complete_input(&console_state.input);
+        console_state.input = completed;
+        if !candidates.is_empty() {
+            console_state.print(candidates.join(" "));
+        }
+    }
+    if key_input.just_pressed(KeyCode::Up) && !console_state.history.is_empty() {
+        let i = console_state
+            .history_index
+            .map(|i| i.saturating_sub(1))
+            .unwrap_or(console_state.history.len() - 1);
+        console_state.history_index = Some(i);
+        console_state.input = console_state.history[i].clone();
+    }
+    if key_input.just_pressed(KeyCode::Down) {
+        if let Some(i) = console_state.history_index {
+            if i + 1 < console_state.history.len() {
+                console_state.history_index = Some(i + 1);
+                console_state.input = console_state.history[i + 1].clone();
+            } else {
+                console_state.history_index = None;
+                console_state.input.clear();
+            }
+        }
+    }
+    if key_input.just_pressed(KeyCode::Return) {
+        let line = console_state.input.trim().to_string();
+        console_state.input.clear();
+        console_state.history_index = None;
+        if !line.is_empty() {
+            console_state.print(format!("> {line}"));
+            console_state.history.push(line.clone());
+            match ConsoleCommand::parse(&line) {
+                Ok(command) => command_events.send(ConsoleCommandEvent(command)),
+                Err(err) => console_state.print(err),
+            }
+        }
+    }
+    key_input.reset_all();
+    mouse_input.reset_all();
+    gamepad_input.reset_all();
+}
+
+pub fn handle_console_commands(
+    mut commands: Commands,
+    mut proto_commands: ProtoCommands,
+    proto: ProtoParam,
+    mut game: GameParam,
+    mut console_state: ResMut<DevConsoleState>,
+    mut command_events: EventReader<ConsoleCommandEvent>,
+    mut player: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &mut CurrentHealth,
+            &MaxHealth,
+            Option<&GodMode>,
+        ),
+        With<Player>,
+    >,
+    mobs: Query<(Entity, &CurrentHealth), (With<Mob>, Without<Player>, Without<Tamed>)>,
+    mut hit_event: EventWriter<HitEvent>,
+    mut move_player_event: EventWriter<MovePlayerEvent>,
+    mut dim_event: EventWriter<DimensionSpawnEvent>,
+    mut night_tracker: ResMut<NightTracker>,
+    mut night: Query<&mut Sprite, With<Night>>,
+    seed: Option<Res<GenerationSeed>>,
+) {
+    for ConsoleCommandEvent(command) in command_events.iter() {
+        let Ok((player_e, player_t, mut health, max_health, god_mode)) = player.get_single_mut()
+        else {
+            console_state.print("No player to run commands on");
+            continue;
+        };
+        let player_pos = player_t.translation().truncate();
+        match command {
+            ConsoleCommand::Give { obj, count } => {
+                proto_commands.spawn_item_from_proto(*obj, &proto, player_pos, *count, Some(1));
+                console_state.print(format!("Gave {count} {obj}"));
+            }
+            ConsoleCommand::Spawn { mob, level, elite } => {
+                let pos = player_pos + Vec2::new(2. * TILE_SIZE.x, 0.);
+                let Some(mob_e) =
+                    proto_commands.spawn_from_proto(mob.clone(), &proto.prototypes, pos)
+                else {
+                    console_state.print(format!("Could not spawn {mob}"));
+                    continue;
+                };
+                if let Some(level) = level {
+                    commands.entity(mob_e).insert(SpawnLevelOverride(*level));
+                }
+                if *elite {
+                    commands.entity(mob_e).insert(EliteMob);
+                }
+                console_state.print(format!("Spawned {mob}"));
+            }
+            ConsoleCommand::Teleport { x, y } => {
+                let pos = Vec2::new(*x as f32 * TILE_SIZE.x, *y as f32 * TILE_SIZE.y);
+                move_player_event.send(MovePlayerEvent {
+                    pos: world_pos_to_tile_pos(pos),
+                });
+                console_state.print(format!("Teleported to {x}, {y}"));
+            }
+            ConsoleCommand::Time(hour) => {
+                night_tracker.time = *hour;
+                for mut sprite in night.iter_mut() {
+                    sprite.color = overwrite_alpha(sprite.color, night_tracker.get_alpha());
+                }
+                console_state.print(format!("Time set to {hour}:00"));
+            }
+            ConsoleCommand::Day(day) => {
+                night_tracker.days = *day;
+                console_state.print(format!("Day set to {day}"));
+            }
+            ConsoleCommand::Seed => match &seed {
+                Some(seed) => console_state.print(format!("Seed: {}", seed.seed)),
+                None => console_state.print("No world seed yet"),
+            },
+            ConsoleCommand::Heal => {
+                health.0 = max_health.0;
+                console_state.print("Healed");
+            }
+            ConsoleCommand::God => {
+                if god_mode.is_some() {
+                    commands.entity(player_e).remove::<GodMode>();
+                    console_state.print("God mode off");
+                } else {
+                    commands.entity(player_e).insert(GodMode);
+                    console_state.print("God mode on");
+                }
+            }
+            ConsoleCommand::KillAll => {
+                let mut count = 0;
+                for (mob_e, mob_health) in mobs.iter() {
+                    hit_event.send(HitEvent {
+                        hit_entity: mob_e,
+                        damage: mob_health.0,
+                        dir: Vec2::ZERO,
+                        hit_with_melee: None,
+                        hit_with_projectile: None,
+                    });
+                    count += 1;
+                }
+                console_state.print(format!("Killed {count} mobs"));
+            }
+            ConsoleCommand::Upgrade(upgrade) => {
+                for u in DevUpgrade::iter().filter(|u| upgrade.map_or(true, |up| up == *u)) {
+                    u.insert(&mut commands, player_e);
+                }
+                console_state.print("Upgraded");
+            }
+            ConsoleCommand::Dungeon => {
+                spawn_new_dungeon_dimension(
+                    &mut game,
+                    &mut commands,
+                    &mut proto_commands,
+                    &mut move_player_event,
+                );
+                console_state.print("Entering a new dungeon");
+            }
+            ConsoleCommand::Regen => {
+                dim_event.send(DimensionSpawnEvent {
+                    generation_params: proto.get_world_gen().unwrap(),
+                    swap_to_dim_now: true,
+                });
+                console_state.print("Regenerating the world");
+            }
+            ConsoleCommand::Help => {
+                let usages: Vec<&str> = COMMANDS.iter().map(|(_, usage)| *usage).collect();
+                for line in usages.chunks(4) {
+                    console_state.print(line.join(", "));
+                }
+            }
+        }
+    }
+}
+
+pub fn spawn_dev_console(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    console_state: Res<DevConsoleState>,
+    existing: Query<(), With<DevConsoleUI>>,
+) {
+    if !existing.is_empty() {
+        return;
+    }
+    let text = commands
+        .spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    console_state.get_text(),
+                    TextStyle {
+                        font: asset_server.load("fonts/Kitchen Sink.ttf"),
+                        font_size: 8.0,
+                        color: LIGHT_GREY,
+                    },
+                ),
+                text_anchor: Anchor::BottomLeft,
+                transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
+                ..default()
+            },
+            ScreenAnchor::new(Anchor::BottomLeft, Vec2::new(4., 4.)),
+            DevConsoleText,
+            RenderLayers::from_layers(&[3]),
+        ))
+        .id();
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: overwrite_alpha(BLACK, 0.7),
+                ..default()
+            },
+            transform: Transform::from_translation(Vec3::new(0., 0., 30.)),
+            ..default()
+        })
+        .insert(ScreenOverlay::default())
+        .insert(DevConsoleUI)
+        .insert(RenderLayers::from_layers(&[3]))
+        .insert(Name::new("DEV CONSOLE"))
+        .push_children(&[text]);
+}
+
+pub fn despawn_dev_console(mut commands: Commands, console: Query<Entity, With<DevConsoleUI>>) {
+    for e in console.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub fn update_dev_console_text(
+    console_state: Res<DevConsoleState>,
+    mut text: Query<&mut Text, With<DevConsoleText>>,
+) {
+    for mut text in text.iter_mut() {
+        text.sections[0].value = console_state.get_text();
+    }
+}
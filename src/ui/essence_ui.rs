@@ -7,15 +7,17 @@ use rand::seq::IteratorRandom;
 use crate::{
     assets::Graphics,
     attributes::attribute_helpers::create_new_random_item_stack_with_attributes,
-    client::GameData,
+    client::{get_data_path, GameData, GAME_DATA_PATH},
     inventory::{Inventory, ItemStack},
     item::WorldObject,
     player::Player,
     proto::proto_param::ProtoParam,
-    GameParam, GAME_HEIGHT, GAME_WIDTH,
+    GameParam,
 };
 
-use super::{spawn_item_stack_icon, Interactable, UIElement, UIState, ESSENCE_UI_SIZE};
+use super::{
+    spawn_item_stack_icon, Interactable, ScreenOverlay, UIElement, UIState, ESSENCE_UI_SIZE,
+};
 
 #[derive(Component)]
 pub struct EssenceUI;
@@ -59,7 +61,6 @@ pub fn setup_essence_ui(
         .spawn(SpriteBundle {
             sprite: Sprite {
                 color: Color::rgba(146. / 255., 116. / 255., 65. / 255., 0.3),
-                custom_size: Some(Vec2::new(GAME_WIDTH + 10., GAME_HEIGHT + 10.)),
                 ..default()
             },
             transform: Transform {
@@ -70,6 +71,7 @@ pub fn setup_essence_ui(
             ..default()
         })
         .insert(RenderLayers::from_layers(&[3]))
+        .insert(ScreenOverlay::new(10.))
         .insert(Name::new("overlay"))
         .id();
 
@@ -232,7 +234,7 @@ pub fn handle_populate_essence_shop_on_new_spawn(
         ];
 
         let mut shop_choices = vec![];
-        if let Ok(file_file) = File::open("game_data.json") {
+        if let Ok(file_file) = File::open(get_data_path(GAME_DATA_PATH)) {
             let reader = BufReader::new(file_file);
             let mut rng = rand::thread_rng();
             // Read the JSON contents of the file as an instance of `User`.
@@ -2,9 +2,10 @@ use bevy::{
     diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
     prelude::*,
     render::view::RenderLayers,
+    sprite::Anchor,
 };
 
-use crate::{GAME_HEIGHT, GAME_WIDTH};
+use super::ScreenAnchor;
 
 #[derive(Component)]
 pub struct FPSText;
@@ -28,13 +29,14 @@ pub fn spawn_fps_text(mut commands: Commands, asset_server: Res<AssetServer>) {
             )
             .with_alignment(TextAlignment::Right),
             transform: Transform {
-                translation: Vec3::new(GAME_WIDTH / 2. - 10., -GAME_HEIGHT / 2. + 10., 1.),
+                translation: Vec3::new(0., 0., 1.),
                 scale: Vec3::new(1., 1., 1.),
                 ..Default::default()
             },
             ..default()
         },
         Name::new("FPS TEXT"),
+        ScreenAnchor::new(Anchor::BottomRight, Vec2::new(-10., 10.)),
         FPSText,
         RenderLayers::from_layers(&[3]),
     ));
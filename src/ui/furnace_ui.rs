@@ -5,19 +5,21 @@ use crate::{
     assets::Graphics,
     colors::YELLOW,
     container::{Container, ContainerRegistry},
-    item::{Recipes, WorldObject},
+    item::{machine::Machine, Recipes, WorldObject},
     world::world_helpers::world_pos_to_tile_pos,
 };
 
 use super::{
     interactions::Interaction, spawn_inv_slot, InventorySlotType, InventoryState, InventoryUI,
-    UIState,
+    UIState, UI_SLOT_SIZE,
 };
 
 #[derive(Component, Resource, Debug, Clone)]
 pub struct FurnaceContainer {
     pub items: Container,
     pub parent: Entity,
+    pub station: WorldObject,
+    pub machine: Machine,
     pub slot_map: Vec<Vec<WorldObject>>,
     pub timer: Timer,
     pub state: Option<FurnaceState>,
@@ -28,13 +30,10 @@ pub struct FurnaceState {
     pub current_fuel_left: Timer,
 }
 impl FurnaceState {
-    pub fn from_fuel(fuel: WorldObject) -> Self {
+    pub fn from_fuel(fuel: WorldObject, burn_time: f32) -> Self {
         Self {
             current_fuel_type: fuel,
-            current_fuel_left: Timer::from_seconds(
-                if fuel == WorldObject::Coal { 9. } else { 3. },
-                TimerMode::Once,
-            ),
+            current_fuel_left: Timer::from_seconds(burn_time, TimerMode::Once),
         }
     }
 }
@@ -109,57 +108,57 @@ pub fn update_furnace_bar(
 }
 pub fn change_ui_state_to_furnace_when_resource_added(
     mut inv_ui_state: ResMut<NextState<UIState>>,
+    mut inv_state: ResMut<InventoryState>,
+    furnace: Res<FurnaceContainer>,
 ) {
+    inv_state.machine_slot_positions = get_machine_slot_positions(&furnace.machine);
     inv_ui_state.set(UIState::Furnace);
 }
 
+/// Fuel goes bottom left, inputs in a row above it, and outputs in a column on the right
+pub fn get_machine_slot_positions(machine: &Machine) -> Vec<Vec2> {
+    let centered_offset = |i: usize, n: usize| (i as f32 - (n as f32 - 1.) / 2.) * UI_SLOT_SIZE;
+    let mut positions = vec![];
+    if machine.fuel_slot().is_some() {
+        positions.push(Vec2::new(-20.5, 26.));
+    }
+    for i in 0..machine.input_slots {
+        positions.push(Vec2::new(
+            -20.5 + centered_offset(i, machine.input_slots),
+            68.,
+        ));
+    }
+    for i in 0..machine.output_slots {
+        positions.push(Vec2::new(
+            21.5,
+            47. - centered_offset(i, machine.output_slots),
+        ));
+    }
+    positions
+}
+
 pub fn add_container_to_new_furnace_objs(
     mut commands: Commands,
-    new_furnace: Query<(Entity, &GlobalTransform, &WorldObject), Added<WorldObject>>,
+    new_machines: Query<(Entity, &GlobalTransform, &WorldObject, &Machine), Added<Machine>>,
     recipes: Res<Recipes>,
     container_reg: Res<ContainerRegistry>,
 ) {
-    for (e, t, obj) in new_furnace.iter() {
-        let existing_cont_option = container_reg
+    for (e, t, obj, machine) in new_machines.iter() {
+        let mut items = container_reg
             .containers
-            .get(&world_pos_to_tile_pos(t.translation().truncate()));
-        match obj {
-            WorldObject::Furnace => {
-                let ing: Vec<_> = recipes
-                    .furnace_list
-                    .iter()
-                    .map(|(k, _)| k.clone())
-                    .collect();
-                let results: Vec<_> = recipes
-                    .furnace_list
-                    .iter()
-                    .map(|(_, v)| v.clone())
-                    .collect();
-                commands.entity(e).insert(FurnaceContainer {
-                    items: existing_cont_option
-                        .unwrap_or(&Container::with_size(3))
-                        .clone(),
-                    parent: e,
-                    slot_map: vec![vec![WorldObject::Coal], ing.clone(), results.clone()],
-                    timer: Timer::from_seconds(3., TimerMode::Once),
-                    state: None,
-                });
-            }
-            WorldObject::UpgradeStation => {
-                commands.entity(e).insert(FurnaceContainer {
-                    items: existing_cont_option
-                        .unwrap_or(&Container::with_size(2))
-                        .clone(),
-                    parent: e,
-                    slot_map: vec![
-                        vec![WorldObject::UpgradeTome, WorldObject::OrbOfTransformation],
-                        recipes.upgradeable_items.clone(),
-                    ],
-                    timer: Timer::from_seconds(3., TimerMode::Once),
-                    state: None,
-                });
-            }
-            _ => {}
-        }
+            .get(&world_pos_to_tile_pos(t.translation().truncate()))
+            .cloned()
+            .unwrap_or(Container::with_size(machine.num_slots()));
+        // containers saved before the machine's layout changed
+        items.items.resize(machine.num_slots(), None);
+        commands.entity(e).insert(FurnaceContainer {
+            items,
+            parent: e,
+            station: *obj,
+            machine: machine.clone(),
+            slot_map: machine.get_slot_map(*obj, &recipes),
+            timer: Timer::from_seconds(3., TimerMode::Once),
+            state: None,
+        });
     }
 }
@@ -3,12 +3,13 @@ use bevy::prelude::*;
 use strum_macros::{Display, EnumIter};
 
 use crate::{
+    action_map::{ActionInput, InputAction},
     assets::Graphics,
     attributes::attribute_helpers::create_new_random_item_stack_with_attributes,
     colors::{DARK_GREEN, YELLOW_2},
     inputs::CursorPos,
     inventory::{Inventory, InventoryItemStack, ItemStack},
-    item::{CraftedItemEvent, EquipmentType},
+    item::{durability::RepairItemEvent, CraftRequestEvent, CraftedItemEvent, EquipmentType},
     player::stats::{PlayerStats, SkillPoints},
     proto::proto_param::ProtoParam,
     GameParam,
@@ -17,8 +18,8 @@ use crate::{
 use super::{
     crafting_ui::CraftingContainer, spawn_item_stack_icon, stats_ui::StatsButtonState, ui_helpers,
     ChestContainer, EssenceOption, FurnaceContainer, InventorySlotState, MenuButton,
-    MenuButtonClickEvent, SubmitEssenceChoice, ToolTipUpdateEvent, TooltipTeardownEvent,
-    UIContainersParam, UIState,
+    MenuButtonClickEvent, MenuClicks, SubmitEssenceChoice, ToolTipUpdateEvent,
+    TooltipTeardownEvent, UIContainersParam, UIState,
 };
 
 #[derive(Component, Debug, EnumIter, Display, Hash, PartialEq, Eq)]
@@ -56,6 +57,7 @@ pub enum UIElement {
     HungerDebuff2,
     HungerDebuff3,
     ScreenIconSlot,
+    AimReticle,
 }
 
 #[derive(Component, Debug, Clone)]
@@ -396,7 +398,7 @@ pub fn handle_hovering(
 }
 
 pub fn handle_item_drop_clicks(
-    mouse_input: ResMut<Input<MouseButton>>,
+    clicks: Res<MenuClicks>,
     cursor_pos: Res<CursorPos>,
     ui_sprites: Query<(Entity, &Sprite, &GlobalTransform), With<Interactable>>,
     slot_states: Query<&mut InventorySlotState>,
@@ -409,8 +411,8 @@ pub fn handle_item_drop_clicks(
     mut right_clicks: Local<Vec<usize>>,
     ui_state: Res<State<UIState>>,
 ) {
-    let left_mouse_pressed = mouse_input.just_pressed(MouseButton::Left);
-    let right_mouse_pressed = mouse_input.pressed(MouseButton::Right);
+    let left_mouse_pressed = clicks.select.just_pressed;
+    let right_mouse_pressed = clicks.split.pressed;
     if !right_mouse_pressed {
         right_clicks.clear();
     }
@@ -467,7 +469,7 @@ pub fn handle_item_drop_clicks(
                             parent_interactable_entity: Some(e),
                             stack_empty: true,
                         });
-                    } else if mouse_input.just_pressed(MouseButton::Right) {
+                    } else if clicks.split.just_pressed {
                         let lonely_item_stack: ItemStack = item_stack.copy_with_count(1);
                         item_stack.modify_count(-1);
                         world_drop_events.send(DropInWorldEvent {
@@ -505,8 +507,8 @@ pub fn handle_drop_dragged_items_on_inv_close(
 pub fn handle_interaction_clicks(
     mut commands: Commands,
     cursor_pos: Res<CursorPos>,
-    mut mouse_input: ResMut<Input<MouseButton>>,
-    key_input: ResMut<Input<KeyCode>>,
+    mut clicks: ResMut<MenuClicks>,
+    actions: ActionInput,
     ui_sprites: Query<(Entity, &Sprite, &GlobalTransform), With<Interactable>>,
     mut inv_slots: Query<(Entity, &mut Interactable, &mut InventorySlotState)>,
     mut inv_item_icons: Query<(Entity, &mut Transform, &ItemStack)>,
@@ -526,10 +528,10 @@ pub fn handle_interaction_clicks(
     }
 
     let hit_test = ui_helpers::pointcast_2d(&cursor_pos, &ui_sprites, None);
-    let left_mouse_pressed = mouse_input.just_pressed(MouseButton::Left);
-    let left_mouse_pressing = mouse_input.pressed(MouseButton::Left);
-    let right_mouse_pressed = mouse_input.just_pressed(MouseButton::Right);
-    let shift_key_pressed = key_input.pressed(KeyCode::LShift);
+    let left_mouse_pressed = clicks.select.just_pressed;
+    let left_mouse_pressing = clicks.select.pressed;
+    let right_mouse_pressed = clicks.split.just_pressed;
+    let shift_key_pressed = actions.pressed(InputAction::QuickMove);
     let currently_dragging = dragging_query.iter().len() > 0;
     for (e, mut interactable, mut state) in inv_slots.iter_mut() {
         match hit_test {
@@ -585,13 +587,41 @@ pub fn handle_interaction_clicks(
                                 }
 
                                 state.dirty = true;
-                                mouse_input.clear();
+                                clicks.clear();
                             }
                         }
                     } else if right_mouse_pressed && !currently_dragging && !shift_key_pressed {
                         if state.r#type.is_crafting() {
+                            // queue one more craft of this recipe
+                            if let Some(obj) = state.obj_type {
+                                container_param.craft_request_event.send(CraftRequestEvent {
+                                    obj,
+                                    count: 1,
+                                    station_pos: container_param.get_crafting_station_pos(),
+                                });
+                            }
                             continue;
                         }
+                        // at an Anvil, right clicking a worn item repairs it instead of picking it up
+                        if container_param.is_anvil_open() && !state.r#type.is_chest() {
+                            let is_repairable = state
+                                .item
+                                .and_then(|item| inv_item_icons.get(item).ok())
+                                .map_or(false, |(_, _, stack)| {
+                                    stack.attributes.is_damaged()
+                                        && container_param
+                                            .recipes
+                                            .repair_materials
+                                            .contains_key(&stack.obj_type)
+                                });
+                            if is_repairable {
+                                container_param.repair_item_event.send(RepairItemEvent {
+                                    slot: state.slot_index,
+                                    slot_type: state.r#type,
+                                });
+                                continue;
+                            }
+                        }
                         if let Some(item) = state.item {
                             if let Ok(item_icon) = inv_item_icons.get_mut(item) {
                                 let mut inv = inv.single_mut();
@@ -616,11 +646,19 @@ pub fn handle_interaction_clicks(
 
                                 commands.entity(e).insert(DraggedItem);
                                 interactable.change(Interaction::Dragging { item: e });
-                                mouse_input.clear();
+                                clicks.clear();
                             }
                         }
                     } else if shift_key_pressed && left_mouse_pressing {
                         if state.r#type.is_crafting() {
+                            // queue as many crafts as the ingredients allow
+                            if let Some(obj) = state.obj_type.filter(|_| left_mouse_pressed) {
+                                container_param.craft_request_event.send(CraftRequestEvent {
+                                    obj,
+                                    count: usize::MAX,
+                                    station_pos: container_param.get_crafting_station_pos(),
+                                });
+                            }
                             continue;
                         }
                         let mut inv = inv.single_mut();
@@ -658,7 +696,7 @@ pub fn handle_interaction_clicks(
 
 pub fn handle_cursor_stats_buttons(
     cursor_pos: Res<CursorPos>,
-    mouse_input: Res<Input<MouseButton>>,
+    clicks: Res<MenuClicks>,
     ui_sprites: Query<(Entity, &Sprite, &GlobalTransform), With<Interactable>>,
     mut stats_buttons: Query<
         (Entity, &mut Interactable, &StatsButtonState),
@@ -667,7 +705,7 @@ pub fn handle_cursor_stats_buttons(
     mut player_stats: Query<(&mut PlayerStats, &mut SkillPoints)>,
 ) {
     let hit_test = ui_helpers::pointcast_2d(&cursor_pos, &ui_sprites, None);
-    let left_mouse_pressed = mouse_input.just_pressed(MouseButton::Left);
+    let left_mouse_pressed = clicks.select.just_pressed;
 
     for (e, mut interactable, state) in stats_buttons.iter_mut() {
         match hit_test {
@@ -754,13 +792,13 @@ pub fn handle_cursor_main_menu_buttons(
 
 pub fn handle_cursor_essence_buttons(
     cursor_pos: Res<CursorPos>,
-    mouse_input: Res<Input<MouseButton>>,
+    clicks: Res<MenuClicks>,
     ui_sprites: Query<(Entity, &Sprite, &GlobalTransform), With<Interactable>>,
     mut essence_buttons: Query<(Entity, &mut Interactable, &EssenceOption)>,
     mut essence_event: EventWriter<SubmitEssenceChoice>,
 ) {
     let hit_test = ui_helpers::pointcast_2d(&cursor_pos, &ui_sprites, None);
-    let left_mouse_pressed = mouse_input.just_pressed(MouseButton::Left);
+    let left_mouse_pressed = clicks.select.just_pressed;
 
     for (e, mut interactable, essence_option) in essence_buttons.iter_mut() {
         match hit_test {
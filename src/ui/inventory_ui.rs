@@ -1,18 +1,18 @@
-use bevy::{prelude::*, render::view::RenderLayers};
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
 
 use crate::{
     assets::Graphics,
-    attributes::AttributeChangeEvent,
+    attributes::{AttributeChangeEvent, ItemAttributes},
+    colors::{BLACK, LIGHT_GREEN, RED, YELLOW},
     inventory::{Inventory, InventoryItemStack, ItemStack},
     item::WorldObject,
     ui::{CHEST_INVENTORY_UI_SIZE, INVENTORY_UI_SIZE},
-    GAME_HEIGHT, GAME_WIDTH,
 };
 
 use super::{
-    crafting_ui::CraftingContainer, interactions::Interaction, Interactable,
-    ShowInvPlayerStatsEvent, UIContainersParam, UIElement, CRAFTING_INVENTORY_UI_SIZE,
-    FURNACE_INVENTORY_UI_SIZE, UI_SLOT_SIZE,
+    crafting_ui::CraftingContainer, interactions::Interaction, Interactable, ScreenAnchor,
+    ScreenOverlay, ShowInvPlayerStatsEvent, UIContainersParam, UIElement,
+    CRAFTING_INVENTORY_UI_SIZE, FURNACE_INVENTORY_UI_SIZE, UI_SLOT_SIZE,
 };
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States, Component)]
@@ -26,6 +26,7 @@ pub enum UIState {
     Crafting,
     Furnace,
     Essence,
+    RecipeBook,
 }
 impl UIState {
     pub fn is_inv_open(&self) -> bool {
@@ -36,6 +37,8 @@ impl UIState {
     }
 }
 
+const DURABILITY_BAR_WIDTH: f32 = 12.;
+
 #[derive(Component, Default, Clone)]
 pub struct InventoryUI;
 #[derive(Component, FromReflect, Reflect, Clone, Debug)]
@@ -52,6 +55,8 @@ pub struct InventoryState {
     pub active_hotbar_slot: usize,
     pub inv_size: Vec2,
     pub hotbar_dirty: bool,
+    /// Slot positions of the open machine, set when its UI opens
+    pub machine_slot_positions: Vec<Vec2>,
 }
 #[derive(FromReflect, PartialEq, Reflect, Debug, Clone, Copy)]
 pub enum InventorySlotType {
@@ -121,7 +126,6 @@ pub fn setup_inv_ui(
         .spawn(SpriteBundle {
             sprite: Sprite {
                 color: Color::rgba(146. / 255., 116. / 255., 65. / 255., 0.3),
-                custom_size: Some(Vec2::new(GAME_WIDTH, GAME_HEIGHT)),
                 ..default()
             },
             transform: Transform {
@@ -132,6 +136,7 @@ pub fn setup_inv_ui(
             ..default()
         })
         .insert(RenderLayers::from_layers(&[3]))
+        .insert(ScreenOverlay::default())
         .insert(Name::new("overlay"))
         .id();
     let inv_e = commands
@@ -274,7 +279,8 @@ pub fn spawn_inv_slot(
         + inv_slot_offset.y;
 
     if slot_type.is_hotbar() {
-        y = -GAME_HEIGHT / 2. + 14.;
+        // kept along the bottom of the screen by its `ScreenAnchor`
+        y = 0.;
         x = ((slot_index % 6) as f32 * UI_SLOT_SIZE) - 2. * UI_SLOT_SIZE;
     } else if slot_type.is_crafting() {
         x = ((slot_index % 8) as f32 * UI_SLOT_SIZE) - (inv_state.inv_size.x) / 2.
@@ -303,15 +309,9 @@ pub fn spawn_inv_slot(
     } else if slot_type.is_chest() {
         y += 4. * UI_SLOT_SIZE + 11.;
     } else if slot_type.is_furnace() {
-        if slot_index == 0 {
-            x = -20.5;
-            y = 26.;
-        } else if slot_index == 1 {
-            x = -20.5;
-            y = 68.;
-        } else if slot_index == 2 {
-            x = 21.5;
-            y = 47.;
+        if let Some(pos) = inv_state.machine_slot_positions.get(slot_index) {
+            x = pos.x;
+            y = pos.y;
         }
     } else if ((slot_index / 6) as f32).trunc() == 0. {
         y -= 3.;
@@ -374,7 +374,9 @@ pub fn spawn_inv_slot(
     if let Some(i) = item_icon_option {
         slot_entity.push_children(&[i]);
     }
-    if !slot_type.is_hotbar() {
+    if slot_type.is_hotbar() {
+        slot_entity.insert(ScreenAnchor::new(Anchor::BottomCenter, Vec2::new(x, 14.)));
+    } else {
         let inv_e = inv_query.single();
         slot_entity
             .set_parent(inv_e)
@@ -439,8 +441,58 @@ pub fn spawn_item_stack_icon(
             .id();
         commands.entity(item).push_children(&[text]);
     }
+    if item_stack.attributes.is_damaged() {
+        spawn_durability_bar(commands, item, &item_stack.attributes);
+    }
     item
 }
+
+/// Thin bar along the bottom of a worn item's icon, showing how much durability is left
+fn spawn_durability_bar(commands: &mut Commands, icon: Entity, attributes: &ItemAttributes) {
+    let percent = (attributes.durability.max(0) as f32 / attributes.max_durability as f32).min(1.);
+    let color = if percent > 0.5 {
+        LIGHT_GREEN
+    } else if percent > 0.25 {
+        YELLOW
+    } else {
+        RED
+    };
+    let bar_bg = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: BLACK,
+                custom_size: Some(Vec2::new(DURABILITY_BAR_WIDTH, 2.)),
+                anchor: Anchor::CenterLeft,
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(-DURABILITY_BAR_WIDTH / 2., -7., 1.),
+                ..Default::default()
+            },
+            ..default()
+        })
+        .insert(RenderLayers::from_layers(&[3]))
+        .insert(Name::new("durability bar"))
+        .id();
+    let bar = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::new(DURABILITY_BAR_WIDTH * percent, 1.)),
+                anchor: Anchor::CenterLeft,
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(0., 0., 1.),
+                ..Default::default()
+            },
+            ..default()
+        })
+        .insert(RenderLayers::from_layers(&[3]))
+        .id();
+    commands.entity(bar_bg).push_children(&[bar]);
+    commands.entity(icon).push_children(&[bar_bg]);
+}
 //TODO: make event?
 pub fn change_hotbar_slot(
     slot: usize,
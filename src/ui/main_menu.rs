@@ -8,7 +8,7 @@ use crate::{
     GameState, GAME_HEIGHT, GAME_WIDTH,
 };
 
-use super::{Interactable, UIElement};
+use super::{Interactable, SettingsMenuState, UIElement};
 
 #[derive(Component, Clone)]
 pub enum MenuButton {
@@ -71,6 +71,7 @@ pub fn remove_main_menu(
 pub fn handle_menu_button_click_events(
     mut event_reader: EventReader<MenuButtonClickEvent>,
     mut next_state: ResMut<NextState<GameState>>,
+    mut settings_state: ResMut<SettingsMenuState>,
     mut commands: Commands,
 ) {
     for event in event_reader.iter() {
@@ -85,7 +86,7 @@ pub fn handle_menu_button_click_events(
                 commands.insert_resource(WorldObjectCache::default());
             }
             MenuButton::Options => {
-                println!("OPTIONS");
+                settings_state.open();
             }
             MenuButton::Quit => {
                 exit(0);
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+
+use crate::{
+    action_map::{ActionInput, InputAction, MENU_NAV_ACTIONS},
+    inputs::CursorPos,
+};
+
+use super::Interactable;
+
+const MENU_NAV_DIRECTIONS: [Vec2; 4] = [Vec2::Y, Vec2::NEG_Y, Vec2::NEG_X, Vec2::X];
+
+/// Moves the cursor to the nearest slot or button in the pressed direction,
+/// so the cursor driven menus can be navigated with the D-pad
+pub fn handle_menu_navigation(
+    actions: ActionInput,
+    mut cursor_pos: ResMut<CursorPos>,
+    ui_sprites: Query<&GlobalTransform, With<Interactable>>,
+) {
+    let Some(direction) = MENU_NAV_ACTIONS
+        .iter()
+        .zip(MENU_NAV_DIRECTIONS)
+        .find(|(action, _)| actions.just_pressed(**action))
+        .map(|(_, direction)| direction)
+    else {
+        return;
+    };
+    let cursor = cursor_pos.ui_coords.truncate();
+    // prefer targets in a straight line over closer ones off to the side
+    let target = ui_sprites
+        .iter()
+        .map(|t| t.translation().truncate())
+        .filter_map(|pos| {
+            let offset = pos - cursor;
+            let along = offset.dot(direction);
+            (along > 1.).then(|| (pos, along + 2. * offset.perp_dot(direction).abs()))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+    if let Some((pos, _)) = target {
+        cursor_pos.ui_coords = pos.extend(cursor_pos.ui_coords.z);
+    }
+}
+
+/// A mouse button, or the menu action standing in for it, as read by the cursor driven menus
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MenuButtonState {
+    pub just_pressed: bool,
+    pub pressed: bool,
+}
+
+/// Clicks for the cursor driven menus, from the mouse or the menu select and split actions,
+/// so picking up, splitting and dropping stacks works the same on every device
+#[derive(Resource, Debug, Default)]
+pub struct MenuClicks {
+    /// Left click, or `InputAction::MenuSelect`
+    pub select: MenuButtonState,
+    /// Right click, or `InputAction::MenuSplit`
+    pub split: MenuButtonState,
+}
+
+impl MenuClicks {
+    /// Consumes this frame's clicks, so the stack just picked up isn't dropped straight back
+    pub fn clear(&mut self) {
+        self.select.just_pressed = false;
+        self.split.just_pressed = false;
+    }
+}
+
+/// Runs before the rest of the game reads input, like a real click
+pub fn update_menu_clicks(
+    actions: ActionInput,
+    mouse_input: Res<Input<MouseButton>>,
+    mut clicks: ResMut<MenuClicks>,
+) {
+    let read = |action, button| MenuButtonState {
+        just_pressed: mouse_input.just_pressed(button) || actions.just_pressed(action),
+        pressed: mouse_input.pressed(button) || actions.pressed(action),
+    };
+    clicks.select = read(InputAction::MenuSelect, MouseButton::Left);
+    clicks.split = read(InputAction::MenuSplit, MouseButton::Right);
+}
@@ -6,15 +6,15 @@ use crate::item::WorldObject;
 use crate::world::dimension::SpawnDimension;
 use crate::world::world_helpers::{camera_pos_to_chunk_pos, camera_pos_to_tile_pos};
 use crate::world::{TileMapPosition, CHUNK_SIZE};
-use crate::{CustomFlush, GameParam, GameState, Player, GAME_HEIGHT, GAME_WIDTH, MINIMAP};
+use crate::{CustomFlush, GameParam, GameState, Player, MINIMAP};
 use bevy::prelude::*;
 use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
 use bevy::render::view::RenderLayers;
-use bevy::sprite::MaterialMesh2dBundle;
+use bevy::sprite::{Anchor, MaterialMesh2dBundle};
 use bevy::utils::HashMap;
 use bevy_ecs_tilemap::prelude::*;
 
-use super::UIElement;
+use super::{ScreenAnchor, UIElement};
 pub struct MinimapPlugin;
 //TODO: temp disable minimap
 impl Plugin for MinimapPlugin {
@@ -242,15 +242,18 @@ fn setup_mini_map(
 
                     ..Default::default()
                 },
-                transform: Transform::from_translation(Vec3::new(
-                    (GAME_WIDTH - ((num_tiles + 1) * 2) as f32) / 2. - 2.,
-                    (GAME_HEIGHT - ((num_tiles + 1) * 2 + 1) as f32) / 2. - 2.,
-                    1.,
-                )),
+                transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
                 ..Default::default()
             })
             .insert(RenderLayers::from_layers(&[3]))
             .insert(Minimap)
+            .insert(ScreenAnchor::new(
+                Anchor::TopRight,
+                Vec2::new(
+                    -(((num_tiles + 1) * 2) as f32) / 2. - 2.,
+                    -(((num_tiles + 1) * 2 + 1) as f32) / 2. - 2.,
+                ),
+            ))
             .insert(Name::new("MAP"))
             .id();
         let map = commands
@@ -1,9 +1,11 @@
+mod aim_reticle;
 pub mod chest_ui;
+mod craft_queue_hud;
 pub mod crafting_ui;
 pub mod damage_numbers;
 pub mod screen_effects;
 pub mod ui_container_param;
-use bevy::sprite::Material2dPlugin;
+use bevy::{input::InputSystem, sprite::Material2dPlugin, transform::TransformSystem};
 use damage_numbers::handle_clamp_screen_locked_icons;
 use screen_effects::{handle_add_screen_effects, setup_screen_effects, ScreenEffectMaterial};
 pub use ui_container_param::*;
@@ -14,26 +16,47 @@ mod interactions;
 mod inventory_ui;
 pub mod minimap;
 mod player_hud;
+mod raid_hud;
+pub mod recipe_book_ui;
 pub mod stats_ui;
 mod tile_hover;
 mod tooltips;
 mod ui_helpers;
+use aim_reticle::*;
 pub use chest_ui::*;
+use craft_queue_hud::*;
 pub use enemy_health_bar::*;
 use fps_text::*;
 pub use furnace_ui::*;
 pub use interactions::*;
 pub use inventory_ui::*;
 pub use player_hud::*;
+use raid_hud::*;
+pub use recipe_book_ui::*;
 pub use tooltips::*;
 mod main_menu;
 pub use main_menu::*;
+pub mod controls_ui;
+pub use controls_ui::*;
+pub mod settings_ui;
+pub use settings_ui::*;
+pub mod pause_menu;
+pub use pause_menu::*;
+mod screen_anchor;
+pub use screen_anchor::*;
+mod menu_navigation;
+use menu_navigation::*;
 mod essence_ui;
 pub use essence_ui::*;
+pub mod dev_console;
+pub use dev_console::*;
 
 use crate::{
-    client::load_state, combat::handle_hits, item::item_actions::ActionSuccessEvent, CustomFlush,
-    GameState, DEBUG_MODE,
+    client::{load_state, save_state},
+    combat::handle_hits,
+    item::item_actions::ActionSuccessEvent,
+    settings::damage_numbers_enabled,
+    CustomFlush, GameState, PauseState, DEBUG_MODE,
 };
 
 use self::{
@@ -55,6 +78,10 @@ pub const TOOLTIP_UI_SIZE: Vec2 = Vec2::new(93., 120.5);
 pub const CHEST_INVENTORY_UI_SIZE: Vec2 = Vec2::new(127., 142.);
 pub const CRAFTING_INVENTORY_UI_SIZE: Vec2 = Vec2::new(171., 166.);
 pub const FURNACE_INVENTORY_UI_SIZE: Vec2 = Vec2::new(171., 166.);
+pub const RECIPE_BOOK_UI_SIZE: Vec2 = Vec2::new(171., 166.);
+pub const CONTROLS_UI_SIZE: Vec2 = Vec2::new(290., 166.);
+pub const SETTINGS_UI_SIZE: Vec2 = Vec2::new(150., 166.);
+pub const PAUSE_UI_SIZE: Vec2 = Vec2::new(80., 76.);
 pub const UI_SLOT_SIZE: f32 = 20.0;
 
 pub struct UIPlugin;
@@ -64,6 +91,12 @@ impl Plugin for UIPlugin {
         app.add_state::<UIState>()
             .insert_resource(LastHoveredSlot { slot: None })
             .insert_resource(InventoryState::default())
+            .insert_resource(RecipeBookState::default())
+            .insert_resource(ControlsMenuState::default())
+            .insert_resource(SettingsMenuState::default())
+            .insert_resource(PauseMenuState::default())
+            .insert_resource(DevConsoleState::default())
+            .insert_resource(MenuClicks::default())
             .insert_resource(TooltipsManager {
                 timer: Timer::from_seconds(0.3, TimerMode::Once),
             })
@@ -77,6 +110,7 @@ impl Plugin for UIPlugin {
             .add_event::<SubmitEssenceChoice>()
             .add_event::<DropInWorldEvent>()
             .add_event::<MenuButtonClickEvent>()
+            .add_event::<ConsoleCommandEvent>()
             .add_plugin(Material2dPlugin::<ScreenEffectMaterial>::default())
             .register_type::<InventorySlotState>()
             .add_plugin(MinimapPlugin)
@@ -100,6 +134,8 @@ impl Plugin for UIPlugin {
                     setup_hotbar_hud,
                     setup_xp_bar_ui.after(load_state),
                     setup_bars_ui.after(load_state),
+                    setup_raid_hud,
+                    setup_craft_queue_hud,
                 )
                     .in_schedule(OnEnter(GameState::Main)),
             )
@@ -112,7 +148,7 @@ impl Plugin for UIPlugin {
                     handle_enemy_health_visibility,
                     add_ui_icon_for_elite_mobs,
                     handle_add_damage_numbers_after_hit.after(handle_hits),
-                    handle_add_dodge_text,
+                    handle_add_dodge_text.run_if(damage_numbers_enabled),
                     tick_damage_numbers,
                 )
                     .in_set(OnUpdate(GameState::Main)),
@@ -176,19 +212,130 @@ impl Plugin for UIPlugin {
                     handle_add_screen_effects,
                     setup_screen_effects,
                     handle_clamp_screen_locked_icons,
+                    update_raid_hud,
+                    update_craft_queue_hud,
+                    update_aim_reticle,
                     setup_essence_ui
                         .before(CustomFlush)
                         .run_if(resource_added::<EssenceShopChoices>()),
                 )
                     .in_set(OnUpdate(GameState::Main)),
             )
+            .add_systems(
+                (
+                    toggle_recipe_book,
+                    setup_recipe_book_ui.before(CustomFlush).run_if(
+                        state_changed::<UIState>().and_then(in_state(UIState::RecipeBook)),
+                    ),
+                    update_recipe_book_rows
+                        .after(CustomFlush)
+                        .run_if(in_state(UIState::RecipeBook)),
+                    handle_recipe_book_buttons.run_if(in_state(UIState::RecipeBook)),
+                    handle_recipe_book_scroll.run_if(in_state(UIState::RecipeBook)),
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            )
+            .add_system(
+                handle_recipe_book_text_input
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem)
+                    .run_if(in_state(GameState::Main).and_then(in_state(UIState::RecipeBook))),
+            )
             .add_system(
                 handle_new_ui_state
                     .in_base_set(CoreSet::PostUpdate)
                     .run_if(in_state(GameState::Main)),
             )
             .add_system(handle_hovering.run_if(ui_hover_interactions_condition))
-            .add_system(handle_cursor_main_menu_buttons.in_set(OnUpdate(GameState::MainMenu)))
+            .add_system(
+                handle_menu_navigation
+                    .before(handle_interaction_clicks)
+                    .in_set(OnUpdate(GameState::Main))
+                    .run_if(not(in_state(UIState::Closed))),
+            )
+            .add_system(
+                update_menu_clicks
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem)
+                    .after(handle_dev_console_text_input)
+                    .run_if(in_state(GameState::Main)),
+            )
+            .add_system(
+                handle_rebind_input
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem)
+                    .run_if(is_rebinding_controls),
+            )
+            .add_systems(
+                (
+                    toggle_dev_console,
+                    handle_dev_console_text_input
+                        .after(toggle_dev_console)
+                        .run_if(is_dev_console_open),
+                )
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem)
+                    .run_if(in_state(GameState::Main)),
+            )
+            .add_systems(
+                (
+                    handle_console_commands,
+                    spawn_dev_console.run_if(
+                        resource_changed::<DevConsoleState>().and_then(is_dev_console_open),
+                    ),
+                    despawn_dev_console.run_if(
+                        resource_changed::<DevConsoleState>().and_then(not(is_dev_console_open)),
+                    ),
+                    update_dev_console_text
+                        .after(handle_console_commands)
+                        .run_if(resource_changed::<DevConsoleState>()),
+                )
+                    .run_if(in_state(GameState::Main)),
+            )
+            .add_system(
+                toggle_pause_menu
+                    .before(handle_settings_menu_buttons)
+                    .run_if(in_state(GameState::Main))
+                    .run_if(not(is_quitting_to_main_menu)),
+            )
+            .add_systems(
+                (pause_simulation, spawn_pause_menu).in_schedule(OnEnter(PauseState::Paused)),
+            )
+            .add_systems(
+                (resume_simulation, despawn_pause_menu).in_schedule(OnExit(PauseState::Paused)),
+            )
+            .add_systems(
+                (
+                    handle_pause_menu_buttons
+                        .before(handle_settings_menu_buttons)
+                        .before(save_state),
+                    highlight_selected_pause_button.run_if(resource_changed::<PauseMenuState>()),
+                )
+                    .in_set(OnUpdate(PauseState::Paused)),
+            )
+            .add_systems(
+                (update_screen_anchors, update_screen_overlays)
+                    .in_base_set(CoreSet::PostUpdate)
+                    .before(TransformSystem::TransformPropagate),
+            )
+            .add_system(
+                quit_to_main_menu
+                    .after(save_state)
+                    .run_if(in_state(GameState::Main))
+                    .run_if(is_quitting_to_main_menu),
+            )
+            .add_systems((
+                update_settings_menu,
+                handle_settings_menu_buttons.before(handle_controls_menu_buttons),
+                update_controls_menu,
+                handle_controls_menu_buttons,
+            ))
+            .add_system(
+                handle_cursor_main_menu_buttons
+                    .in_set(OnUpdate(GameState::MainMenu))
+                    .run_if(is_settings_menu_closed)
+                    .run_if(is_controls_menu_closed),
+            )
             .add_system(apply_system_buffers.in_set(CustomFlush));
     }
 }
@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::RapierConfiguration;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::{
+    action_map::{ActionInput, InputAction},
+    animations::game_over::{teardown_run, RunEntityFilter},
+    client::SaveTimer,
+    colors::{BLACK, LIGHT_BROWN, RED, YELLOW_2},
+    inputs::CursorPos,
+    world::dungeon::Dungeon,
+    GameState, PauseState,
+};
+
+use super::{
+    ui_helpers::{get_menu_button_at_cursor, spawn_menu_button, spawn_menu_panel, spawn_menu_text},
+    ControlsMenuState, MenuClicks, SettingsMenuState, UIState, PAUSE_UI_SIZE,
+};
+
+const PAUSE_BUTTON_SIZE: Vec2 = Vec2::new(64., 12.);
+
+#[derive(Resource, Default, Debug)]
+pub struct PauseMenuState {
+    /// The button the menu actions move between, highlighted for gamepads
+    pub selected: usize,
+    /// Set by Save & Quit, the world is torn down once the save has been written
+    pub quitting: bool,
+    /// Quits without waiting for a save, for runs that shouldn't be picked back up
+    pub skip_save: bool,
+}
+
+#[derive(Component)]
+pub struct PauseMenuUI;
+
+/// Shown when Save & Quit is pressed somewhere the run can't be saved
+#[derive(Component)]
+pub struct PauseMenuWarning;
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, EnumIter)]
+pub enum PauseMenuButton {
+    Resume,
+    Settings,
+    SaveAndQuit,
+}
+
+impl PauseMenuButton {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            PauseMenuButton::Resume => "Resume",
+            PauseMenuButton::Settings => "Settings",
+            PauseMenuButton::SaveAndQuit => "Save & Quit",
+        }
+    }
+}
+
+pub fn is_quitting_to_main_menu(pause_menu_state: Res<PauseMenuState>) -> bool {
+    pause_menu_state.quitting
+}
+
+/// Pauses with the close menu key when no other menu is open, and resumes with it
+/// from the pause menu. Settings and controls opened from here close themselves first.
+pub fn toggle_pause_menu(
+    actions: ActionInput,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    ui_state: Res<State<UIState>>,
+    settings_state: Res<SettingsMenuState>,
+    controls_state: Res<ControlsMenuState>,
+) {
+    if !actions.just_pressed(InputAction::CloseMenu) || settings_state.open || controls_state.open {
+        return;
+    }
+    match pause_state.0 {
+        PauseState::Paused => next_pause_state.0 = Some(PauseState::Running),
+        PauseState::Running if ui_state.0 == UIState::Closed => {
+            next_pause_state.0 = Some(PauseState::Paused)
+        }
+        PauseState::Running => {}
+    }
+}
+
+/// Stops `Time`, so timers and the fixed timestep freeze along with the gameplay sets
+pub fn pause_simulation(mut time: ResMut<Time>, mut rapier_config: ResMut<RapierConfiguration>) {
+    time.pause();
+    rapier_config.physics_pipeline_active = false;
+}
+
+pub fn resume_simulation(mut time: ResMut<Time>, mut rapier_config: ResMut<RapierConfiguration>) {
+    time.unpause();
+    rapier_config.physics_pipeline_active = true;
+}
+
+pub fn spawn_pause_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut pause_menu_state: ResMut<PauseMenuState>,
+) {
+    pause_menu_state.selected = 0;
+    let top = PAUSE_UI_SIZE.y / 2.;
+    let mut children = vec![spawn_menu_text(
+        &mut commands,
+        &asset_server,
+        "Paused".to_string(),
+        BLACK,
+        Vec3::new(-18., top - 9., 1.),
+    )];
+    for (i, button) in PauseMenuButton::iter().enumerate() {
+        let label = spawn_menu_text(
+            &mut commands,
+            &asset_server,
+            button.get_name().to_string(),
+            BLACK,
+            Vec3::new(-PAUSE_BUTTON_SIZE.x / 2. + 4., 0., 1.),
+        );
+        children.push(spawn_menu_button(
+            &mut commands,
+            PAUSE_BUTTON_SIZE,
+            Vec3::new(0., top - 26. - i as f32 * (PAUSE_BUTTON_SIZE.y + 4.), 1.),
+            LIGHT_BROWN,
+            button,
+            vec![label],
+        ));
+    }
+    let panel = spawn_menu_panel(&mut commands, PAUSE_UI_SIZE, 10.);
+    commands
+        .entity(panel)
+        .insert(PauseMenuUI)
+        .insert(Name::new("PAUSE UI"))
+        .push_children(&children);
+}
+
+pub fn despawn_pause_menu(mut commands: Commands, menu: Query<Entity, With<PauseMenuUI>>) {
+    for e in menu.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+/// Clicks pick a button directly, the menu actions move the highlight and press it.
+/// Dungeons aren't saved, so Save & Quit only warns while in one.
+pub fn handle_pause_menu_buttons(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    cursor_pos: Res<CursorPos>,
+    clicks: Res<MenuClicks>,
+    actions: ActionInput,
+    buttons: Query<(&Sprite, &GlobalTransform, &PauseMenuButton)>,
+    mut pause_menu_state: ResMut<PauseMenuState>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    mut settings_state: ResMut<SettingsMenuState>,
+    controls_state: Res<ControlsMenuState>,
+    mut save_timer: ResMut<SaveTimer>,
+    dungeon_check: Query<&Dungeon>,
+    menu: Query<Entity, With<PauseMenuUI>>,
+    warning: Query<(), With<PauseMenuWarning>>,
+) {
+    if settings_state.open || controls_state.open || pause_menu_state.quitting {
+        return;
+    }
+    let num_buttons = PauseMenuButton::iter().count();
+    if actions.just_pressed(InputAction::MenuDown) {
+        pause_menu_state.selected = (pause_menu_state.selected + 1) % num_buttons;
+    } else if actions.just_pressed(InputAction::MenuUp) {
+        pause_menu_state.selected = (pause_menu_state.selected + num_buttons - 1) % num_buttons;
+    }
+    let pressed = if actions.just_pressed(InputAction::MenuSelect) {
+        PauseMenuButton::iter().nth(pause_menu_state.selected)
+    } else if clicks.select.just_pressed {
+        get_menu_button_at_cursor(&cursor_pos, &buttons).copied()
+    } else {
+        None
+    };
+    match pressed {
+        Some(PauseMenuButton::Resume) => next_pause_state.0 = Some(PauseState::Running),
+        Some(PauseMenuButton::Settings) => settings_state.open(),
+        Some(PauseMenuButton::SaveAndQuit) if dungeon_check.get_single().is_ok() => {
+            let Ok(menu) = menu.get_single() else {
+                return;
+            };
+            if !warning.is_empty() {
+                return;
+            }
+            let text = spawn_menu_text(
+                &mut commands,
+                &asset_server,
+                "Can't save here".to_string(),
+                RED,
+                Vec3::new(-PAUSE_BUTTON_SIZE.x / 2., -PAUSE_UI_SIZE.y / 2. + 6., 1.),
+            );
+            commands
+                .entity(text)
+                .insert(PauseMenuWarning)
+                .set_parent(menu);
+        }
+        Some(PauseMenuButton::SaveAndQuit) => {
+            save_timer.request_save();
+            pause_menu_state.quitting = true;
+        }
+        None => {}
+    }
+}
+
+pub fn highlight_selected_pause_button(
+    pause_menu_state: Res<PauseMenuState>,
+    mut buttons: Query<(&mut Sprite, &PauseMenuButton)>,
+) {
+    let selected = PauseMenuButton::iter().nth(pause_menu_state.selected);
+    for (mut sprite, button) in buttons.iter_mut() {
+        sprite.color = if Some(*button) == selected {
+            YELLOW_2
+        } else {
+            LIGHT_BROWN
+        };
+    }
+}
+
+/// Runs after the save requested by Save & Quit, the save file is kept so Start picks
+/// the run back up. If nothing was written the run stays, rather than losing progress.
+pub fn quit_to_main_menu(
+    mut commands: Commands,
+    everything: Query<Entity, RunEntityFilter>,
+    mut pause_menu_state: ResMut<PauseMenuState>,
+    save_timer: Res<SaveTimer>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    pause_menu_state.quitting = false;
+    if !pause_menu_state.skip_save && !save_timer.was_save_written() {
+        println!("Save was not written, staying in the run");
+        return;
+    }
+    pause_menu_state.skip_save = false;
+    println!("Saved and quit, Sending to main menu");
+    teardown_run(&mut commands, &everything);
+    next_state.0 = Some(GameState::MainMenu);
+    next_pause_state.0 = Some(PauseState::Running);
+}
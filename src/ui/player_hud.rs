@@ -6,12 +6,11 @@ use crate::{
     colors::{BLACK, BLUE, RED, YELLOW},
     inventory::Inventory,
     player::{levels::PlayerLevel, Player},
-    GAME_HEIGHT, GAME_WIDTH,
 };
 
 use super::{
     interactions::Interaction, spawn_inv_slot, InventorySlotType, InventoryState, InventoryUI,
-    UIElement, UIState,
+    ScreenAnchor, UIElement, UIState,
 };
 
 #[derive(Component)]
@@ -37,17 +36,14 @@ pub fn setup_bars_ui(mut commands: Commands, graphics: Res<Graphics>) {
                 ..Default::default()
             },
             transform: Transform {
-                translation: Vec3::new(
-                    (-GAME_WIDTH + 91.) / 2.,
-                    (GAME_HEIGHT - 15.) / 2. - 12.,
-                    10.,
-                ),
+                translation: Vec3::new(0., 0., 10.),
                 scale: Vec3::new(1., 1., 1.),
                 ..Default::default()
             },
             ..Default::default()
         })
         .insert(Name::new("HUD FRAME"))
+        .insert(ScreenAnchor::new(Anchor::TopLeft, Vec2::new(45.5, -19.5)))
         .insert(RenderLayers::from_layers(&[3]))
         .id();
     let inner_health = commands
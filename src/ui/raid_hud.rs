@@ -0,0 +1,53 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+
+use crate::{colors::RED, enemy::raid::RaidTracker};
+
+use super::ScreenAnchor;
+
+#[derive(Component)]
+pub struct RaidWaveText;
+
+pub fn setup_raid_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/Kitchen Sink.ttf"),
+                    font_size: 8.0,
+                    color: RED,
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            transform: Transform {
+                translation: Vec3::new(0., 0., 1.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..default()
+        },
+        Name::new("RAID WAVE TEXT"),
+        ScreenAnchor::new(Anchor::TopCenter, Vec2::new(0., -12.)),
+        RaidWaveText,
+        RenderLayers::from_layers(&[3]),
+    ));
+}
+
+pub fn update_raid_hud(
+    raid_tracker: Res<RaidTracker>,
+    mut query: Query<&mut Text, With<RaidWaveText>>,
+) {
+    if !raid_tracker.is_changed() {
+        return;
+    }
+    for mut text in query.iter_mut() {
+        text.sections[0].value = if raid_tracker.active {
+            format!(
+                "RAID - Wave {}/{} - {} left",
+                raid_tracker.wave, raid_tracker.num_waves, raid_tracker.raiders_left
+            )
+        } else {
+            "".to_string()
+        };
+    }
+}
@@ -0,0 +1,640 @@
+use bevy::{
+    input::mouse::MouseWheel, prelude::*, render::view::RenderLayers, sprite::Anchor,
+    window::ReceivedCharacter,
+};
+
+use crate::{
+    action_map::{ActionInput, InputAction},
+    assets::Graphics,
+    colors::{_WHITE, BLACK, DARK_BROWN, DARK_GREEN, GREY, LIGHT_BROWN, RED, YELLOW_2},
+    container::Container,
+    inputs::CursorPos,
+    inventory::{Inventory, ItemStack},
+    item::{
+        get_ingredient_count, get_max_crafts, get_nearby_chests, object_actions::ObjectAction,
+        CraftRequestEvent, CraftingTracker, RecipeUnlock, Recipes, WorldObject,
+    },
+    player::Player,
+};
+
+use super::{
+    crafting_ui::CraftingContainerType, spawn_item_stack_icon, ui_helpers, ChestContainer,
+    Interactable, Interaction, MenuClicks, ScreenOverlay, UIState, RECIPE_BOOK_UI_SIZE,
+};
+
+pub const RECIPE_BOOK_ROWS: usize = 6;
+const RECIPE_ROW_HEIGHT: f32 = 20.;
+/// How close the player needs to be to a station to craft its recipes from the book
+const CRAFTING_STATION_RANGE: f32 = 48.;
+/// Amounts the craft buttons queue, stepped through with the quantity buttons.
+/// `usize::MAX` crafts as many as the ingredients allow.
+const CRAFT_QUANTITIES: [usize; 5] = [1, 5, 10, 20, usize::MAX];
+const QUANTITY_WIDTH: f32 = 44.;
+
+#[derive(Resource, Default, Debug)]
+pub struct RecipeBookState {
+    pub search: String,
+    pub search_focused: bool,
+    pub filter: Option<CraftingContainerType>,
+    pub scroll: usize,
+    /// Index into `CRAFT_QUANTITIES`, kept while the book is closed
+    pub quantity: usize,
+    pub dirty: bool,
+}
+
+impl RecipeBookState {
+    pub fn get_craft_quantity(&self) -> usize {
+        CRAFT_QUANTITIES[self.quantity]
+    }
+}
+
+#[derive(Component)]
+pub struct RecipeBookUI;
+#[derive(Component)]
+pub struct RecipeBookRows;
+#[derive(Component)]
+pub struct RecipeBookSearchText;
+#[derive(Component)]
+pub struct RecipeBookQuantityText;
+
+#[derive(Component, Clone, Debug)]
+pub enum RecipeBookButton {
+    Search,
+    Filter(Option<CraftingContainerType>),
+    QuantityDown,
+    QuantityUp,
+    Craft(WorldObject),
+}
+
+const FILTERS: [(&str, Option<CraftingContainerType>); 6] = [
+    ("All", None),
+    ("Inv", Some(CraftingContainerType::Inventory)),
+    ("Table", Some(CraftingContainerType::CraftingTable)),
+    ("Anvil", Some(CraftingContainerType::Anvil)),
+    ("Pot", Some(CraftingContainerType::Cauldron)),
+    ("Alch", Some(CraftingContainerType::AlchemyTable)),
+];
+
+fn spawn_text(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    text: String,
+    color: Color,
+    translation: Vec3,
+) -> Entity {
+    commands
+        .spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    text,
+                    TextStyle {
+                        font: asset_server.load("fonts/Kitchen Sink.ttf"),
+                        font_size: 8.0,
+                        color,
+                    },
+                ),
+                text_anchor: Anchor::CenterLeft,
+                transform: Transform {
+                    translation,
+                    scale: Vec3::new(1., 1., 1.),
+                    ..Default::default()
+                },
+                ..default()
+            },
+            RenderLayers::from_layers(&[3]),
+        ))
+        .id()
+}
+
+fn spawn_text_button(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    text: &str,
+    size: Vec2,
+    translation: Vec3,
+    button: RecipeBookButton,
+) -> Entity {
+    let label = spawn_text(
+        commands,
+        asset_server,
+        text.to_string(),
+        BLACK,
+        Vec3::new(-size.x / 2. + 2., 0., 1.),
+    );
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: LIGHT_BROWN,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform {
+                    translation,
+                    scale: Vec3::new(1., 1., 1.),
+                    ..Default::default()
+                },
+                ..default()
+            },
+            Interactable::default(),
+            button,
+            RenderLayers::from_layers(&[3]),
+            Name::new("RECIPE BOOK BUTTON"),
+        ))
+        .push_children(&[label])
+        .id()
+}
+
+/// Opens the book from any other UI, or closes it if it's already open
+pub fn toggle_recipe_book(
+    ui_state: Res<State<UIState>>,
+    mut next_ui_state: ResMut<NextState<UIState>>,
+    actions: ActionInput,
+) {
+    if !actions.just_pressed(InputAction::RecipeBook) {
+        return;
+    }
+    if ui_state.0 == UIState::RecipeBook {
+        next_ui_state.set(UIState::Closed);
+    } else {
+        next_ui_state.set(UIState::RecipeBook);
+    }
+}
+
+pub fn setup_recipe_book_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut book_state: ResMut<RecipeBookState>,
+) {
+    book_state.search_focused = false;
+    book_state.scroll = 0;
+    book_state.dirty = true;
+
+    let overlay = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(146. / 255., 116. / 255., 65. / 255., 0.3),
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(0., 0., -2.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..default()
+        })
+        .insert(RenderLayers::from_layers(&[3]))
+        .insert(ScreenOverlay::new(10.))
+        .insert(Name::new("overlay"))
+        .id();
+    let border = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: DARK_BROWN,
+                custom_size: Some(RECIPE_BOOK_UI_SIZE + Vec2::new(4., 4.)),
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(0., 0., -1.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..default()
+        })
+        .insert(RenderLayers::from_layers(&[3]))
+        .id();
+    let book_e = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: _WHITE,
+                custom_size: Some(RECIPE_BOOK_UI_SIZE),
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(0., 0., 10.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..default()
+        })
+        .insert(RecipeBookUI)
+        .insert(Name::new("RECIPE BOOK UI"))
+        .insert(UIState::RecipeBook)
+        .insert(RenderLayers::from_layers(&[3]))
+        .id();
+
+    let top = RECIPE_BOOK_UI_SIZE.y / 2.;
+    let right = RECIPE_BOOK_UI_SIZE.x / 2. - 4.;
+    let search = spawn_text_button(
+        &mut commands,
+        &asset_server,
+        "",
+        Vec2::new(RECIPE_BOOK_UI_SIZE.x - 8. - QUANTITY_WIDTH, 10.),
+        Vec3::new(-QUANTITY_WIDTH / 2., top - 9., 1.),
+        RecipeBookButton::Search,
+    );
+    let search_text = spawn_text(
+        &mut commands,
+        &asset_server,
+        "Search: ".to_string(),
+        BLACK,
+        Vec3::new(-RECIPE_BOOK_UI_SIZE.x / 2. + 6., top - 9., 2.),
+    );
+    commands
+        .entity(search_text)
+        .insert(RecipeBookSearchText)
+        .insert(Name::new("RECIPE SEARCH TEXT"));
+
+    let quantity_down = spawn_text_button(
+        &mut commands,
+        &asset_server,
+        "-",
+        Vec2::new(10., 10.),
+        Vec3::new(right - QUANTITY_WIDTH + 7., top - 9., 1.),
+        RecipeBookButton::QuantityDown,
+    );
+    let quantity_up = spawn_text_button(
+        &mut commands,
+        &asset_server,
+        "+",
+        Vec2::new(10., 10.),
+        Vec3::new(right - 5., top - 9., 1.),
+        RecipeBookButton::QuantityUp,
+    );
+    let quantity_text = spawn_text(
+        &mut commands,
+        &asset_server,
+        String::new(),
+        BLACK,
+        Vec3::new(right - QUANTITY_WIDTH + 14., top - 9., 2.),
+    );
+    commands
+        .entity(quantity_text)
+        .insert(RecipeBookQuantityText)
+        .insert(Name::new("RECIPE QUANTITY TEXT"));
+
+    let mut children = vec![
+        overlay,
+        border,
+        search,
+        search_text,
+        quantity_down,
+        quantity_up,
+        quantity_text,
+    ];
+    let filter_width = (RECIPE_BOOK_UI_SIZE.x - 8.) / FILTERS.len() as f32;
+    for (i, (label, filter)) in FILTERS.iter().enumerate() {
+        children.push(spawn_text_button(
+            &mut commands,
+            &asset_server,
+            label,
+            Vec2::new(filter_width - 2., 10.),
+            Vec3::new(
+                -RECIPE_BOOK_UI_SIZE.x / 2. + 4. + filter_width * (i as f32 + 0.5),
+                top - 22.,
+                1.,
+            ),
+            RecipeBookButton::Filter(filter.clone()),
+        ));
+    }
+    let rows = commands
+        .spawn((
+            SpatialBundle::default(),
+            RecipeBookRows,
+            Name::new("RECIPE ROWS"),
+        ))
+        .id();
+    children.push(rows);
+    commands.entity(book_e).push_children(&children);
+}
+
+/// Recipes matching the search text (by result or ingredient name) and station filter,
+/// known ones first. Locked recipes are only listed when not searching,
+/// so their names are not given away.
+pub fn get_recipe_book_entries(
+    book_state: &RecipeBookState,
+    craft_tracker: &CraftingTracker,
+    recipes: &Recipes,
+) -> Vec<(WorldObject, bool)> {
+    let search = book_state.search.to_lowercase();
+    let mut entries: Vec<(WorldObject, bool)> = recipes
+        .crafting_list
+        .iter()
+        .filter(|(result, (ingredients, station, _))| {
+            let matches_filter = book_state
+                .filter
+                .as_ref()
+                .map_or(true, |filter| filter == station);
+            let matches_search = search.is_empty()
+                || (craft_tracker.is_recipe_known(result)
+                    && (result.to_string().to_lowercase().contains(&search)
+                        || ingredients
+                            .iter()
+                            .any(|i| i.item.to_string().to_lowercase().contains(&search))));
+            matches_filter && matches_search
+        })
+        .map(|(result, _)| (*result, craft_tracker.is_recipe_known(result)))
+        .collect();
+    entries.sort_by_key(|(obj, known)| (!known, obj.to_string()));
+    entries
+}
+
+/// Locked recipes only show the result's outline, without its count or colours
+fn spawn_silhouette_icon(
+    commands: &mut Commands,
+    graphics: &Graphics,
+    obj: WorldObject,
+    translation: Vec3,
+) -> Entity {
+    let mut sprite = graphics
+        .icons
+        .as_ref()
+        .unwrap()
+        .get(&obj)
+        .or_else(|| graphics.spritesheet_map.as_ref().unwrap().get(&obj))
+        .unwrap_or_else(|| panic!("No graphic for object {:?}", obj))
+        .clone();
+    sprite.color = BLACK;
+    commands
+        .spawn((
+            SpriteSheetBundle {
+                sprite,
+                texture_atlas: graphics.texture_atlas.as_ref().unwrap().clone(),
+                transform: Transform::from_translation(translation),
+                ..Default::default()
+            },
+            RenderLayers::from_layers(&[3]),
+        ))
+        .id()
+}
+
+/// What a locked recipe row says about how to learn it
+fn get_unlock_hint(result: &WorldObject, recipes: &Recipes) -> String {
+    match recipes.unlocks.get(result) {
+        Some(RecipeUnlock::KeyIngredient(key)) => format!("Find {}", key),
+        Some(RecipeUnlock::Blueprint) => "Read a Blueprint".to_string(),
+        Some(RecipeUnlock::Level(level)) => format!("Reach Level {}", level),
+        None => "Find an ingredient".to_string(),
+    }
+}
+
+pub fn update_recipe_book_rows(
+    mut commands: Commands,
+    graphics: Res<Graphics>,
+    asset_server: Res<AssetServer>,
+    mut book_state: ResMut<RecipeBookState>,
+    craft_tracker: Res<CraftingTracker>,
+    recipes: Res<Recipes>,
+    inv: Query<&Inventory>,
+    changed_inv: Query<(), Changed<Inventory>>,
+    rows: Query<Entity, With<RecipeBookRows>>,
+    mut search_text: Query<&mut Text, With<RecipeBookSearchText>>,
+    mut quantity_text: Query<
+        &mut Text,
+        (With<RecipeBookQuantityText>, Without<RecipeBookSearchText>),
+    >,
+    player_t: Query<&GlobalTransform, With<Player>>,
+    stations: Query<(&GlobalTransform, &ObjectAction)>,
+    chests: Query<(Entity, &GlobalTransform, &ChestContainer)>,
+    chest_option: Option<Res<ChestContainer>>,
+) {
+    let Ok(rows_e) = rows.get_single() else {
+        return;
+    };
+    if !book_state.dirty && changed_inv.is_empty() && !craft_tracker.is_changed() {
+        return;
+    }
+    book_state.dirty = false;
+    if let Ok(mut text) = search_text.get_single_mut() {
+        text.sections[0].value = format!(
+            "Search: {}{}",
+            book_state.search,
+            if book_state.search_focused { "_" } else { "" }
+        );
+    }
+    if let Ok(mut text) = quantity_text.get_single_mut() {
+        text.sections[0].value = match book_state.get_craft_quantity() {
+            usize::MAX => "Max".to_string(),
+            quantity => format!("x{}", quantity),
+        };
+    }
+    commands.entity(rows_e).despawn_descendants();
+
+    let inv = inv.single();
+    let player_pos = player_t.single().translation().truncate();
+    let nearby_chests: Vec<&Container> =
+        get_nearby_chests(player_pos, chests.iter(), chest_option.as_deref())
+            .into_iter()
+            .map(|e| &chests.get(e).unwrap().2.items)
+            .collect();
+    let is_near_station = |station: &CraftingContainerType| {
+        station == &CraftingContainerType::Inventory
+            || stations.iter().any(|(t, action)| match action {
+                ObjectAction::Crafting(t_type) => {
+                    t_type == station
+                        && t.translation().truncate().distance(player_pos) <= CRAFTING_STATION_RANGE
+                }
+                _ => false,
+            })
+    };
+
+    let entries = get_recipe_book_entries(&book_state, &craft_tracker, &recipes);
+    book_state.scroll = book_state
+        .scroll
+        .min(entries.len().saturating_sub(RECIPE_BOOK_ROWS));
+    let top = RECIPE_BOOK_UI_SIZE.y / 2. - 40.;
+    let left = -RECIPE_BOOK_UI_SIZE.x / 2.;
+    let mut children = vec![];
+    for (row, (result, known)) in entries
+        .iter()
+        .skip(book_state.scroll)
+        .take(RECIPE_BOOK_ROWS)
+        .enumerate()
+    {
+        let (ingredients, station, yield_count) = recipes.crafting_list.get(result).unwrap();
+        let y = top - row as f32 * RECIPE_ROW_HEIGHT;
+
+        if !known {
+            children.push(spawn_silhouette_icon(
+                &mut commands,
+                &graphics,
+                *result,
+                Vec3::new(left + 14., y, 1.),
+            ));
+            children.push(spawn_text(
+                &mut commands,
+                &asset_server,
+                format!("??? - {}", get_unlock_hint(result, &recipes)),
+                GREY,
+                Vec3::new(left + 28., y, 1.),
+            ));
+            continue;
+        }
+
+        let icon = spawn_item_stack_icon(
+            &mut commands,
+            &graphics,
+            &ItemStack::crate_icon_stack(*result).copy_with_count(*yield_count),
+            &asset_server,
+        );
+        commands
+            .entity(icon)
+            .insert(Transform::from_translation(Vec3::new(left + 14., y, 1.)));
+        children.push(icon);
+
+        for (i, ingredient) in ingredients.iter().enumerate() {
+            let x = left + 34. + i as f32 * 30.;
+            let icon = spawn_item_stack_icon(
+                &mut commands,
+                &graphics,
+                &ItemStack::crate_icon_stack(ingredient.item),
+                &asset_server,
+            );
+            commands
+                .entity(icon)
+                .insert(Transform::from_translation(Vec3::new(x, y, 1.)));
+            let owned = get_ingredient_count(ingredient.item, &inv.items, &nearby_chests);
+            let count_text = spawn_text(
+                &mut commands,
+                &asset_server,
+                format!("{}/{}", owned, ingredient.count),
+                if owned >= ingredient.count {
+                    DARK_GREEN
+                } else {
+                    RED
+                },
+                Vec3::new(x + 8., y - 4., 1.),
+            );
+            children.push(icon);
+            children.push(count_text);
+        }
+
+        let max_crafts = get_max_crafts(ingredients, &inv.items, &nearby_chests);
+        if max_crafts > 0 && is_near_station(station) {
+            children.push(spawn_text_button(
+                &mut commands,
+                &asset_server,
+                "Make",
+                Vec2::new(20., 10.),
+                Vec3::new(-left - 14., y, 1.),
+                RecipeBookButton::Craft(*result),
+            ));
+        }
+    }
+    if entries.is_empty() {
+        children.push(spawn_text(
+            &mut commands,
+            &asset_server,
+            "No recipes found".to_string(),
+            YELLOW_2,
+            Vec3::new(left + 6., top, 1.),
+        ));
+    }
+    commands.entity(rows_e).push_children(&children);
+}
+
+pub fn handle_recipe_book_buttons(
+    cursor_pos: Res<CursorPos>,
+    clicks: Res<MenuClicks>,
+    ui_sprites: Query<(Entity, &Sprite, &GlobalTransform), With<Interactable>>,
+    mut buttons: Query<(Entity, &mut Interactable, &RecipeBookButton)>,
+    mut book_state: ResMut<RecipeBookState>,
+    mut craft_event: EventWriter<CraftRequestEvent>,
+) {
+    let hit_test = ui_helpers::pointcast_2d(&cursor_pos, &ui_sprites, None);
+    let left_mouse_pressed = clicks.select.just_pressed;
+    if left_mouse_pressed && book_state.search_focused {
+        book_state.search_focused = false;
+        book_state.dirty = true;
+    }
+
+    for (e, mut interactable, button) in buttons.iter_mut() {
+        match hit_test {
+            Some(hit_ent) if hit_ent.0 == e => match interactable.current() {
+                Interaction::None => {
+                    interactable.change(Interaction::Hovering);
+                }
+                Interaction::Hovering => {
+                    if !left_mouse_pressed {
+                        continue;
+                    }
+                    match button {
+                        RecipeBookButton::Search => {
+                            book_state.search_focused = true;
+                        }
+                        RecipeBookButton::Filter(filter) => {
+                            book_state.filter = filter.clone();
+                            book_state.scroll = 0;
+                        }
+                        RecipeBookButton::QuantityDown => {
+                            book_state.quantity = book_state.quantity.saturating_sub(1);
+                        }
+                        RecipeBookButton::QuantityUp => {
+                            book_state.quantity =
+                                (book_state.quantity + 1).min(CRAFT_QUANTITIES.len() - 1);
+                        }
+                        RecipeBookButton::Craft(obj) => {
+                            // fewer are crafted if there aren't enough ingredients for all of them
+                            craft_event.send(CraftRequestEvent {
+                                obj: *obj,
+                                count: book_state.get_craft_quantity(),
+                                station_pos: None,
+                            });
+                        }
+                    }
+                    book_state.dirty = true;
+                }
+                _ => (),
+            },
+            _ => {
+                let Interaction::Hovering = interactable.current() else {
+                    continue;
+                };
+                interactable.change(Interaction::None);
+            }
+        }
+    }
+}
+
+pub fn handle_recipe_book_scroll(
+    mut scroll_events: EventReader<MouseWheel>,
+    mut book_state: ResMut<RecipeBookState>,
+) {
+    for scroll in scroll_events.iter() {
+        if scroll.y > 0. {
+            book_state.scroll = book_state.scroll.saturating_sub(1);
+        } else if scroll.y < 0. {
+            book_state.scroll += 1;
+        }
+        book_state.dirty = true;
+    }
+}
+
+/// Runs before the rest of the game reads input, so typing in the search box
+/// does not also trigger hotkeys like opening the inventory.
+pub fn handle_recipe_book_text_input(
+    mut book_state: ResMut<RecipeBookState>,
+    mut char_events: EventReader<ReceivedCharacter>,
+    mut key_input: ResMut<Input<KeyCode>>,
+) {
+    if !book_state.search_focused {
+        char_events.clear();
+        return;
+    }
+    for event in char_events.iter() {
+        if !event.char.is_control() {
+            book_state.search.push(event.char);
+            book_state.scroll = 0;
+            book_state.dirty = true;
+        }
+    }
+    if key_input.just_pressed(KeyCode::Back) {
+        book_state.search.pop();
+        book_state.dirty = true;
+    }
+    if key_input.just_pressed(KeyCode::Return) || key_input.just_pressed(KeyCode::Escape) {
+        book_state.search_focused = false;
+        book_state.dirty = true;
+    }
+    key_input.reset_all();
+}
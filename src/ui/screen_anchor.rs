@@ -0,0 +1,67 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{GameViewport, GAME_HEIGHT, GAME_WIDTH};
+
+/// Keeps a HUD element at an offset from an edge or corner of the screen,
+/// so it stays in place however wide or tall the view is
+#[derive(Component, Clone, Debug)]
+pub struct ScreenAnchor {
+    pub anchor: Anchor,
+    /// Added to the anchor point, so offsets from the top and right edges are negative
+    pub offset: Vec2,
+}
+
+impl ScreenAnchor {
+    pub fn new(anchor: Anchor, offset: Vec2) -> Self {
+        Self { anchor, offset }
+    }
+    pub fn get_position(&self, view_size: Vec2) -> Vec2 {
+        self.anchor.as_vec() * view_size + self.offset
+    }
+}
+
+/// Tints and screen effects that cover the whole view, with some margin past its edges
+#[derive(Component, Clone, Debug, Default)]
+pub struct ScreenOverlay {
+    pub margin: f32,
+}
+
+impl ScreenOverlay {
+    pub fn new(margin: f32) -> Self {
+        Self { margin }
+    }
+    pub fn get_size(&self, view_size: Vec2) -> Vec2 {
+        view_size + Vec2::splat(self.margin)
+    }
+}
+
+pub fn update_screen_anchors(
+    viewport: Res<GameViewport>,
+    mut anchored: Query<(Ref<ScreenAnchor>, &mut Transform)>,
+) {
+    for (anchor, mut t) in anchored.iter_mut() {
+        if !viewport.is_changed() && !anchor.is_changed() {
+            continue;
+        }
+        let pos = anchor.get_position(viewport.size);
+        t.translation = pos.extend(t.translation.z);
+    }
+}
+
+/// Sprites are resized, and meshes built at the default view size are scaled
+pub fn update_screen_overlays(
+    viewport: Res<GameViewport>,
+    mut overlays: Query<(Ref<ScreenOverlay>, Option<&mut Sprite>, &mut Transform)>,
+) {
+    for (overlay, sprite, mut t) in overlays.iter_mut() {
+        if !viewport.is_changed() && !overlay.is_changed() {
+            continue;
+        }
+        let size = overlay.get_size(viewport.size);
+        if let Some(mut sprite) = sprite {
+            sprite.custom_size = Some(size);
+        } else {
+            t.scale = (size / Vec2::new(GAME_WIDTH, GAME_HEIGHT)).extend(1.);
+        }
+    }
+}
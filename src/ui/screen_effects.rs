@@ -14,6 +14,8 @@ use crate::{
     GAME_HEIGHT, GAME_WIDTH,
 };
 
+use super::ScreenOverlay;
+
 #[derive(Component)]
 pub struct HealthScreenEffect;
 
@@ -66,6 +68,7 @@ pub fn setup_screen_effects(
         }))),
         hp_effect_material.clone(),
         HealthScreenEffect,
+        ScreenOverlay::default(),
         RenderLayers::from_layers(&[3]),
         Name::new("hp screen effect"),
         SpatialBundle::from_transform(Transform::from_xyz(0., 0., 1.)),
@@ -77,6 +80,7 @@ pub fn setup_screen_effects(
         }))),
         hunger_effect_material.clone(),
         HungerScreenEffect,
+        ScreenOverlay::default(),
         RenderLayers::from_layers(&[3]),
         Name::new("hunger screen effect"),
         SpatialBundle::from_transform(Transform::from_xyz(0., 0., 1.)),
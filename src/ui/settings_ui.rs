@@ -0,0 +1,281 @@
+use bevy::prelude::*;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::{
+    action_map::{ActionInput, InputAction},
+    colors::{BLACK, LIGHT_BROWN},
+    inputs::CursorPos,
+    settings::{Settings, RESOLUTIONS},
+};
+
+use super::{
+    ui_helpers::{get_menu_button_at_cursor, spawn_menu_button, spawn_menu_panel, spawn_menu_text},
+    ControlsMenuState, SETTINGS_UI_SIZE,
+};
+
+const SETTINGS_ROW_HEIGHT: f32 = 12.;
+const VOLUME_STEP: f32 = 0.1;
+
+/// Like the controls screen, opened over the main menu or the game
+#[derive(Resource, Default, Debug)]
+pub struct SettingsMenuState {
+    pub open: bool,
+    pub dirty: bool,
+}
+
+impl SettingsMenuState {
+    pub fn open(&mut self) {
+        self.open = true;
+        self.dirty = true;
+    }
+}
+
+#[derive(Component)]
+pub struct SettingsMenuUI;
+#[derive(Component)]
+pub struct SettingsRows;
+
+/// One row per setting, left click cycles forward and right click back
+#[derive(Clone, Copy, Debug, PartialEq, EnumIter)]
+pub enum SettingsOption {
+    Resolution,
+    Fullscreen,
+    VSync,
+    Scaling,
+    View,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    ScreenShake,
+    DamageNumbers,
+    AimAssist,
+}
+
+#[derive(Component, Clone, Debug)]
+pub enum SettingsButton {
+    Option(SettingsOption),
+    Controls,
+    Back,
+}
+
+impl SettingsOption {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SettingsOption::Resolution => "Resolution",
+            SettingsOption::Fullscreen => "Fullscreen",
+            SettingsOption::VSync => "VSync",
+            SettingsOption::Scaling => "Scaling",
+            SettingsOption::View => "Wide View",
+            SettingsOption::MasterVolume => "Master Volume",
+            SettingsOption::MusicVolume => "Music Volume",
+            SettingsOption::SfxVolume => "SFX Volume",
+            SettingsOption::ScreenShake => "Screen Shake",
+            SettingsOption::DamageNumbers => "Damage Numbers",
+            SettingsOption::AimAssist => "Aim Assist",
+        }
+    }
+    pub fn get_value_text(&self, settings: &Settings) -> String {
+        let on_off = |on: bool| (if on { "On" } else { "Off" }).to_string();
+        let percent = |volume: f32| format!("{}%", (volume * 100.).round());
+        match self {
+            SettingsOption::Resolution => {
+                format!("{}x{}", settings.resolution.0, settings.resolution.1)
+            }
+            SettingsOption::Fullscreen => on_off(settings.fullscreen),
+            SettingsOption::VSync => on_off(settings.vsync),
+            SettingsOption::Scaling if settings.integer_scaling => "Integer".to_string(),
+            SettingsOption::Scaling => "Fit".to_string(),
+            SettingsOption::View if settings.expand_view => "Expand".to_string(),
+            SettingsOption::View => "Letterbox".to_string(),
+            SettingsOption::MasterVolume => percent(settings.master_volume),
+            SettingsOption::MusicVolume => percent(settings.music_volume),
+            SettingsOption::SfxVolume => percent(settings.sfx_volume),
+            SettingsOption::ScreenShake => on_off(settings.screen_shake),
+            SettingsOption::DamageNumbers => on_off(settings.damage_numbers),
+            SettingsOption::AimAssist => on_off(settings.aim_assist),
+        }
+    }
+    /// Moves the setting to its next value, or its previous one if `step` is negative
+    pub fn cycle(&self, settings: &mut Settings, step: i32) {
+        let step_volume = |volume: &mut f32| {
+            let steps = (1. / VOLUME_STEP).round() as i32 + 1;
+            let current = (*volume / VOLUME_STEP).round() as i32;
+            *volume = (current + step).rem_euclid(steps) as f32 * VOLUME_STEP;
+        };
+        match self {
+            SettingsOption::Resolution => {
+                let current = RESOLUTIONS
+                    .iter()
+                    .position(|r| *r == settings.resolution)
+                    .unwrap_or(0) as i32;
+                let next = (current + step).rem_euclid(RESOLUTIONS.len() as i32);
+                settings.resolution = RESOLUTIONS[next as usize];
+            }
+            SettingsOption::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            SettingsOption::VSync => settings.vsync = !settings.vsync,
+            SettingsOption::Scaling => settings.integer_scaling = !settings.integer_scaling,
+            SettingsOption::View => settings.expand_view = !settings.expand_view,
+            SettingsOption::MasterVolume => step_volume(&mut settings.master_volume),
+            SettingsOption::MusicVolume => step_volume(&mut settings.music_volume),
+            SettingsOption::SfxVolume => step_volume(&mut settings.sfx_volume),
+            SettingsOption::ScreenShake => settings.screen_shake = !settings.screen_shake,
+            SettingsOption::DamageNumbers => settings.damage_numbers = !settings.damage_numbers,
+            SettingsOption::AimAssist => settings.aim_assist = !settings.aim_assist,
+        }
+    }
+}
+
+pub fn is_settings_menu_closed(settings_state: Res<SettingsMenuState>) -> bool {
+    !settings_state.open
+}
+
+/// Spawns the screen when it opens, refreshes the values when a setting changes,
+/// and despawns it when it closes
+pub fn update_settings_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut settings_state: ResMut<SettingsMenuState>,
+    settings: Res<Settings>,
+    menu: Query<Entity, With<SettingsMenuUI>>,
+    rows: Query<Entity, With<SettingsRows>>,
+) {
+    if !settings_state.open {
+        for e in menu.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+    let top = SETTINGS_UI_SIZE.y / 2.;
+    let left = -SETTINGS_UI_SIZE.x / 2.;
+    if menu.get_single().is_err() {
+        let title = spawn_menu_text(
+            &mut commands,
+            &asset_server,
+            "Settings".to_string(),
+            BLACK,
+            Vec3::new(-22., top - 9., 1.),
+        );
+        let controls_label = spawn_menu_text(
+            &mut commands,
+            &asset_server,
+            "Controls".to_string(),
+            BLACK,
+            Vec3::new(-20., 0., 1.),
+        );
+        let controls = spawn_menu_button(
+            &mut commands,
+            Vec2::new(46., 10.),
+            Vec3::new(left + 30., -top + 9., 1.),
+            LIGHT_BROWN,
+            SettingsButton::Controls,
+            vec![controls_label],
+        );
+        let back_label = spawn_menu_text(
+            &mut commands,
+            &asset_server,
+            "Back".to_string(),
+            BLACK,
+            Vec3::new(-14., 0., 1.),
+        );
+        let back = spawn_menu_button(
+            &mut commands,
+            Vec2::new(34., 10.),
+            Vec3::new(-left - 24., -top + 9., 1.),
+            LIGHT_BROWN,
+            SettingsButton::Back,
+            vec![back_label],
+        );
+        let rows = commands
+            .spawn((
+                SpatialBundle::default(),
+                SettingsRows,
+                Name::new("SETTINGS ROWS"),
+            ))
+            .id();
+        let panel = spawn_menu_panel(&mut commands, SETTINGS_UI_SIZE, 15.);
+        commands
+            .entity(panel)
+            .insert(SettingsMenuUI)
+            .insert(Name::new("SETTINGS UI"))
+            .push_children(&[title, controls, back, rows]);
+        // the rows are filled in next frame, once the screen exists
+        settings_state.dirty = true;
+        return;
+    }
+    if !settings_state.dirty && !settings.is_changed() {
+        return;
+    }
+    let Ok(rows_e) = rows.get_single() else {
+        return;
+    };
+    settings_state.dirty = false;
+    commands.entity(rows_e).despawn_descendants();
+
+    let row_width = SETTINGS_UI_SIZE.x - 8.;
+    let children: Vec<Entity> = SettingsOption::iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let name = spawn_menu_text(
+                &mut commands,
+                &asset_server,
+                option.get_name().to_string(),
+                BLACK,
+                Vec3::new(-row_width / 2. + 4., 0., 1.),
+            );
+            let value = spawn_menu_text(
+                &mut commands,
+                &asset_server,
+                option.get_value_text(&settings),
+                BLACK,
+                Vec3::new(row_width / 2. - 52., 0., 1.),
+            );
+            spawn_menu_button(
+                &mut commands,
+                Vec2::new(row_width, SETTINGS_ROW_HEIGHT - 2.),
+                Vec3::new(0., top - 24. - i as f32 * SETTINGS_ROW_HEIGHT, 1.),
+                LIGHT_BROWN,
+                SettingsButton::Option(option),
+                vec![name, value],
+            )
+        })
+        .collect();
+    commands.entity(rows_e).push_children(&children);
+}
+
+pub fn handle_settings_menu_buttons(
+    cursor_pos: Res<CursorPos>,
+    mouse_input: Res<Input<MouseButton>>,
+    actions: ActionInput,
+    buttons: Query<(&Sprite, &GlobalTransform, &SettingsButton)>,
+    mut settings_state: ResMut<SettingsMenuState>,
+    mut controls_state: ResMut<ControlsMenuState>,
+    mut settings: ResMut<Settings>,
+) {
+    // the controls screen is drawn on top, and handles its own input
+    if !settings_state.open || controls_state.open {
+        return;
+    }
+    if actions.just_pressed(InputAction::CloseMenu) {
+        settings_state.open = false;
+        return;
+    }
+    let step = if mouse_input.just_pressed(MouseButton::Left) {
+        1
+    } else if mouse_input.just_pressed(MouseButton::Right) {
+        -1
+    } else {
+        return;
+    };
+    let Some(button) = get_menu_button_at_cursor(&cursor_pos, &buttons) else {
+        return;
+    };
+    match button {
+        SettingsButton::Option(option) => {
+            option.cycle(&mut settings, step);
+            settings.save();
+        }
+        SettingsButton::Controls => controls_state.open(),
+        SettingsButton::Back => settings_state.open = false,
+    }
+}
@@ -1,13 +1,15 @@
 use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
 
 use crate::{
+    action_map::{ActionInput, InputAction},
     assets::Graphics,
     colors::BLACK,
     player::stats::{PlayerStats, SkillPoints},
-    GAME_HEIGHT, GAME_WIDTH,
 };
 
-use super::{Interactable, ShowInvPlayerStatsEvent, UIElement, UIState, STATS_UI_SIZE};
+use super::{
+    Interactable, ScreenOverlay, ShowInvPlayerStatsEvent, UIElement, UIState, STATS_UI_SIZE,
+};
 
 #[derive(Component)]
 pub struct StatsUI;
@@ -41,7 +43,6 @@ pub fn setup_stats_ui(
         .spawn(SpriteBundle {
             sprite: Sprite {
                 color: Color::rgba(146. / 255., 116. / 255., 65. / 255., 0.3),
-                custom_size: Some(Vec2::new(GAME_WIDTH + 10., GAME_HEIGHT + 10.)),
                 ..default()
             },
             transform: Transform {
@@ -52,6 +53,7 @@ pub fn setup_stats_ui(
             ..default()
         })
         .insert(RenderLayers::from_layers(&[3]))
+        .insert(ScreenOverlay::new(10.))
         .insert(Name::new("overlay"))
         .id();
     let stats_e = commands
@@ -165,9 +167,9 @@ pub fn setup_stats_ui(
 
 pub fn toggle_stats_visibility(
     mut next_inv_state: ResMut<NextState<UIState>>,
-    key_input: ResMut<Input<KeyCode>>,
+    actions: ActionInput,
 ) {
-    if key_input.just_pressed(KeyCode::B) {
+    if actions.just_pressed(InputAction::Stats) {
         next_inv_state.set(UIState::Stats);
     }
 }
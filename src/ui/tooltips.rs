@@ -3,7 +3,9 @@ use bevy::{ prelude::*, render::view::RenderLayers, sprite::Anchor };
 use crate::{
     assets::Graphics,
     attributes::{
-        item_abilities::ItemAbility,
+        affixes::get_item_power,
+        item_abilities::ItemAbilityDefs,
+        set_bonuses::ActiveSetBonuses,
         Attack,
         BonusDamage,
         CritChance,
@@ -20,7 +22,7 @@ use crate::{
         Thorns,
         XpRateBonus,
     },
-    colors::{ BLACK, GOLD, GREY, LIGHT_GREEN },
+    colors::{ BLACK, GOLD, GREY, LIGHT_GREEN, RED },
     inventory::ItemStack,
     item::{ Recipes, WorldObject },
     player::Player,
@@ -62,6 +64,28 @@ pub struct ToolTipUpdateEvent {
 #[derive(Default)]
 pub struct TooltipTeardownEvent;
 
+/// Longest line an item name can take up at the top of a tooltip
+const TOOLTIP_NAME_MAX_CHARS: usize = 15;
+
+/// Splits long item names, like ones with affixes, over multiple lines
+fn wrap_tooltip_name(name: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for word in name.split_whitespace() {
+        let line_len = lines.last().map(|line| line.chars().count() + 1);
+        match (lines.last_mut(), line_len) {
+            (Some(line), Some(len)) if len + word.chars().count() <= TOOLTIP_NAME_MAX_CHARS => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    if lines.is_empty() {
+        lines.push(name.to_string());
+    }
+    lines
+}
+
 pub fn tick_tooltip_timer(time: Res<Time>, mut tooltip_manager: ResMut<TooltipsManager>) {
     if tooltip_manager.timer.finished() {
         return;
@@ -95,7 +119,8 @@ pub fn handle_spawn_inv_item_tooltip(
     inv: Query<Entity, With<InventoryUI>>,
     essence: Query<Entity, With<EssenceUI>>,
     cur_inv_state: Res<State<UIState>>,
-    recipes: Res<Recipes>
+    recipes: Res<Recipes>,
+    ability_defs: Res<ItemAbilityDefs>
 ) {
     for item in updates.iter() {
         let parent_inv_size = match cur_inv_state.0 {
@@ -107,10 +132,41 @@ pub fn handle_spawn_inv_item_tooltip(
             _ => unreachable!(),
         };
         let attributes = item.item_stack.attributes.get_tooltips();
-        // let durability = item.item_stack.attributes.get_durability_tooltip();
+        let has_durability = item.item_stack.attributes.has_durability();
+        let is_broken = item.item_stack.attributes.is_broken();
         let level = item.item_stack.metadata.level;
         let should_show_attributes = attributes.len() > 0 && !item.is_recipe;
-        let size = Vec2::new(93.0, 120.5);
+        let name_lines = wrap_tooltip_name(&item.item_stack.metadata.name);
+        let affix_lines: Vec<String> = if should_show_attributes {
+            item.item_stack.metadata.affixes
+                .iter()
+                .map(|affix| format!("{} T{}", affix.name, affix.tier))
+                .collect()
+        } else {
+            vec![]
+        };
+        let socket_lines: Vec<String> = if
+            should_show_attributes &&
+            recipes.upgradeable_items.contains(&item.item_stack.obj_type)
+        {
+            let gems = &item.item_stack.metadata.gems;
+            (0..usize::max(item.item_stack.rarity.get_num_sockets(), gems.len()))
+                .map(|i| {
+                    gems.get(i).map_or("[Empty Socket]".to_string(), |gem| format!("[{}]", gem))
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        let num_body_lines =
+            name_lines.len() +
+            (if should_show_attributes {
+                1 + attributes.len() + affix_lines.len() + socket_lines.len()
+            } else {
+                item.item_stack.metadata.desc.len()
+            });
+        // grow the tooltip if the lines would run into the durability and level lines
+        let size = Vec2::new(93.0, f32::max(120.5, (num_body_lines as f32) * 10.0 + 44.0));
         let tooltip = commands
             .spawn((
                 SpriteBundle {
@@ -136,12 +192,35 @@ pub fn handle_spawn_inv_item_tooltip(
             .id();
 
         let mut tooltip_text: Vec<(String, f32)> = vec![];
-        tooltip_text.push((item.item_stack.metadata.name.clone(), 0.0));
+        let mut durability_line = None;
+        for name_line in name_lines.iter() {
+            tooltip_text.push((name_line.clone(), 0.0));
+        }
+        let first_attribute_line = name_lines.len() + (if should_show_attributes { 1 } else { 0 });
+        let first_affix_line = first_attribute_line + attributes.len();
+        let first_socket_line = first_affix_line + affix_lines.len();
+        let affix_line_range = first_affix_line..first_socket_line;
+        let socket_line_range = first_socket_line..first_socket_line + socket_lines.len();
 
         if should_show_attributes {
+            tooltip_text.push((format!("Power {}", get_item_power(&item.item_stack)), 0.0));
             for a in attributes.iter().clone() {
                 tooltip_text.push((a.to_string(), 0.0));
             }
+            for a in affix_lines.iter() {
+                tooltip_text.push((a.clone(), 0.0));
+            }
+            for socket in socket_lines.iter() {
+                tooltip_text.push((socket.clone(), 0.0));
+            }
+            if has_durability {
+                durability_line = Some(tooltip_text.len());
+                tooltip_text.push((
+                    "Durability ".to_string() +
+                        &item.item_stack.attributes.get_durability_tooltip(),
+                    size.y - ((tooltip_text.len() + 1) as f32) * 10.0 - 24.0,
+                ));
+            }
             if let Some(level) = level {
                 tooltip_text.push((
                     "Level ".to_string() + &level.to_string(),
@@ -155,10 +234,10 @@ pub fn handle_spawn_inv_item_tooltip(
         }
 
         for (i, (text, d)) in tooltip_text.iter().enumerate() {
-            let text_pos = if i == 0 {
+            let text_pos = if i < name_lines.len() {
                 Vec3::new(
                     -f32::ceil(((text.chars().count() * 6 - 1) as f32) / 2.0) + 0.5,
-                    size.y / 2.0 - 12.0,
+                    size.y / 2.0 - 12.0 - (i as f32) * 10.0,
                     1.0
                 )
             } else {
@@ -177,11 +256,19 @@ pub fn handle_spawn_inv_item_tooltip(
                         text: Text::from_section(text, TextStyle {
                             font: asset_server.load("fonts/Kitchen Sink.ttf"),
                             font_size: 8.0,
-                            color: if i == 0 {
+                            color: if i < name_lines.len() {
+                                item.item_stack.rarity.get_color()
+                            } else if should_show_attributes && affix_line_range.contains(&i) {
                                 item.item_stack.rarity.get_color()
+                            } else if should_show_attributes && socket_line_range.contains(&i) {
+                                GREY
+                            } else if durability_line == Some(i) && is_broken {
+                                RED
+                            } else if durability_line == Some(i) {
+                                GREY
                             } else if i > 1 && i == tooltip_text.len() - 1 {
                                 GREY
-                            } else if i > 2 && should_show_attributes {
+                            } else if i > first_attribute_line + 1 && should_show_attributes {
                                 GOLD
                             } else {
                                 GREY
@@ -200,7 +287,7 @@ pub fn handle_spawn_inv_item_tooltip(
                 ))
                 .id();
 
-            if item.is_recipe && i > 0 {
+            if item.is_recipe && i >= name_lines.len() {
                 let ingredient_world_obj: Vec<WorldObject> = recipes.crafting_list
                     .get(&item.item_stack.obj_type)
                     .unwrap()
@@ -211,7 +298,7 @@ pub fn handle_spawn_inv_item_tooltip(
                     &mut commands,
                     &graphics,
                     &(ItemStack {
-                        obj_type: ingredient_world_obj[i - 1],
+                        obj_type: ingredient_world_obj[i - name_lines.len()],
                         count: 1,
                         ..Default::default()
                     }),
@@ -229,10 +316,11 @@ pub fn handle_spawn_inv_item_tooltip(
             commands.entity(tooltip).add_child(text);
         }
         if let Some(ability) = &item.item_stack.metadata.item_ability {
-            let (obj, dmg) = match ability {
-                ItemAbility::Arc(dmg) => (WorldObject::Feather, dmg),
-                ItemAbility::FireAttack(dmg) => (WorldObject::Fireball, dmg),
-            };
+            let obj = ability_defs.0
+                .get(ability.get_name())
+                .map(|def| def.icon)
+                .unwrap_or(WorldObject::MagicGem);
+            let dmg = ability.get_value();
             let pos = Vec3::new(28.0, -size.y / 2.0 + 12.0, 1.0);
             let icon_e = spawn_item_stack_icon(
                 &mut commands,
@@ -305,6 +393,7 @@ pub fn handle_spawn_inv_player_stats(
         ),
         With<Player>
     >,
+    set_bonuses: Query<&ActiveSetBonuses, With<Player>>,
     inv: Query<Entity, With<InventoryUI>>,
     stats: Query<Entity, With<StatsUI>>,
     tooltip_manager: Res<TooltipsManager>,
@@ -362,6 +451,18 @@ pub fn handle_spawn_inv_player_stats(
             loot_rate: loot_rate_bonus.0,
             ..default()
         }).get_stats_summary();
+        let active_sets = set_bonuses
+            .get_single()
+            .map(|bonuses| bonuses.sets.clone())
+            .unwrap_or_default();
+        // each set bonus needs a line under the stats
+        let size = Vec2::new(
+            TOOLTIP_UI_SIZE.x,
+            f32::max(
+                TOOLTIP_UI_SIZE.y,
+                ((attributes.len() + active_sets.len()) as f32) * 8.0 + 24.0
+            )
+        );
 
         let tooltip = commands
             .spawn((
@@ -373,7 +474,7 @@ pub fn handle_spawn_inv_player_stats(
                         ..Default::default()
                     },
                     sprite: Sprite {
-                        custom_size: Some(TOOLTIP_UI_SIZE),
+                        custom_size: Some(size),
                         ..Default::default()
                     },
                     ..Default::default()
@@ -390,18 +491,22 @@ pub fn handle_spawn_inv_player_stats(
         for (_i, a) in attributes.iter().enumerate().clone() {
             tooltip_text.push(((a.0.clone(), a.1.clone()), 0.0));
         }
+        for set in active_sets.iter() {
+            let set_progress = format!("{}/{}", set.equipped, set.total);
+            tooltip_text.push(((format!("{} Set ", set.name), set_progress), 0.0));
+        }
 
         for (i, (text, d)) in tooltip_text.iter().enumerate() {
             let text_pos = if i == 0 {
                 Vec3::new(
                     -f32::ceil(((text.0.chars().count() * 6 - 1) as f32) / 2.0) + 0.5,
-                    TOOLTIP_UI_SIZE.y / 2.0 - 12.0,
+                    size.y / 2.0 - 12.0,
                     1.0
                 )
             } else {
                 Vec3::new(
-                    -TOOLTIP_UI_SIZE.x / 2.0 + 8.0,
-                    TOOLTIP_UI_SIZE.y / 2.0 - 12.0 - (i as f32) * 8.0 - d - 2.0,
+                    -size.x / 2.0 + 8.0,
+                    size.y / 2.0 - 12.0 - (i as f32) * 8.0 - d - 2.0,
                     1.0
                 )
             };
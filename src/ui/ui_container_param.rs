@@ -2,15 +2,20 @@ use std::marker::PhantomData;
 
 use bevy::{
     ecs::system::SystemParam,
-    prelude::{EventWriter, Res, ResMut, State},
+    prelude::{EventWriter, Res, ResMut, State, Vec2},
 };
 
 use crate::{
     container::Container,
-    item::{CraftedItemEvent, CraftingTracker, Recipes},
+    item::{
+        durability::RepairItemEvent, CraftRequestEvent, CraftedItemEvent, CraftingTracker, Recipes,
+    },
 };
 
-use super::{crafting_ui::CraftingContainer, ChestContainer, FurnaceContainer, UIState};
+use super::{
+    crafting_ui::{CraftingContainer, CraftingContainerType},
+    ChestContainer, FurnaceContainer, UIState,
+};
 
 #[derive(SystemParam)]
 pub struct UIContainersParam<'w, 's> {
@@ -19,6 +24,8 @@ pub struct UIContainersParam<'w, 's> {
     pub crafting_option: Option<ResMut<'w, CraftingContainer>>,
 
     pub crafted_event: EventWriter<'w, CraftedItemEvent>,
+    pub craft_request_event: EventWriter<'w, CraftRequestEvent>,
+    pub repair_item_event: EventWriter<'w, RepairItemEvent>,
     pub crafting_tracker: Res<'w, CraftingTracker>,
     pub recipes: Res<'w, Recipes>,
     pub ui_state: Res<'w, State<UIState>>,
@@ -36,6 +43,17 @@ impl<'w, 's> UIContainersParam<'w, 's> {
             _ => None,
         }
     }
+    /// Station the open crafting UI belongs to, `None` when crafting from the inventory
+    pub fn get_crafting_station_pos(&self) -> Option<Vec2> {
+        self.crafting_option.as_ref().and_then(|c| c.station_pos)
+    }
+    pub fn is_anvil_open(&self) -> bool {
+        self.ui_state.0 == UIState::Crafting
+            && self
+                .crafting_option
+                .as_ref()
+                .map_or(false, |c| c.station == CraftingContainerType::Anvil)
+    }
     pub fn get_active_ui_container_mut(&mut self) -> Option<&mut Container> {
         match self.ui_state.0 {
             UIState::Chest => self.chest_option.as_mut().map(|c| &mut c.items),
@@ -1,8 +1,12 @@
-use crate::{inputs::CursorPos, world, Game};
-use bevy::prelude::*;
+use crate::{
+    colors::{_WHITE, DARK_BROWN},
+    inputs::CursorPos,
+    world, Game,
+};
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
 use bevy_ecs_tilemap::tiles::TilePos;
 
-use super::Interactable;
+use super::{Interactable, ScreenOverlay};
 
 pub fn pointcast_2d<'a>(
     cursor_pos: &Res<CursorPos>,
@@ -45,3 +49,117 @@ pub fn _get_player_chunk_tile_coords(game: &mut Game) -> (IVec2, TilePos) {
         world::world_helpers::camera_pos_to_tile_pos(&Vec2::new(player_pos.x, player_pos.y));
     (chunk_pos, tile_pos)
 }
+
+/// Spawns an opaque menu panel with a border, over a tint covering the rest of the screen.
+/// Returns the panel, for the caller to add its contents to.
+pub fn spawn_menu_panel(commands: &mut Commands, size: Vec2, z: f32) -> Entity {
+    let overlay = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(146. / 255., 116. / 255., 65. / 255., 0.3),
+                ..default()
+            },
+            transform: Transform::from_translation(Vec3::new(0., 0., -2.)),
+            ..default()
+        })
+        .insert(RenderLayers::from_layers(&[3]))
+        .insert(ScreenOverlay::new(10.))
+        .id();
+    let border = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: DARK_BROWN,
+                custom_size: Some(size + Vec2::new(4., 4.)),
+                ..default()
+            },
+            transform: Transform::from_translation(Vec3::new(0., 0., -1.)),
+            ..default()
+        })
+        .insert(RenderLayers::from_layers(&[3]))
+        .id();
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: _WHITE,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_translation(Vec3::new(0., 0., z)),
+            ..default()
+        })
+        .insert(RenderLayers::from_layers(&[3]))
+        .push_children(&[overlay, border])
+        .id()
+}
+
+pub fn spawn_menu_text(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    text: String,
+    color: Color,
+    translation: Vec3,
+) -> Entity {
+    commands
+        .spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    text,
+                    TextStyle {
+                        font: asset_server.load("fonts/Kitchen Sink.ttf"),
+                        font_size: 8.0,
+                        color,
+                    },
+                ),
+                text_anchor: Anchor::CenterLeft,
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            RenderLayers::from_layers(&[3]),
+        ))
+        .id()
+}
+
+/// A flat coloured button, found with `get_menu_button_at_cursor` rather than being
+/// an `Interactable`, so menus opened over other screens don't share their hit tests
+pub fn spawn_menu_button(
+    commands: &mut Commands,
+    size: Vec2,
+    translation: Vec3,
+    color: Color,
+    button: impl Component,
+    labels: Vec<Entity>,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            button,
+            RenderLayers::from_layers(&[3]),
+            Name::new("MENU BUTTON"),
+        ))
+        .push_children(&labels)
+        .id()
+}
+
+pub fn get_menu_button_at_cursor<'a, B: Component>(
+    cursor_pos: &CursorPos,
+    buttons: &'a Query<(&Sprite, &GlobalTransform, &B)>,
+) -> Option<&'a B> {
+    buttons
+        .iter()
+        .find(|(sprite, t, _)| {
+            let Some(size) = sprite.custom_size else {
+                return false;
+            };
+            let offset = (cursor_pos.ui_coords.truncate() - t.translation().truncate()).abs();
+            offset.x <= size.x / 2. && offset.y <= size.y / 2.
+        })
+        .map(|(_, _, button)| button)
+}
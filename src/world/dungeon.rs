@@ -1,11 +1,12 @@
-use bevy::{prelude::*, render::view::RenderLayers};
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
 use bevy_proto::prelude::ProtoCommands;
 
 use crate::{
     player::MovePlayerEvent,
     proto::proto_param::ProtoParam,
+    ui::ScreenAnchor,
     world::dimension::{Dimension, SpawnDimension},
-    GameParam, GAME_HEIGHT,
+    GameParam,
 };
 
 use super::{
@@ -14,13 +15,28 @@ use super::{
         add_dungeon_chests, add_dungeon_exit_block, gen_new_dungeon, get_player_spawn_tile, Bias,
     },
     world_helpers::world_pos_to_tile_pos,
-    TileMapPosition, CHUNK_SIZE,
+    TileMapPosition, CHUNK_SIZE, TILE_SIZE,
 };
 
 #[derive(Component)]
 pub struct Dungeon {
     pub grid: Vec<Vec<i8>>,
 }
+impl Dungeon {
+    /// The area of the world the grid covers. Grid (x, y) is tile
+    /// (x - 3 * CHUNK_SIZE, 4 * CHUNK_SIZE - 1 - y), the same as `get_player_spawn_tile`.
+    pub fn get_world_bounds(&self) -> Rect {
+        let grid_size = self.grid.len() as f32;
+        let chunk_size = CHUNK_SIZE as f32;
+        let min_tile = Vec2::new(-3. * chunk_size, 4. * chunk_size - grid_size);
+        let max_tile = Vec2::new(grid_size - 3. * chunk_size - 1., 4. * chunk_size - 1.);
+        // tiles are centred on their position
+        Rect::from_corners(
+            (min_tile - 0.5) * TILE_SIZE.x,
+            (max_tile + 0.5) * TILE_SIZE.x,
+        )
+    }
+}
 pub struct DungeonPlugin;
 impl Plugin for DungeonPlugin {
     fn build(&self, app: &mut App) {
@@ -142,7 +158,7 @@ pub fn spawn_dungeon_text(
                 )
                 .with_alignment(TextAlignment::Center),
                 transform: Transform {
-                    translation: Vec3::new(0., GAME_HEIGHT / 2. - 12., 1.),
+                    translation: Vec3::new(0., 0., 1.),
                     scale: Vec3::new(1., 1., 1.),
                     ..Default::default()
                 },
@@ -150,6 +166,7 @@ pub fn spawn_dungeon_text(
             },
             Name::new("FPS TEXT"),
             DungeonText,
+            ScreenAnchor::new(Anchor::TopCenter, Vec2::new(0., -12.)),
             RenderLayers::from_layers(&[3]),
         ));
     }
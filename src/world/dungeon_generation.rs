@@ -6,6 +6,7 @@ use rand::seq::IteratorRandom;
 use rand::Rng;
 
 use crate::{
+    camera::{CameraFocusEvent, DUNGEON_EXIT_FOCUS_SECS},
     item::{PlaceItemEvent, WorldObject},
     world::world_helpers::{tile_pos_to_world_pos, world_pos_to_tile_pos},
 };
@@ -164,6 +165,7 @@ pub fn gen_new_dungeon(steps: i32, grid_size: usize, bias: Bias) -> Vec<Vec<i8>>
 pub fn add_dungeon_chests(
     new_dungeon: Query<&Dungeon, Added<ActiveDimension>>,
     mut place_item_event: EventWriter<PlaceItemEvent>,
+    mut focus_event: EventWriter<CameraFocusEvent>,
 ) {
     let Ok(dungeon) = new_dungeon.get_single() else {
         return;
@@ -263,5 +265,10 @@ pub fn add_dungeon_exit_block(
             placed_by_player: false,
             override_existing_obj: false,
         });
+        // shows the player where they're headed before handing the camera back
+        focus_event.send(CameraFocusEvent {
+            pos: tile_pos_to_world_pos(*pos, false),
+            secs: DUNGEON_EXIT_FOCUS_SECS,
+        });
     }
 }